@@ -1,12 +1,11 @@
 use crossterm::event::KeyModifiers;
-use dlg::parser::Menu;
-use dlg::player::Animation;
+use dlg::manifest::Manifest;
+use dlg::parser::{Fragment, Style as TextStyle, TextEffect};
+use dlg::player::{AliasResolver, DialogRunner, SpeakerDisplay, SpeakerResolver, Step};
 use dlg::prelude::*;
-use dlg::{parser::State, player::DialogState};
 use io::Error as IoError;
 use unicode_segmentation::UnicodeSegmentation;
 
-use core::panic;
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
     execute,
@@ -29,9 +28,8 @@ use tui::{
 
 #[derive(Debug)]
 enum InputMode {
-    NextPhrase,
-    NextLine,
-    Menu { selection: usize, menu: Menu },
+    Next,
+    Menu { selection: usize },
 }
 
 #[derive(Debug)]
@@ -43,13 +41,32 @@ enum ViewMode {
 
 /// App holds the state of the application
 #[derive(Debug)]
-struct App {
+struct App<'a> {
     view_mode: ViewMode,
-    dialog: Dialog,
-    state: DialogState,
+    runner: DialogRunner<'a>,
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
+    let mut file = None;
+    let mut manifest = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--manifest" => {
+                let path = args.next().ok_or("`--manifest` needs a value")?;
+                manifest = Some(load_manifest(Path::new(&path))?);
+            }
+            _ if file.is_none() => file = Some(arg),
+            _ => return Err(format!("unexpected argument `{}`", arg).into()),
+        }
+    }
+
+    // load dialog before the terminal setup so errors are printed to the normal screen
+    let dialog = match file {
+        Some(file) => Some(load_dialog(Path::new(&file))?),
+        None => None,
+    };
+
     // setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -57,34 +74,22 @@ fn main() -> Result<(), Box<dyn Error>> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let args: Vec<String> = std::env::args().collect();
-
-    let path = args.get(1);
-
-    let dialog = if let Some(file) = path {
-        let file = PathBuf::from(file);
-        if file.exists() {
-            let raw = fs::read_to_string(file).expect("error in reading file");
-            let raw = format!("\n{}\n", raw); // FIXME: improve parsing of mentions in start of file
-            Dialog::from_str(&raw).expect("can't load dialog from file")
-        } else {
-            panic!("file is not exists");
-        }
-    } else {
-        match run_select_file_menu(&mut terminal) {
-            Ok(dialog) => dialog,
-            Err(e) => panic!("{:?}", e),
+    let res = match dialog {
+        Some(dialog) => Ok(dialog),
+        None => run_select_file_menu(&mut terminal),
+    }
+    .and_then(|dialog| {
+        // create app and run it
+        let mut app = App {
+            view_mode: ViewMode::NextAnimationFrame,
+            runner: DialogRunner::new(&dialog),
+        };
+        if let Some(manifest) = manifest {
+            app.runner.set_speaker_resolver(manifest);
         }
-    };
 
-    // create app and run it
-    let app = App {
-        view_mode: ViewMode::NextAnimationFrame,
-        dialog,
-        state: DialogState::default(),
-    };
-
-    let res = run_dialog(&mut terminal, app);
+        run_dialog(&mut terminal, app)
+    });
 
     // restore terminal
     disable_raw_mode()?;
@@ -102,6 +107,20 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+fn load_dialog(path: &Path) -> io::Result<Dialog> {
+    let raw = fs::read_to_string(path)?;
+
+    Dialog::from_str(&raw)
+        .map_err(|e| IoError::new(ErrorKind::InvalidData, format!("{}:{}", path.display(), e)))
+}
+
+fn load_manifest(path: &Path) -> io::Result<Manifest> {
+    let raw = fs::read_to_string(path)?;
+
+    Manifest::from_str(&raw)
+        .map_err(|e| IoError::new(ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+}
+
 fn find_all_dialog_files<P: AsRef<Path>>(path: P) -> Vec<PathBuf> {
     let mut files = vec![];
 
@@ -136,26 +155,16 @@ fn run_select_file_menu<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<Di
 
         if let Event::Key(key) = event::read()? {
             if let KeyCode::Esc = key.code {
-                return Err(IoError::new(ErrorKind::Other, "exited from program!"));
+                return Err(IoError::other("exited from program!"));
             } else if key.modifiers.contains(KeyModifiers::CONTROL) {
                 if let KeyCode::Char('c') = key.code {
-                    return Err(IoError::new(ErrorKind::Other, "exited from program!"));
+                    return Err(IoError::other("exited from program!"));
                 }
             };
 
             if let KeyCode::Enter = key.code {
                 if let Some(path) = files.get(selection) {
-                    let raw = fs::read_to_string(path).expect("can't read the file");
-                    let raw = format!("\n{}\n", raw); // TODO: fix mention in beginning of file
-
-                    let dialog = Dialog::from_str(&raw);
-
-                    match dialog {
-                        Ok(dialog) => return Ok(dialog),
-                        Err(_) => {
-                            return Err(IoError::new(ErrorKind::Other, "сan't parse dialog!"))
-                        }
-                    }
+                    return load_dialog(path);
                 }
                 return Err(IoError::new(ErrorKind::NotFound, "file not found!"));
             }
@@ -251,8 +260,6 @@ fn run_dialog<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Resul
             .unwrap_or_else(|| Duration::from_secs(0));
         if crossterm::event::poll(timeout)? {
             if let Event::Key(key) = event::read()? {
-                let cursor = &mut app.state.cursor;
-
                 if let KeyCode::Esc = key.code {
                     return Ok(());
                 } else if key.modifiers.contains(KeyModifiers::CONTROL) {
@@ -262,63 +269,44 @@ fn run_dialog<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Resul
                 };
 
                 match &mut app.view_mode {
-                    ViewMode::Input(input) => {
-                        match input {
-                            InputMode::NextLine => {
-                                if let KeyCode::Enter = key.code {
-                                    cursor.next_line_index();
-                                    app.view_mode = ViewMode::NextAnimationFrame;
-                                    app.state.animation = Animation::default();
-                                };
-                            }
-                            InputMode::NextPhrase => {
-                                if let KeyCode::Enter = key.code {
-                                    cursor.next_phrase_index();
+                    ViewMode::Input(input) => match input {
+                        InputMode::Next => {
+                            if let KeyCode::Enter = key.code {
+                                app.runner.advance();
+                                app.view_mode = ViewMode::NextAnimationFrame;
+                            };
+                        }
+                        InputMode::Menu { selection } => {
+                            if let KeyCode::Enter = key.code {
+                                if app.runner.choose(*selection) {
                                     app.view_mode = ViewMode::NextAnimationFrame;
-                                    app.state.animation = Animation::default();
-                                };
-                            }
-                            InputMode::Menu { selection, menu } => {
-                                if let KeyCode::Enter = key.code {
-                                    let opt = menu.options.get(*selection);
-
-                                    if let Some(opt) = opt {
-                                        let section = &opt.args[1..]; // FIXME: remove hash from link
-                                        let _ = &app.state.cursor.set_section(section.to_string());
-                                        app.view_mode = ViewMode::NextAnimationFrame;
-                                        app.state.animation = Animation::default();
-                                    } else {
-                                        panic!(
-                                            "Out of bounds: index `{}`, len `{}`",
-                                            *selection,
-                                            menu.options.len()
-                                        )
-                                    }
-                                    continue;
                                 }
+                                continue;
+                            }
 
-                                let raw_selection = match key.code {
-                                    KeyCode::Up => *selection as isize - 1isize,
-                                    KeyCode::Down => *selection as isize + 1isize,
-                                    _ => *selection as isize,
-                                };
-
-                                let len = menu.options.len();
-                                let rem = raw_selection % len as isize;
-                                let new_selection =
-                                    (rem + (len * usize::from(rem < 0)) as isize) as usize;
-
-                                *input = InputMode::Menu {
-                                    selection: new_selection,
-                                    menu: menu.clone(),
-                                };
+                            let len = match app.runner.current() {
+                                Step::Menu(menu) => menu.options.len(),
+                                _ => continue,
+                            };
+                            if len == 0 {
+                                continue;
                             }
+
+                            let raw_selection = match key.code {
+                                KeyCode::Up => *selection as isize - 1isize,
+                                KeyCode::Down => *selection as isize + 1isize,
+                                _ => *selection as isize,
+                            };
+
+                            let rem = raw_selection % len as isize;
+                            *selection = (rem + (len * usize::from(rem < 0)) as isize) as usize;
                         }
-                    }
+                    },
                     ViewMode::End => return Ok(()),
                     ViewMode::NextAnimationFrame => {
                         if let KeyCode::Enter = key.code {
-                            app.state.animation.current = app.state.animation.target;
+                            let animation = &mut app.runner.state_mut().animation;
+                            animation.current = animation.target;
                             // end the animation
                         };
                     }
@@ -329,8 +317,9 @@ fn run_dialog<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Resul
         if last_tick.elapsed() >= tick_rate {
             last_tick = Instant::now();
             if let ViewMode::NextAnimationFrame = &mut app.view_mode {
-                if app.state.animation.current < app.state.animation.target {
-                    app.state.animation.current += 1;
+                if let Step::Phrase { fragments, .. } = app.runner.current() {
+                    let tick_ms = tick_rate.as_millis() as u32;
+                    app.runner.state_mut().animation.tick(&fragments, tick_ms);
                 }
             }
         }
@@ -338,7 +327,7 @@ fn run_dialog<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Resul
 }
 
 fn dialog_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) -> ViewMode {
-    let cursor = &app.state.cursor;
+    let step = app.runner.current();
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -355,7 +344,7 @@ fn dialog_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) -> ViewMode {
 
     let msg = match &app.view_mode {
         ViewMode::Input(input) => match input {
-            InputMode::NextPhrase | InputMode::NextLine => {
+            InputMode::Next => {
                 vec![
                     Span::raw("Press "),
                     Span::styled(
@@ -399,59 +388,55 @@ fn dialog_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) -> ViewMode {
 
     let input = Paragraph::new(format!(
         "cursor: {}, animation: {}",
-        &app.state.cursor, &app.state.animation
+        &app.runner.state().cursor,
+        &app.runner.state().animation
     ))
     .style(Style::default().fg(Color::Yellow))
     .block(Block::default().borders(Borders::ALL).title("State"));
     f.render_widget(input, chunks[1]);
 
-    // TODO: Provide line from outside
-    let line = match app.dialog.get_line_by_cursor(cursor) {
-        Some(line) => line,
-        None => return ViewMode::End,
-    };
-
-    match line {
-        Line::Phrase { speaker, lines } => {
-            let name = match speaker {
-                Speaker::Narrator => String::from("Narrator"),
-                Speaker::Character(alias, state) => match state {
-                    State::Default => alias.0.to_string(),
-                    State::Named(state) => format!("{}: {}", alias.0, state),
-                },
+    match step {
+        Step::Phrase {
+            speaker,
+            text,
+            fragments,
+            ..
+        } => {
+            // raw speaker is shown too, so states can be checked with a manifest
+            let display = app.runner.speaker_display(&speaker);
+            let raw = AliasResolver.resolve(&speaker).name;
+            let name = if display.name == raw {
+                raw
+            } else {
+                format!("{} ({})", display.name, raw)
             };
+            let name = Span::styled(name, speaker_style(&display));
 
-            if let Some(line) = lines.get(cursor.phrase_index()) {
-                let letters = line.graphemes(true).collect::<Vec<_>>();
-                app.state.animation.target = letters.len();
-                let line_part = &letters[0..app.state.animation.current];
-
-                let messages = Paragraph::new(line_part.join(""))
-                    .wrap(Wrap { trim: true })
-                    .block(Block::default().borders(Borders::ALL).title(name));
-                f.render_widget(messages, chunks[2]);
-
-                if app.state.animation.current < app.state.animation.target {
-                    ViewMode::NextAnimationFrame
-                } else if cursor.phrase_index() == lines.len() - 1 {
-                    ViewMode::Input(InputMode::NextLine)
-                } else {
-                    ViewMode::Input(InputMode::NextPhrase)
-                }
+            let animation = &mut app.runner.state_mut().animation;
+            animation.target = text.graphemes(true).count();
+            let line_part = styled_spans(&fragments, animation.current);
+
+            let messages = Paragraph::new(line_part)
+                .wrap(Wrap { trim: true })
+                .block(Block::default().borders(Borders::ALL).title(name));
+            f.render_widget(messages, chunks[2]);
+
+            if animation.current < animation.target {
+                ViewMode::NextAnimationFrame
             } else {
-                panic!("no line");
+                ViewMode::Input(InputMode::Next)
             }
         }
-        Line::Menu(menu) => {
+        Step::Menu(menu) => {
             let current_selection = match &app.view_mode {
-                ViewMode::Input(InputMode::Menu { selection, .. }) => *selection,
+                ViewMode::Input(InputMode::Menu { selection }) => *selection,
                 _ => 0,
             };
 
             let mut items = menu
                 .options
                 .iter()
-                .map(|o| Span::raw(o.title.clone().unwrap()))
+                .map(|o| Span::raw(o.title.clone().unwrap_or_default()))
                 .collect::<Vec<_>>();
 
             if let Some(item) = items.get_mut(current_selection) {
@@ -462,14 +447,96 @@ fn dialog_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) -> ViewMode {
                 .block(
                     Block::default()
                         .borders(Borders::ALL)
-                        .title(menu.title.clone().unwrap()),
+                        .title(menu.title.clone().unwrap_or_default()),
                 );
             f.render_widget(messages, chunks[2]);
 
             ViewMode::Input(InputMode::Menu {
                 selection: current_selection,
-                menu: menu.clone(),
             })
         }
+        Step::Event { name, args } => {
+            // stop on every event so it can be inspected
+            let args = args.iter().map(ToString::to_string).collect::<Vec<_>>();
+            let event = Paragraph::new(format!("{} {}", name, args.join(" ")))
+                .style(Style::default().fg(Color::Yellow))
+                .block(Block::default().borders(Borders::ALL).title("Event"));
+            f.render_widget(event, chunks[2]);
+
+            ViewMode::Input(InputMode::Next)
+        }
+        Step::End => ViewMode::End,
+    }
+}
+
+/// Converts the first `count` graphemes of the phrase into styled spans of terminal
+fn styled_spans(fragments: &[Fragment], count: usize) -> Spans<'static> {
+    let mut spans = vec![];
+    let mut left = count;
+
+    for fragment in fragments {
+        let (text, style) = match fragment {
+            Fragment::Text(text, style) => (text, style),
+            Fragment::Wait(_) => continue,
+        };
+        if left == 0 {
+            break;
+        }
+
+        let letters = text.graphemes(true).take(left).collect::<String>();
+        left -= letters.graphemes(true).count();
+        spans.push(Span::styled(letters, terminal_style(style)));
+    }
+
+    Spans::from(spans)
+}
+
+/// Speaker name is shown in the color from the manifest
+fn speaker_style(display: &SpeakerDisplay) -> Style {
+    match display.color {
+        Some(color) => Style::default().fg(Color::Rgb(color.r, color.g, color.b)),
+        None => Style::default(),
+    }
+}
+
+fn terminal_style(style: &TextStyle) -> Style {
+    let mut result = Style::default();
+    if style.bold {
+        result = result.add_modifier(Modifier::BOLD);
+    }
+    if style.italic {
+        result = result.add_modifier(Modifier::ITALIC);
+    }
+    // terminal can't move letters, so effects are shown with blinking
+    match style.effect {
+        Some(TextEffect::Shake) => result = result.add_modifier(Modifier::RAPID_BLINK),
+        Some(TextEffect::Wave) => result = result.add_modifier(Modifier::SLOW_BLINK),
+        None => {}
+    }
+    if let Some(color) = style.color.as_deref().and_then(terminal_color) {
+        result = result.fg(color);
+    }
+    result
+}
+
+fn terminal_color(color: &str) -> Option<Color> {
+    if let Some(hex) = color.strip_prefix('#') {
+        let rgb = u32::from_str_radix(hex, 16)
+            .ok()
+            .filter(|_| hex.len() == 6)?;
+        return Some(Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8));
+    }
+
+    match color {
+        "black" => Some(Color::Black),
+        "red" => Some(Color::Red),
+        "green" => Some(Color::Green),
+        "yellow" => Some(Color::Yellow),
+        "blue" => Some(Color::Blue),
+        "magenta" => Some(Color::Magenta),
+        "cyan" => Some(Color::Cyan),
+        "gray" => Some(Color::Gray),
+        "white" => Some(Color::White),
+        _ => None,
     }
 }
@@ -0,0 +1,98 @@
+use dlg::export::graph::Graph;
+use dlg::localization::to_pot;
+use dlg::prelude::*;
+use dlg::project::{DialogProject, DirectorySource};
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: dlg-export <command> [options] <file>
+
+Commands:
+    graph [--format dot|mermaid]    prints sections and transitions between them, in DOT by default
+    pot                             prints gettext template with phrases, menu titles and option titles
+
+Files included with `:include` are loaded too.
+Exits with code 1 if the file can't be read or parsed and with code 2 if arguments are wrong";
+
+#[derive(Debug, PartialEq, Eq)]
+enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.is_empty() || args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let result = match args[0].as_str() {
+        "graph" => parse_graph_args(&args[1..]).map(|(format, file)| graph(&file, format)),
+        "pot" => match &args[1..] {
+            [flag] if flag.starts_with('-') => Err(format!("unknown flag `{}`", flag)),
+            [file] => Ok(load(Path::new(file)).map(|dialog| to_pot(&dialog))),
+            [] => Err(String::from("no file given")),
+            [_, extra, ..] => Err(format!("unexpected argument `{}`", extra)),
+        },
+        command => Err(format!("unknown command `{}`", command)),
+    };
+
+    match result {
+        Ok(Ok(output)) => {
+            print!("{}", output);
+            ExitCode::SUCCESS
+        }
+        Ok(Err(e)) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn parse_graph_args(args: &[String]) -> Result<(GraphFormat, PathBuf), String> {
+    let mut format = GraphFormat::Dot;
+    let mut file = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = match args.next().map(String::as_str) {
+                    Some("dot") => GraphFormat::Dot,
+                    Some("mermaid") => GraphFormat::Mermaid,
+                    Some(other) => return Err(format!("unknown format `{}`", other)),
+                    None => return Err(String::from("`--format` needs a value")),
+                }
+            }
+            flag if flag.starts_with('-') => return Err(format!("unknown flag `{}`", flag)),
+            path if file.is_none() => file = Some(PathBuf::from(path)),
+            path => return Err(format!("unexpected argument `{}`", path)),
+        }
+    }
+
+    let file = file.ok_or_else(|| String::from("no file given"))?;
+    Ok((format, file))
+}
+
+/// Loads the file with all files it includes
+fn load(file: &Path) -> Result<Dialog, String> {
+    DialogProject::load(&DirectorySource::new("."), file)
+        .map(|project| project.dialog)
+        .map_err(|e| e.to_string())
+}
+
+fn graph(file: &Path, format: GraphFormat) -> Result<String, String> {
+    let graph = Graph::new(&load(file)?);
+
+    Ok(match format {
+        GraphFormat::Dot => graph.to_dot(),
+        GraphFormat::Mermaid => graph.to_mermaid(),
+    })
+}
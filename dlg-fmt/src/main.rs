@@ -0,0 +1,107 @@
+use dlg::prelude::*;
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: dlg-fmt [--check] [path...]
+
+Rewrites every .dlg file in the given files and directories in canonical form, the current directory by default.
+With --check files are not changed and the ones that are not formatted are listed.
+Exits with code 1 if a file can't be parsed or, with --check, is not formatted and with code 2 if arguments are wrong";
+
+fn main() -> ExitCode {
+    let mut check = false;
+    let mut paths = vec![];
+
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            "--check" => check = true,
+            flag if flag.starts_with('-') => {
+                eprintln!("unknown flag `{}`\n\n{}", flag, USAGE);
+                return ExitCode::from(2);
+            }
+            path => paths.push(PathBuf::from(path)),
+        }
+    }
+
+    if paths.is_empty() {
+        paths.push(PathBuf::from("."));
+    }
+
+    let mut files = vec![];
+    for path in &paths {
+        if path.is_dir() {
+            match find_all_dialog_files(path) {
+                Ok(mut found) => files.append(&mut found),
+                Err(e) => {
+                    eprintln!("error: can't read {}: {}", path.display(), e);
+                    return ExitCode::from(2);
+                }
+            }
+        } else {
+            files.push(path.clone());
+        }
+    }
+    files.sort();
+
+    let mut failed = false;
+    for file in &files {
+        match format_file(file, check) {
+            Ok(true) if check => {
+                println!("not formatted: {}", file.display());
+                failed = true;
+            }
+            Ok(true) => println!("formatted: {}", file.display()),
+            Ok(false) => {}
+            Err(e) => {
+                eprintln!("error: {}: {}", file.display(), e);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Formats the file and returns whether it has changed. With `check` the file is not written
+fn format_file(path: &Path, check: bool) -> Result<bool, Box<dyn std::error::Error>> {
+    let raw = fs::read_to_string(path)?;
+    let (dialog, notes) = Dialog::parse_with_notes(&raw)?;
+    let formatted = dialog.to_dlg_string_with_notes(&notes);
+
+    if formatted == raw {
+        return Ok(false);
+    }
+
+    if !check {
+        fs::write(path, formatted)?;
+    }
+
+    Ok(true)
+}
+
+fn find_all_dialog_files<P: AsRef<Path>>(path: P) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+
+    for res in fs::read_dir(path)? {
+        let entry = res?.path();
+
+        if entry.is_dir() {
+            files.append(&mut find_all_dialog_files(&entry)?);
+        } else if entry.extension().is_some_and(|ext| ext == "dlg") {
+            files.push(entry);
+        }
+    }
+
+    Ok(files)
+}
@@ -0,0 +1,427 @@
+use dlg::lint::{self, lint, Diagnostic, Lint, Severity};
+use dlg::manifest::Manifest;
+use dlg::parser::Span;
+use dlg::prelude::*;
+use dlg::project::{DialogProject, DirectorySource, ProjectError, ProjectErrorKind};
+use serde::Serialize;
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::str::FromStr;
+
+const USAGE: &str = "usage: dlg-lint [--format human|json] [--manifest characters.toml] [path...]
+
+Checks every .dlg file in the given files and directories, the current directory by default.
+With a manifest, characters and states of all files are compared with the declared ones.
+Exits with code 1 if any error is found and with code 2 if arguments are wrong";
+
+#[derive(Debug, PartialEq, Eq)]
+enum Format {
+    Human,
+    Json,
+}
+
+/// Diagnostic of a file in JSON output
+#[derive(Serialize)]
+struct Report<'a> {
+    file: String,
+    severity: Severity,
+    message: String,
+    line: usize,
+    column: usize,
+    start: usize,
+    end: usize,
+    #[serde(skip)]
+    source: Option<&'a str>,
+}
+
+fn main() -> ExitCode {
+    if std::env::args().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let Args {
+        format,
+        manifest,
+        paths,
+    } = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut files = vec![];
+    for path in &paths {
+        if path.is_dir() {
+            match find_all_dialog_files(path) {
+                Ok(mut found) => files.append(&mut found),
+                Err(e) => {
+                    eprintln!("error: can't read {}: {}", path.display(), e);
+                    return ExitCode::from(2);
+                }
+            }
+        } else {
+            files.push(path.clone());
+        }
+    }
+    files.sort();
+
+    let sources: Vec<_> = files.iter().map(fs::read_to_string).collect();
+    let checked = check_projects(&files, &sources);
+    let texts = checked
+        .diagnostics
+        .iter()
+        .map(|(path, _)| path)
+        .chain(checked.errors.iter().map(|error| &error.path))
+        .filter_map(|path| Some((path.clone(), fs::read_to_string(path).ok()?)))
+        .collect::<HashMap<_, _>>();
+
+    let mut reports = vec![];
+    for (path, diagnostic) in &checked.diagnostics {
+        if let Some(text) = texts.get(path) {
+            reports.push(Report::new(path, diagnostic, text));
+        }
+    }
+    for error in &checked.errors {
+        let message = error.kind.to_string();
+        reports.push(match (error.span, texts.get(&error.path)) {
+            (Some(span), Some(text)) => {
+                Report::at(&error.path, Severity::Error, message, span, text)
+            }
+            _ => Report::file(&error.path, Severity::Error, message),
+        });
+    }
+
+    let stems = dialog_names(&files);
+    for (file, source) in files.iter().zip(&sources) {
+        if checked.covered.contains(&key(file)) {
+            continue;
+        }
+        match source {
+            Ok(source) => reports.extend(
+                lint(source)
+                    .iter()
+                    .filter(|diagnostic| !links_other_file(diagnostic, source, &stems))
+                    .map(|diagnostic| Report::new(file, diagnostic, source)),
+            ),
+            Err(e) => reports.push(Report::file(
+                file,
+                Severity::Error,
+                format!("can't read file: {}", e),
+            )),
+        }
+    }
+
+    reports.sort_by(|a, b| a.file.cmp(&b.file));
+
+    if let Some(manifest) = &manifest {
+        reports.extend(check_manifest(manifest, &sources));
+    }
+
+    let errors = reports
+        .iter()
+        .filter(|report| report.severity == Severity::Error)
+        .count();
+    let warnings = reports.len() - errors;
+
+    match format {
+        Format::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&reports).expect("reports are serializable")
+        ),
+        Format::Human => {
+            for report in &reports {
+                println!("{}", report);
+            }
+            println!(
+                "checked {} files: {} errors, {} warnings",
+                files.len(),
+                errors,
+                warnings
+            );
+        }
+    }
+
+    if errors > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+struct Args {
+    format: Format,
+    manifest: Option<PathBuf>,
+    paths: Vec<PathBuf>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut format = Format::Human;
+    let mut manifest = None;
+    let mut paths = vec![];
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = match args.next().as_deref() {
+                    Some("human") => Format::Human,
+                    Some("json") => Format::Json,
+                    Some(other) => return Err(format!("unknown format `{}`", other)),
+                    None => return Err("`--format` needs a value".to_owned()),
+                }
+            }
+            "--manifest" => match args.next() {
+                Some(path) => manifest = Some(PathBuf::from(path)),
+                None => return Err("`--manifest` needs a value".to_owned()),
+            },
+            flag if flag.starts_with('-') => return Err(format!("unknown flag `{}`", flag)),
+            path => paths.push(PathBuf::from(path)),
+        }
+    }
+
+    if paths.is_empty() {
+        paths.push(PathBuf::from("."));
+    }
+
+    Ok(Args {
+        format,
+        manifest,
+        paths,
+    })
+}
+
+/// Diagnostics of the files checked as parts of projects
+struct Checked {
+    diagnostics: Vec<(PathBuf, Diagnostic)>,
+    /// Projects that can't be loaded
+    errors: Vec<ProjectError>,
+    /// Files of loaded projects, see [`key`]
+    covered: HashSet<PathBuf>,
+}
+
+/// Checks files with `:include` that no other file includes, together with the files they include.
+/// Links between files are resolved, so they are neither dangling nor make sections unreachable
+fn check_projects(files: &[PathBuf], sources: &[io::Result<String>]) -> Checked {
+    let source = DirectorySource::new(".");
+    let mut checked = Checked {
+        diagnostics: vec![],
+        errors: vec![],
+        covered: HashSet::new(),
+    };
+
+    let mut projects = vec![];
+    for (file, raw) in files.iter().zip(sources) {
+        let includes = raw
+            .as_ref()
+            .ok()
+            .and_then(|raw| Dialog::from_str(raw).ok())
+            .is_some_and(|dialog| !dialog.includes.is_empty());
+        if includes {
+            match DialogProject::load(&source, file) {
+                Ok(project) => projects.push(project),
+                // a file that doesn't parse is reported when it is checked alone
+                Err(error) if matches!(error.kind, ProjectErrorKind::Parse(_)) => {}
+                Err(error) => checked.errors.push(error),
+            }
+        }
+    }
+
+    let included = projects
+        .iter()
+        .flat_map(|project| &project.files[1..])
+        .map(key)
+        .collect::<HashSet<_>>();
+    for project in projects {
+        if included.contains(&key(&project.files[0])) {
+            continue;
+        }
+        checked
+            .diagnostics
+            .extend(lint::check_project(&project, &source));
+        checked.covered.extend(project.files.iter().map(key));
+    }
+
+    checked
+}
+
+/// Path that is the same for all ways to write it
+fn key(path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_owned())
+}
+
+/// Names of the checked files and the dialogs next to them, which unqualified links of included files may go to
+fn dialog_names(files: &[PathBuf]) -> HashSet<String> {
+    let directories = files
+        .iter()
+        .filter_map(|file| file.parent())
+        .collect::<HashSet<_>>();
+    let siblings = directories
+        .into_iter()
+        .filter_map(|directory| {
+            let directory = if directory.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                directory
+            };
+            fs::read_dir(directory).ok()
+        })
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "dlg"));
+
+    files
+        .iter()
+        .cloned()
+        .chain(siblings)
+        .filter_map(|file| Some(file.file_stem()?.to_string_lossy().into_owned()))
+        .collect()
+}
+
+/// Unqualified link to the initial section of another file, which is resolved only when
+/// the file is checked with the file that includes it
+fn links_other_file(diagnostic: &Diagnostic, source: &str, stems: &HashSet<String>) -> bool {
+    match &diagnostic.lint {
+        Lint::DanglingLink(Section::Named(name)) => {
+            stems.contains(name)
+                && Dialog::from_str(source).is_ok_and(|dialog| dialog.includes.is_empty())
+        }
+        _ => false,
+    }
+}
+
+/// Compares characters of all dialogs that parse with the manifest
+fn check_manifest(path: &Path, sources: &[io::Result<String>]) -> Vec<Report<'static>> {
+    let manifest = match fs::read_to_string(path) {
+        Ok(raw) => raw.parse::<Manifest>().map_err(|e| e.to_string()),
+        Err(e) => Err(format!("can't read file: {}", e)),
+    };
+    let manifest = match manifest {
+        Ok(manifest) => manifest,
+        Err(message) => return vec![Report::file(path, Severity::Error, message)],
+    };
+
+    let mut characters: HashMap<Alias, Requirements> = HashMap::new();
+    for dialog in sources
+        .iter()
+        .flatten()
+        .filter_map(|source| Dialog::from_str(source).ok())
+    {
+        for (alias, requirements) in dialog.characters {
+            let merged = characters.entry(alias).or_default();
+            for state in requirements.states {
+                if !merged.states.contains(&state) {
+                    merged.states.push(state);
+                }
+            }
+        }
+    }
+
+    manifest
+        .validate(&characters)
+        .iter()
+        .map(|issue| Report::file(path, issue.severity(), issue.to_string()))
+        .collect()
+}
+
+fn find_all_dialog_files<P: AsRef<Path>>(path: P) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+
+    for res in fs::read_dir(path)? {
+        let entry = res?.path();
+
+        if entry.is_dir() {
+            files.append(&mut find_all_dialog_files(&entry)?);
+        } else if entry.extension().is_some_and(|ext| ext == "dlg") {
+            files.push(entry);
+        }
+    }
+
+    Ok(files)
+}
+
+impl<'a> Report<'a> {
+    /// Report about the whole file
+    fn file(file: &Path, severity: Severity, message: String) -> Self {
+        Self {
+            file: file.display().to_string(),
+            severity,
+            message,
+            line: 0,
+            column: 0,
+            start: 0,
+            end: 0,
+            source: None,
+        }
+    }
+
+    fn new(file: &Path, diagnostic: &Diagnostic, source: &'a str) -> Self {
+        Self::at(
+            file,
+            diagnostic.severity(),
+            diagnostic.lint.to_string(),
+            diagnostic.span,
+            source,
+        )
+    }
+
+    /// Report about a place in the file
+    fn at(file: &Path, severity: Severity, message: String, span: Span, source: &'a str) -> Self {
+        Self {
+            file: file.display().to_string(),
+            severity,
+            message,
+            line: span.line,
+            column: span.column,
+            start: span.start,
+            end: span.end,
+            source: Some(source),
+        }
+    }
+}
+
+impl std::fmt::Display for Report<'_> {
+    /// Formats report like rustc does: message, location and the source line with the span underlined
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}: {}", self.severity, self.message)?;
+
+        let source = match self.source {
+            Some(source) => source,
+            None => return writeln!(f, " --> {}", self.file),
+        };
+
+        let line_start = source[..self.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[self.start..]
+            .find('\n')
+            .map_or(source.len(), |i| self.start + i);
+        let text = &source[line_start..line_end];
+        let underline = source[self.start..self.end.clamp(self.start, line_end)]
+            .chars()
+            .count()
+            .max(1);
+
+        let number = self.line.to_string();
+        let padding = " ".repeat(number.len());
+        writeln!(
+            f,
+            "{}--> {}:{}:{}",
+            padding, self.file, self.line, self.column
+        )?;
+        writeln!(f, "{} |", padding)?;
+        writeln!(f, "{} | {}", number, text.trim_end())?;
+        writeln!(
+            f,
+            "{} | {}{}",
+            padding,
+            " ".repeat(self.column - 1),
+            "^".repeat(underline)
+        )
+    }
+}
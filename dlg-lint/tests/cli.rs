@@ -0,0 +1,59 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Directory with a project: `story.dlg` includes `chapters/forest.dlg` and `chapters/cave.dlg`
+fn project(name: &str, forest: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("dlg-lint-{}-{}", name, std::process::id()));
+    fs::create_dir_all(directory.join("chapters")).unwrap();
+    fs::write(
+        directory.join("story.dlg"),
+        ":include \"chapters/forest.dlg\"\n:include \"chapters/cave.dlg\"\n@alice Hi\n:move #forest\n",
+    )
+    .unwrap();
+    fs::write(directory.join("chapters/forest.dlg"), forest).unwrap();
+    fs::write(
+        directory.join("chapters/cave.dlg"),
+        "Dark\n\n#deep\nDeeper\n",
+    )
+    .unwrap();
+    directory
+}
+
+fn run(directory: &Path, path: &str) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_dlg-lint"))
+        .arg(path)
+        .current_dir(directory)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_links_between_files() {
+    let directory = project("links", "Trees\n:move #cave\n:move #cave::deep\n");
+
+    let all = run(&directory, ".");
+    let alone = run(&directory, "chapters/forest.dlg");
+    fs::remove_dir_all(&directory).unwrap();
+
+    let stdout = String::from_utf8(all.stdout).unwrap();
+    assert!(all.status.success(), "{}", stdout);
+    assert!(stdout.contains("checked 3 files: 0 errors, 0 warnings"));
+
+    let stdout = String::from_utf8(alone.stdout).unwrap();
+    assert!(alone.status.success(), "{}", stdout);
+    assert!(stdout.contains("checked 1 files: 0 errors, 0 warnings"));
+}
+
+#[test]
+fn test_missing_section_of_other_file() {
+    let directory = project("missing", "Trees\n:move #cave::missing\n");
+
+    let output = run(&directory, ".");
+    fs::remove_dir_all(&directory).unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(Some(1), output.status.code());
+    assert!(stdout.contains("section #cave::missing is not declared"));
+    assert!(stdout.contains("chapters/forest.dlg:2:1"));
+}
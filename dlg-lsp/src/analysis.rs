@@ -0,0 +1,342 @@
+use dlg::lint::{lint, Severity};
+use dlg::parser::{SyntaxKind, SyntaxTree};
+use dlg::prelude::*;
+use lsp_types::{
+    CompletionItem, CompletionItemKind, Diagnostic, DiagnosticSeverity, Position, Range,
+};
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// What a piece of source refers to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SymbolKind {
+    /// `#section` that starts a section
+    SectionHeader(String),
+    /// `#section` in `:move` or `:opt(...)`
+    SectionLink(String),
+    /// Alias in `@alice:calm`
+    Character(String),
+    /// State in `@alice:calm`
+    State(String, String),
+}
+
+/// Symbol with its byte range in source
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub kind: SymbolKind,
+    pub range: std::ops::Range<usize>,
+}
+
+/// Opened `.dlg` file
+pub struct Document {
+    pub text: String,
+    line_starts: Vec<usize>,
+    symbols: Vec<Symbol>,
+    /// Characters of the last version of document that was parsed without errors
+    characters: HashMap<Alias, Requirements>,
+}
+
+impl Document {
+    pub fn new(text: String) -> Self {
+        let mut document = Self {
+            text: String::new(),
+            line_starts: vec![],
+            symbols: vec![],
+            characters: HashMap::new(),
+        };
+        document.update(text);
+        document
+    }
+
+    /// Replaces text of document. Characters are kept if the new text can't be parsed
+    pub fn update(&mut self, text: String) {
+        if let Ok(dialog) = Dialog::from_str(&text) {
+            self.characters = dialog.characters;
+        }
+
+        self.line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        self.symbols = symbols(&SyntaxTree::parse(&text));
+        self.text = text;
+    }
+
+    /// LSP position of byte offset. Columns are counted in UTF-16 code units
+    pub fn position(&self, offset: usize) -> Position {
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        };
+        let start = self.line_starts[line];
+        let character = self.text[start..offset].encode_utf16().count();
+
+        Position::new(line as u32, character as u32)
+    }
+
+    /// Byte offset of LSP position. Positions past the end of line point to its end
+    pub fn offset(&self, position: Position) -> usize {
+        let start = match self.line_starts.get(position.line as usize) {
+            Some(start) => *start,
+            None => return self.text.len(),
+        };
+        let line = self.text[start..].split('\n').next().unwrap_or_default();
+
+        let mut units = 0;
+        for (i, c) in line.char_indices() {
+            if units >= position.character as usize {
+                return start + i;
+            }
+            units += c.len_utf16();
+        }
+        start + line.len()
+    }
+
+    pub fn range(&self, range: &std::ops::Range<usize>) -> Range {
+        Range::new(self.position(range.start), self.position(range.end))
+    }
+
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        lint(&self.text)
+            .into_iter()
+            .map(|diagnostic| {
+                let severity = match diagnostic.severity() {
+                    Severity::Error => DiagnosticSeverity::ERROR,
+                    Severity::Warning => DiagnosticSeverity::WARNING,
+                };
+
+                Diagnostic {
+                    range: self.range(&diagnostic.span.range()),
+                    severity: Some(severity),
+                    source: Some("dlg".to_owned()),
+                    message: diagnostic.lint.to_string(),
+                    ..Diagnostic::default()
+                }
+            })
+            .collect()
+    }
+
+    /// Symbol under the cursor
+    pub fn symbol_at(&self, offset: usize) -> Option<&Symbol> {
+        self.symbols
+            .iter()
+            .find(|symbol| symbol.range.start <= offset && offset <= symbol.range.end)
+    }
+
+    /// Header of the section
+    pub fn definition(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.iter().find(
+            |symbol| matches!(&symbol.kind, SymbolKind::SectionHeader(header) if header == name),
+        )
+    }
+
+    /// Links to the same section or mentions of the same character as the symbol
+    pub fn references(&self, symbol: &Symbol, include_declaration: bool) -> Vec<&Symbol> {
+        self.symbols
+            .iter()
+            .filter(|other| match (&symbol.kind, &other.kind) {
+                (
+                    SymbolKind::SectionHeader(name) | SymbolKind::SectionLink(name),
+                    SymbolKind::SectionLink(other),
+                ) => name == other,
+                (
+                    SymbolKind::SectionHeader(name) | SymbolKind::SectionLink(name),
+                    SymbolKind::SectionHeader(other),
+                ) => include_declaration && name == other,
+                (
+                    SymbolKind::Character(alias) | SymbolKind::State(alias, _),
+                    SymbolKind::Character(other),
+                ) => alias == other,
+                _ => false,
+            })
+            .collect()
+    }
+
+    /// Section names after `#`, aliases after `@` and states after `@alias:`
+    pub fn completion(&self, offset: usize) -> Vec<CompletionItem> {
+        let line_start = self.text[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let before = &self.text[line_start..offset];
+        let word_start = before
+            .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .map_or(0, |i| i + 1);
+        let prefix = &before[..word_start];
+
+        let item = |label: String, kind| CompletionItem {
+            label,
+            kind: Some(kind),
+            ..CompletionItem::default()
+        };
+
+        if prefix.ends_with('#') {
+            let mut sections = vec![];
+            for symbol in &self.symbols {
+                if let SymbolKind::SectionHeader(name) = &symbol.kind {
+                    if !sections.contains(name) {
+                        sections.push(name.clone());
+                    }
+                }
+            }
+            return sections
+                .into_iter()
+                .map(|name| item(name, CompletionItemKind::MODULE))
+                .collect();
+        }
+
+        if prefix.ends_with('@') {
+            let mut aliases = self
+                .characters
+                .keys()
+                .map(|alias| alias.0.clone())
+                .collect::<Vec<_>>();
+            aliases.sort();
+            return aliases
+                .into_iter()
+                .map(|alias| item(alias, CompletionItemKind::VARIABLE))
+                .collect();
+        }
+
+        if let Some(mention) = prefix.strip_suffix(':') {
+            let alias_start = mention
+                .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .map_or(0, |i| i + 1);
+            if mention[..alias_start].ends_with('@') && alias_start < mention.len() {
+                let alias = Alias(mention[alias_start..].to_owned());
+                return self
+                    .states(&alias)
+                    .into_iter()
+                    .map(|state| item(state, CompletionItemKind::ENUM_MEMBER))
+                    .collect();
+            }
+        }
+
+        vec![]
+    }
+
+    /// Markdown with required states of the character under cursor
+    pub fn hover(&self, symbol: &Symbol) -> Option<String> {
+        let alias = match &symbol.kind {
+            SymbolKind::Character(alias) | SymbolKind::State(alias, _) => alias,
+            _ => return None,
+        };
+
+        let states = self.states(&Alias(alias.clone()));
+        let states = if states.is_empty() {
+            String::from("no named states")
+        } else {
+            let states = states
+                .iter()
+                .map(|state| format!("`{}`", state))
+                .collect::<Vec<_>>();
+            format!("states: {}", states.join(", "))
+        };
+
+        Some(format!("**@{}**\n\n{}", alias, states))
+    }
+
+    fn states(&self, alias: &Alias) -> Vec<String> {
+        self.characters
+            .get(alias)
+            .map(|requirements| {
+                requirements
+                    .states
+                    .iter()
+                    .filter_map(|state| match state {
+                        State::Named(name) => Some(name.clone()),
+                        State::Default => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Sections and characters in tokens of the tree
+fn symbols(tree: &SyntaxTree) -> Vec<Symbol> {
+    let mut symbols = vec![];
+    let mut offset = 0;
+    let mut line_start = true;
+    // `@:state` changes the state of the last mentioned character
+    let mut speaker = String::new();
+
+    for token in tree.tokens() {
+        let start = offset;
+        offset += token.text.len();
+
+        match token.kind {
+            SyntaxKind::Newline => {
+                line_start = true;
+                continue;
+            }
+            SyntaxKind::Whitespace | SyntaxKind::Comment => continue,
+            SyntaxKind::Link => {
+                let name = token.text[1..].to_owned();
+                let kind = if line_start {
+                    speaker.clear();
+                    SymbolKind::SectionHeader(name)
+                } else {
+                    SymbolKind::SectionLink(name)
+                };
+                symbols.push(Symbol {
+                    kind,
+                    range: start..offset,
+                });
+            }
+            SyntaxKind::Mention => {
+                mention(&token.text, start, &mut speaker, &mut symbols);
+            }
+            SyntaxKind::Command if token.text.starts_with(":opt(") => {
+                // effects of option: `:opt(#section; @alice:calm; =value)`
+                let mut effect_start = start + ":opt(".len();
+                let args = token.text[":opt(".len()..].trim_end_matches(')');
+                for effect in args.split(';') {
+                    let trimmed = effect.trim_start();
+                    let effect_offset = effect_start + effect.len() - trimmed.len();
+                    let trimmed = trimmed.trim_end();
+
+                    if let Some(name) = trimmed.strip_prefix('#') {
+                        symbols.push(Symbol {
+                            kind: SymbolKind::SectionLink(name.to_owned()),
+                            range: effect_offset..effect_offset + trimmed.len(),
+                        });
+                    } else if trimmed.starts_with('@') {
+                        let mut option_speaker = String::new();
+                        mention(trimmed, effect_offset, &mut option_speaker, &mut symbols);
+                    }
+
+                    effect_start += effect.len() + 1;
+                }
+            }
+            _ => {}
+        }
+
+        line_start = false;
+    }
+
+    symbols
+}
+
+fn mention(text: &str, start: usize, speaker: &mut String, symbols: &mut Vec<Symbol>) {
+    let (alias, state) = match text[1..].split_once(':') {
+        Some((alias, state)) => (alias, Some(state)),
+        None => (&text[1..], None),
+    };
+
+    let alias_start = start + 1;
+    if alias.is_empty() && state.is_none() {
+        speaker.clear();
+    } else if !alias.is_empty() {
+        *speaker = alias.to_owned();
+        symbols.push(Symbol {
+            kind: SymbolKind::Character(alias.to_owned()),
+            range: alias_start..alias_start + alias.len(),
+        });
+    }
+
+    if let Some(state) = state.filter(|state| !state.is_empty() && !speaker.is_empty()) {
+        let state_start = alias_start + alias.len() + 1;
+        symbols.push(Symbol {
+            kind: SymbolKind::State(speaker.clone(), state.to_owned()),
+            range: state_start..state_start + state.len(),
+        });
+    }
+}
@@ -0,0 +1,247 @@
+use lsp_server::{Message, Notification, Request, RequestId, Response};
+use serde_json::{json, Value};
+
+use std::io::BufReader;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+const URI: &str = "file:///story.dlg";
+
+const RAW: &str = "@alice:calm Hi
+:menu Where?
+:opt(#walk) Walk
+:opt(#nowhere) Stay
+#walk
+@alice:happy Let's go
+@bob Sure
+";
+
+struct Client {
+    server: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: i32,
+}
+
+impl Client {
+    fn start() -> Self {
+        let mut server = Command::new(env!("CARGO_BIN_EXE_dlg-lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = server.stdin.take().unwrap();
+        let stdout = BufReader::new(server.stdout.take().unwrap());
+
+        let mut client = Self {
+            server,
+            stdin,
+            stdout,
+            next_id: 0,
+        };
+
+        let result = client.request("initialize", json!({ "capabilities": {} }));
+        assert_eq!(json!(1), result["capabilities"]["textDocumentSync"]);
+        client.notify("initialized", json!({}));
+
+        client
+    }
+
+    fn send(&mut self, message: Message) {
+        message.write(&mut self.stdin).unwrap();
+    }
+
+    fn receive(&mut self) -> Message {
+        Message::read(&mut self.stdout).unwrap().unwrap()
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let id = RequestId::from(self.next_id);
+        self.send(Message::Request(Request::new(
+            id.clone(),
+            method.to_owned(),
+            params,
+        )));
+
+        match self.receive() {
+            Message::Response(Response {
+                id: response_id,
+                result,
+                error: None,
+            }) if response_id == id => result.unwrap_or_default(),
+            message => panic!("unexpected message {:?}", message),
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(Message::Notification(Notification::new(
+            method.to_owned(),
+            params,
+        )));
+    }
+
+    fn diagnostics(&mut self) -> Value {
+        match self.receive() {
+            Message::Notification(notification)
+                if notification.method == "textDocument/publishDiagnostics" =>
+            {
+                assert_eq!(json!(URI), notification.params["uri"]);
+                notification.params["diagnostics"].clone()
+            }
+            message => panic!("unexpected message {:?}", message),
+        }
+    }
+
+    fn open(&mut self, text: &str) -> Value {
+        self.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": { "uri": URI, "languageId": "dlg", "version": 1, "text": text }
+            }),
+        );
+        self.diagnostics()
+    }
+
+    fn at(&mut self, method: &str, line: u32, character: u32) -> Value {
+        let mut params = json!({
+            "textDocument": { "uri": URI },
+            "position": { "line": line, "character": character },
+        });
+        if method == "textDocument/references" {
+            params["context"] = json!({ "includeDeclaration": true });
+        }
+        self.request(method, params)
+    }
+
+    fn shutdown(mut self) {
+        assert_eq!(Value::Null, self.request("shutdown", Value::Null));
+        self.notify("exit", Value::Null);
+        assert!(self.server.wait().unwrap().success());
+    }
+}
+
+fn range(line: u32, start: u32, end: u32) -> Value {
+    json!({
+        "start": { "line": line, "character": start },
+        "end": { "line": line, "character": end },
+    })
+}
+
+fn labels(completion: &Value) -> Vec<&str> {
+    let mut labels = completion
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    labels.sort();
+    labels
+}
+
+#[test]
+fn test_diagnostics() {
+    let mut client = Client::start();
+
+    let diagnostics = client.open(RAW);
+    assert_eq!(1, diagnostics.as_array().unwrap().len());
+    assert_eq!(range(3, 0, 14), diagnostics[0]["range"]);
+    assert_eq!(json!(1), diagnostics[0]["severity"]);
+    assert_eq!(
+        json!("section #nowhere is not declared"),
+        diagnostics[0]["message"]
+    );
+
+    client.notify(
+        "textDocument/didChange",
+        json!({
+            "textDocument": { "uri": URI, "version": 2 },
+            "contentChanges": [{ "text": ":if\n" }],
+        }),
+    );
+    let diagnostics = client.diagnostics();
+    assert_eq!(1, diagnostics.as_array().unwrap().len());
+    assert_eq!(json!(1), diagnostics[0]["severity"]);
+
+    client.notify(
+        "textDocument/didClose",
+        json!({ "textDocument": { "uri": URI } }),
+    );
+    assert_eq!(json!([]), client.diagnostics());
+
+    client.shutdown();
+}
+
+#[test]
+fn test_navigation() {
+    let mut client = Client::start();
+    client.open(RAW);
+
+    let definition = client.at("textDocument/definition", 2, 7);
+    assert_eq!(json!(URI), definition["uri"]);
+    assert_eq!(range(4, 0, 5), definition["range"]);
+
+    assert_eq!(Value::Null, client.at("textDocument/definition", 3, 7));
+
+    let references = client.at("textDocument/references", 4, 2);
+    let ranges = references
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|location| location["range"].clone())
+        .collect::<Vec<_>>();
+    assert_eq!(vec![range(2, 5, 10), range(4, 0, 5)], ranges);
+
+    let references = client.at("textDocument/references", 5, 3);
+    let lines = references
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|location| location["range"]["start"]["line"].as_u64().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(vec![0, 5], lines);
+
+    client.shutdown();
+}
+
+#[test]
+fn test_completion() {
+    let mut client = Client::start();
+    client.open(RAW);
+
+    let sections = client.at("textDocument/completion", 2, 6);
+    assert_eq!(vec!["walk"], labels(&sections));
+
+    let aliases = client.at("textDocument/completion", 5, 1);
+    assert_eq!(vec!["alice", "bob"], labels(&aliases));
+
+    let states = client.at("textDocument/completion", 5, 7);
+    assert_eq!(vec!["calm", "happy"], labels(&states));
+
+    let text = client.at("textDocument/completion", 6, 7);
+    assert_eq!(json!([]), text);
+
+    client.shutdown();
+}
+
+#[test]
+fn test_hover() {
+    let mut client = Client::start();
+    client.open(RAW);
+
+    let hover = client.at("textDocument/hover", 0, 2);
+    assert_eq!(
+        json!("**@alice**\n\nstates: `calm`, `happy`"),
+        hover["contents"]["value"]
+    );
+    assert_eq!(range(0, 1, 6), hover["range"]);
+
+    let hover = client.at("textDocument/hover", 6, 2);
+    assert_eq!(
+        json!("**@bob**\n\nno named states"),
+        hover["contents"]["value"]
+    );
+
+    assert_eq!(Value::Null, client.at("textDocument/hover", 1, 2));
+
+    client.shutdown();
+}
@@ -0,0 +1,224 @@
+//! Compile-time parsing of `.dlg` dialogs. The macros expand to a [`Dialog`](dlg::prelude::Dialog)
+//! built without parsing at runtime, so the crate using them must depend on `dlg`
+
+#![warn(missing_docs)]
+
+use std::{env, path::PathBuf};
+
+use dlg::{
+    lint::{self, Severity},
+    prelude::*,
+    project::{DialogProject, DirectorySource, FileSource},
+};
+use proc_macro::{Delimiter, TokenStream, TokenTree};
+use quote::quote;
+use syn::LitStr;
+
+use tokens::Quote;
+
+mod tokens;
+
+/// Embeds a dialog file and everything it `:include`s. The path is relative to the directory
+/// with `Cargo.toml` of the crate. Parse errors, dangling links and menus without options
+/// fail the build
+///
+/// ```ignore
+/// let dialog: Dialog = dlg_macros::include_dlg!("dialogs/intro.dlg");
+/// ```
+#[proc_macro]
+pub fn include_dlg(input: TokenStream) -> TokenStream {
+    let path = syn::parse_macro_input!(input as LitStr);
+    let root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap_or_default());
+
+    let source = DirectorySource::new(&root);
+    let project = match DialogProject::load(&source, path.value()) {
+        Ok(project) => project,
+        Err(error) => return compile_errors(&path, vec![error.to_string()]),
+    };
+
+    let errors = validate(&project, &source);
+    if !errors.is_empty() {
+        return compile_errors(&path, errors);
+    }
+
+    // cargo rebuilds the crate when any of the files changes
+    let tracked = project
+        .files
+        .iter()
+        .map(|file| root.join(file).display().to_string());
+    let dialog = project.dialog.quote();
+    quote! {
+        {
+            #(const _: &str = ::std::include_str!(#tracked);)*
+            #dialog
+        }
+    }
+    .into()
+}
+
+/// Dialog written in place. Takes either a string literal or the dialog itself,
+/// as long as its text consists of valid Rust tokens. Errors point at the line they are found in
+///
+/// ```ignore
+/// let dialog: Dialog = dlg_macros::dlg! {
+///     @alice Hi!
+///     :move #bye
+///     #bye
+///     @bob Bye!
+/// };
+/// ```
+#[proc_macro]
+pub fn dlg(input: TokenStream) -> TokenStream {
+    let source = Source::new(input);
+
+    let (dialog, source_map) = match Dialog::parse_with_source_map(&source.text) {
+        Ok(parsed) => parsed,
+        Err(error) => return source.compile_error(error.span.line, &error.kind.to_string()),
+    };
+
+    if let Some(span) = source_map.includes.first() {
+        return source.compile_error(span.line, "`:include` is not supported, use `include_dlg!`");
+    }
+    let errors = lint::check(&dialog, &source_map)
+        .into_iter()
+        .filter(|diagnostic| diagnostic.severity() == Severity::Error)
+        .map(|diagnostic| source.compile_error(diagnostic.span.line, &diagnostic.lint.to_string()))
+        .collect::<Vec<_>>();
+    if !errors.is_empty() {
+        return block(errors);
+    }
+
+    dialog.quote().into()
+}
+
+/// Errors of every file of the project as `path:line:column: message`
+fn validate(project: &DialogProject, source: &impl FileSource) -> Vec<String> {
+    lint::check_project(project, source)
+        .into_iter()
+        .filter(|(_, diagnostic)| diagnostic.severity() == Severity::Error)
+        .map(|(path, diagnostic)| {
+            format!(
+                "{}:{}: {}",
+                path.display(),
+                diagnostic.span,
+                diagnostic.lint
+            )
+        })
+        .collect()
+}
+
+fn compile_errors(path: &LitStr, errors: Vec<String>) -> TokenStream {
+    block(
+        errors
+            .into_iter()
+            .map(|error| {
+                syn::Error::new(path.span(), error)
+                    .to_compile_error()
+                    .into()
+            })
+            .collect(),
+    )
+}
+
+/// Several `compile_error!` are a valid expression only inside a block
+fn block(errors: Vec<TokenStream>) -> TokenStream {
+    let errors = errors.into_iter().map(proc_macro2::TokenStream::from);
+    quote!({ #(#errors)* }).into()
+}
+
+/// Text of the dialog written in `dlg!` and spans of its lines
+struct Source {
+    text: String,
+    /// First token of every line of the text
+    lines: Vec<proc_macro::Span>,
+    /// Line and column the next token is written at
+    position: (usize, usize),
+}
+
+impl Source {
+    fn new(input: TokenStream) -> Self {
+        let mut source = Source {
+            text: String::new(),
+            lines: vec![],
+            position: (0, 1),
+        };
+
+        let trees = input.clone().into_iter().collect::<Vec<_>>();
+        if let [TokenTree::Literal(_)] = trees.as_slice() {
+            if let Ok(literal) = syn::parse::<LitStr>(input) {
+                source.text = literal.value();
+                source.lines = vec![literal.span().unwrap()];
+                return source;
+            }
+        }
+
+        source.write_stream(trees);
+        source
+    }
+
+    fn write_stream(&mut self, trees: impl IntoIterator<Item = TokenTree>) {
+        for tree in trees {
+            match tree {
+                TokenTree::Group(group) => {
+                    let (open, close) = match group.delimiter() {
+                        Delimiter::Parenthesis => ("(", ")"),
+                        Delimiter::Brace => ("{", "}"),
+                        Delimiter::Bracket => ("[", "]"),
+                        Delimiter::None => ("", ""),
+                    };
+                    self.write(group.span_open(), open);
+                    self.write_stream(group.stream());
+                    self.write(group.span_close(), close);
+                }
+                tree => {
+                    let span = tree.span();
+                    let text = span.source_text().unwrap_or_else(|| tree.to_string());
+                    self.write(span, &text);
+                }
+            }
+        }
+    }
+
+    /// Writes the token at its place in the macro, keeping line breaks and indentation
+    fn write(&mut self, span: proc_macro::Span, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+
+        let (line, column) = (span.line(), span.column());
+        if self.lines.is_empty() {
+            self.lines.push(span);
+            self.position = (line, 1);
+        }
+        // empty lines point at the next token
+        while self.position.0 < line {
+            self.text.push('\n');
+            self.lines.push(span);
+            self.position = (self.position.0 + 1, 1);
+        }
+        if column > self.position.1 {
+            self.text
+                .extend(std::iter::repeat_n(' ', column - self.position.1));
+        }
+
+        self.text.push_str(text);
+        let end = span.end();
+        for _ in line..end.line() {
+            self.lines.push(span);
+        }
+        self.position = (end.line(), end.column());
+    }
+
+    /// Error at the line of the text, pointing at the first token of that line in the macro
+    fn compile_error(&self, line: usize, message: &str) -> TokenStream {
+        let span = self
+            .lines
+            .get(line.saturating_sub(1))
+            .or(self.lines.last())
+            .copied()
+            .unwrap_or_else(proc_macro::Span::call_site);
+        syn::Error::new(span.into(), message)
+            .to_compile_error()
+            .into()
+    }
+}
@@ -0,0 +1,321 @@
+use dlg::parser::{
+    Branch, Comparison, Condition, Effect, FormattedText, Markup, Menu, MenuOption, Segment,
+    TextEffect, Value,
+};
+use dlg::prelude::*;
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// Expression that builds the value at runtime without parsing
+pub(crate) trait Quote {
+    fn quote(&self) -> TokenStream;
+}
+
+impl Quote for String {
+    fn quote(&self) -> TokenStream {
+        quote!(::std::string::String::from(#self))
+    }
+}
+
+impl Quote for u32 {
+    fn quote(&self) -> TokenStream {
+        quote!(#self)
+    }
+}
+
+impl Quote for i64 {
+    fn quote(&self) -> TokenStream {
+        quote!(#self)
+    }
+}
+
+impl Quote for bool {
+    fn quote(&self) -> TokenStream {
+        quote!(#self)
+    }
+}
+
+impl<T: Quote> Quote for Option<T> {
+    fn quote(&self) -> TokenStream {
+        match self {
+            Some(value) => {
+                let value = value.quote();
+                quote!(::std::option::Option::Some(#value))
+            }
+            None => quote!(::std::option::Option::None),
+        }
+    }
+}
+
+impl<T: Quote> Quote for Vec<T> {
+    fn quote(&self) -> TokenStream {
+        let items = self.iter().map(Quote::quote);
+        quote!(::std::vec![#(#items),*])
+    }
+}
+
+impl<T: Quote> Quote for Box<T> {
+    fn quote(&self) -> TokenStream {
+        let value = self.as_ref().quote();
+        quote!(::std::boxed::Box::new(#value))
+    }
+}
+
+impl Quote for Dialog {
+    fn quote(&self) -> TokenStream {
+        // characters are sorted, so the expansion is the same in every build
+        let mut characters = self.characters.iter().collect::<Vec<_>>();
+        characters.sort_by(|(a, _), (b, _)| a.0.cmp(&b.0));
+        let characters = characters.into_iter().map(|(alias, requirements)| {
+            let alias = alias.quote();
+            let states = requirements.states.quote();
+            quote!((#alias, ::dlg::parser::Requirements { states: #states }))
+        });
+        let sections = self.sections.iter().map(|(section, lines)| {
+            let section = section.quote();
+            let lines = lines.quote();
+            quote!((#section, #lines))
+        });
+        let includes = self.includes.quote();
+
+        quote! {
+            ::dlg::parser::Dialog {
+                characters: ::std::iter::IntoIterator::into_iter([#(#characters),*]).collect(),
+                sections: ::std::iter::IntoIterator::into_iter([#(#sections),*]).collect(),
+                includes: #includes,
+            }
+        }
+    }
+}
+
+impl Quote for Alias {
+    fn quote(&self) -> TokenStream {
+        let name = self.0.quote();
+        quote!(::dlg::parser::Alias(#name))
+    }
+}
+
+impl Quote for State {
+    fn quote(&self) -> TokenStream {
+        match self {
+            State::Default => quote!(::dlg::parser::State::Default),
+            State::Named(name) => {
+                let name = name.quote();
+                quote!(::dlg::parser::State::Named(#name))
+            }
+        }
+    }
+}
+
+impl Quote for Speaker {
+    fn quote(&self) -> TokenStream {
+        match self {
+            Speaker::Narrator => quote!(::dlg::parser::Speaker::Narrator),
+            Speaker::Character(alias, state) => {
+                let alias = alias.quote();
+                let state = state.quote();
+                quote!(::dlg::parser::Speaker::Character(#alias, #state))
+            }
+        }
+    }
+}
+
+impl Quote for Section {
+    fn quote(&self) -> TokenStream {
+        match self {
+            Section::Initial => quote!(::dlg::parser::Section::Initial),
+            Section::Named(name) => {
+                let name = name.quote();
+                quote!(::dlg::parser::Section::Named(#name))
+            }
+        }
+    }
+}
+
+impl Quote for Line {
+    fn quote(&self) -> TokenStream {
+        match self {
+            Line::Phrase { speaker, lines, id } => {
+                let speaker = speaker.quote();
+                let lines = lines.quote();
+                let id = id.quote();
+                quote!(::dlg::parser::Line::Phrase { speaker: #speaker, lines: #lines, id: #id })
+            }
+            Line::Menu(menu) => {
+                let menu = menu.quote();
+                quote!(::dlg::parser::Line::Menu(#menu))
+            }
+            Line::Conditional(branches) => {
+                let branches = branches.quote();
+                quote!(::dlg::parser::Line::Conditional(#branches))
+            }
+            Line::Move(section) => {
+                let section = section.quote();
+                quote!(::dlg::parser::Line::Move(#section))
+            }
+            Line::Back => quote!(::dlg::parser::Line::Back),
+            Line::Event { name, args } => {
+                let name = name.quote();
+                let args = args.quote();
+                quote!(::dlg::parser::Line::Event { name: #name, args: #args })
+            }
+        }
+    }
+}
+
+impl Quote for Menu {
+    fn quote(&self) -> TokenStream {
+        let variable = self.variable.quote();
+        let title = self.title.quote();
+        let id = self.id.quote();
+        let options = self.options.quote();
+        quote! {
+            ::dlg::parser::Menu { variable: #variable, title: #title, id: #id, options: #options }
+        }
+    }
+}
+
+impl Quote for MenuOption {
+    fn quote(&self) -> TokenStream {
+        let title = self.title.quote();
+        let id = self.id.quote();
+        let effects = self.effects.quote();
+        quote!(::dlg::parser::MenuOption { title: #title, id: #id, effects: #effects })
+    }
+}
+
+impl Quote for Effect {
+    fn quote(&self) -> TokenStream {
+        match self {
+            Effect::Jump(section) => {
+                let section = section.quote();
+                quote!(::dlg::parser::Effect::Jump(#section))
+            }
+            Effect::Speaker(speaker) => {
+                let speaker = speaker.quote();
+                quote!(::dlg::parser::Effect::Speaker(#speaker))
+            }
+            Effect::Assign(value) => {
+                let value = value.quote();
+                quote!(::dlg::parser::Effect::Assign(#value))
+            }
+        }
+    }
+}
+
+impl Quote for Branch {
+    fn quote(&self) -> TokenStream {
+        let condition = self.condition.quote();
+        let lines = self.lines.quote();
+        quote!(::dlg::parser::Branch { condition: #condition, lines: #lines })
+    }
+}
+
+impl Quote for Value {
+    fn quote(&self) -> TokenStream {
+        match self {
+            Value::String(value) => {
+                let value = value.quote();
+                quote!(::dlg::parser::Value::String(#value))
+            }
+            Value::Integer(value) => quote!(::dlg::parser::Value::Integer(#value)),
+            Value::Bool(value) => quote!(::dlg::parser::Value::Bool(#value)),
+        }
+    }
+}
+
+impl Quote for Condition {
+    fn quote(&self) -> TokenStream {
+        match self {
+            Condition::Value(value) => {
+                let value = value.quote();
+                quote!(::dlg::parser::Condition::Value(#value))
+            }
+            Condition::Variable(name) => {
+                let name = name.quote();
+                quote!(::dlg::parser::Condition::Variable(#name))
+            }
+            Condition::Not(inner) => {
+                let inner = inner.quote();
+                quote!(::dlg::parser::Condition::Not(#inner))
+            }
+            Condition::And(left, right) => {
+                let (left, right) = (left.quote(), right.quote());
+                quote!(::dlg::parser::Condition::And(#left, #right))
+            }
+            Condition::Or(left, right) => {
+                let (left, right) = (left.quote(), right.quote());
+                quote!(::dlg::parser::Condition::Or(#left, #right))
+            }
+            Condition::Compare(left, comparison, right) => {
+                let (left, right) = (left.quote(), right.quote());
+                let comparison = comparison.quote();
+                quote!(::dlg::parser::Condition::Compare(#left, #comparison, #right))
+            }
+        }
+    }
+}
+
+impl Quote for Comparison {
+    fn quote(&self) -> TokenStream {
+        match self {
+            Comparison::Equal => quote!(::dlg::parser::Comparison::Equal),
+            Comparison::NotEqual => quote!(::dlg::parser::Comparison::NotEqual),
+            Comparison::Less => quote!(::dlg::parser::Comparison::Less),
+            Comparison::LessOrEqual => quote!(::dlg::parser::Comparison::LessOrEqual),
+            Comparison::Greater => quote!(::dlg::parser::Comparison::Greater),
+            Comparison::GreaterOrEqual => quote!(::dlg::parser::Comparison::GreaterOrEqual),
+        }
+    }
+}
+
+impl Quote for FormattedText {
+    fn quote(&self) -> TokenStream {
+        let segments = self.0.quote();
+        quote!(::dlg::parser::FormattedText(#segments))
+    }
+}
+
+impl Quote for Segment {
+    fn quote(&self) -> TokenStream {
+        match self {
+            Segment::Text(text) => {
+                let text = text.quote();
+                quote!(::dlg::parser::Segment::Text(#text))
+            }
+            Segment::Expression(condition) => {
+                let condition = condition.quote();
+                quote!(::dlg::parser::Segment::Expression(#condition))
+            }
+            Segment::Styled(markup, segments) => {
+                let markup = markup.quote();
+                let segments = segments.quote();
+                quote!(::dlg::parser::Segment::Styled(#markup, #segments))
+            }
+            Segment::Wait(ms) => {
+                let ms = ms.quote();
+                quote!(::dlg::parser::Segment::Wait(#ms))
+            }
+        }
+    }
+}
+
+impl Quote for Markup {
+    fn quote(&self) -> TokenStream {
+        match self {
+            Markup::Bold => quote!(::dlg::parser::Markup::Bold),
+            Markup::Italic => quote!(::dlg::parser::Markup::Italic),
+            Markup::Color(color) => {
+                let color = color.quote();
+                quote!(::dlg::parser::Markup::Color(#color))
+            }
+            Markup::Effect(effect) => {
+                let effect = match effect {
+                    TextEffect::Shake => quote!(::dlg::parser::TextEffect::Shake),
+                    TextEffect::Wave => quote!(::dlg::parser::TextEffect::Wave),
+                };
+                quote!(::dlg::parser::Markup::Effect(#effect))
+            }
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use std::str::FromStr;
+
+use dlg::{
+    prelude::*,
+    project::{DialogProject, DirectorySource},
+};
+use dlg_macros::{dlg, include_dlg};
+
+#[test]
+fn test_include_dlg() {
+    let expected = DialogProject::load(
+        &DirectorySource::new(env!("CARGO_MANIFEST_DIR")),
+        "tests/dialogs/story.dlg",
+    )
+    .unwrap()
+    .dialog;
+
+    assert_eq!(expected, include_dlg!("tests/dialogs/story.dlg"));
+    assert_eq!(
+        Dialog::from_str(include_str!("dialogs/chapters/forest.dlg")).unwrap(),
+        include_dlg!("tests/dialogs/chapters/forest.dlg")
+    );
+}
+
+#[test]
+fn test_dlg_literal() {
+    let raw = "@alice:happy Hi\n:menu\n:opt(#bye) Bye\n\n#bye\n@bob Bye [#bye]";
+
+    assert_eq!(
+        Dialog::from_str(raw).unwrap(),
+        dlg!("@alice:happy Hi\n:menu\n:opt(#bye) Bye\n\n#bye\n@bob Bye [#bye]")
+    );
+}
+
+#[test]
+fn test_dlg_tokens() {
+    let expected = Dialog::from_str(
+        r#"
+        @alice:happy Hi, Bob! You have {coins} coins [#greeting]
+        [i]Shall we go?[/i]
+        :menu(answer) Where to?
+        :opt(=yes; #walk) For a walk
+        :opt(=no; @bob:sad) Stay
+
+        #walk
+        :if answer == "yes" && coins > 3
+            @bob (Sighs) Fine
+            :event "walk" 10
+        :endif
+        "#,
+    )
+    .unwrap();
+
+    let actual = dlg! {
+        @alice:happy Hi, Bob! You have {coins} coins [#greeting]
+        [i]Shall we go?[/i]
+        :menu(answer) Where to?
+        :opt(=yes; #walk) For a walk
+        :opt(=no; @bob:sad) Stay
+
+        #walk
+        :if answer == "yes" && coins > 3
+            @bob (Sighs) Fine
+            :event "walk" 10
+        :endif
+    };
+
+    assert_eq!(expected, actual);
+}
@@ -0,0 +1,12 @@
+fn main() {
+    let _ = dlg_macros::dlg! {
+        @alice Hi
+        :menu
+        :opt(#bye) Bye
+        :opt(#missing) Stay
+        :opt(#other) Leave
+
+        #bye
+        @bob Bye
+    };
+}
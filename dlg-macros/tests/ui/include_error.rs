@@ -0,0 +1,4 @@
+fn main() {
+    let _ = dlg_macros::include_dlg!("tests/dialogs/missing.dlg");
+    let _ = dlg_macros::include_dlg!(42);
+}
@@ -0,0 +1,8 @@
+fn main() {
+    let _ = dlg_macros::dlg! {
+        @alice Hi
+        :menu
+        :opt(#bye) Bye
+        Text after menu
+    };
+}
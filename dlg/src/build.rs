@@ -0,0 +1,358 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display, Write},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    parser::{Alias, Dialog, Requirements, Section, State},
+    project::{DialogProject, DirectorySource, ProjectError},
+};
+
+/// Error that occurred while generating code
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub enum BuildError {
+    /// Dialog can't be loaded
+    Project(ProjectError),
+    /// Alias, state or section can't be turned into a Rust identifier
+    InvalidName(String),
+    /// Two names become the same Rust identifier
+    NameCollision(String, String),
+    /// Generated file can't be written: its path and the reason
+    Write(PathBuf, String),
+}
+
+/// Generates enums of characters, their states and sections used in the dialogs and writes them to `out`.
+/// Meant to be called from a build script: paths are relative to the crate, and cargo reruns the script
+/// when any of the files or the files they include changes
+///
+/// ```no_run
+/// // build.rs
+/// let out = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("dialogs.rs");
+/// dlg::build::generate(&["dialogs/intro.dlg"], out).unwrap();
+///
+/// // src/main.rs
+/// // include!(concat!(env!("OUT_DIR"), "/dialogs.rs"));
+/// ```
+pub fn generate(files: &[impl AsRef<Path>], out: impl AsRef<Path>) -> Result<(), BuildError> {
+    let source = DirectorySource::new(".");
+    let mut dialog = Dialog::default();
+
+    for file in files {
+        let project = DialogProject::load(&source, file).map_err(BuildError::Project)?;
+        for path in &project.files {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+
+        for (alias, requirements) in project.dialog.characters {
+            let states = &mut dialog
+                .characters
+                .entry(alias)
+                .or_insert_with(|| Requirements { states: vec![] })
+                .states;
+            for state in requirements.states {
+                if !states.contains(&state) {
+                    states.push(state);
+                }
+            }
+        }
+        for section in project.dialog.sections.into_keys() {
+            dialog.sections.entry(section).or_default();
+        }
+    }
+
+    let code = to_rust(&dialog)?;
+    let out = out.as_ref();
+    fs::write(out, code).map_err(|e| BuildError::Write(out.to_owned(), e.to_string()))
+}
+
+/// Rust code with enums of characters, their states and sections of the dialog.
+///
+/// `Character` has a variant for every alias, holding a state enum like `AliceState`
+/// with `Default` and a variant for every named state. `SectionName` has `Initial` and a variant
+/// for every named section. They convert into [`Alias`], [`State`], [`crate::parser::Speaker`]
+/// and [`Section`], and back with `TryFrom`
+pub fn to_rust(dialog: &Dialog) -> Result<String, BuildError> {
+    let mut characters = dialog.characters.iter().collect::<Vec<_>>();
+    characters.sort_by(|(a, _), (b, _)| a.0.cmp(&b.0));
+
+    let mut character_names = Names::default();
+    let mut generated = vec![];
+    for (alias, requirements) in characters {
+        let variant = character_names.add(&alias.0)?;
+
+        let mut states = requirements
+            .states
+            .iter()
+            .filter_map(|state| match state {
+                State::Default => None,
+                State::Named(name) => Some(name),
+            })
+            .collect::<Vec<_>>();
+        states.sort();
+        let mut state_names = Names::default();
+        state_names.add("default")?;
+        let states = states
+            .into_iter()
+            .map(|state| Ok((state.as_str(), state_names.add(state)?)))
+            .collect::<Result<Vec<_>, BuildError>>()?;
+
+        generated.push((alias, variant, states));
+    }
+
+    let mut section_names = Names::default();
+    section_names.add("initial")?;
+    let sections = dialog
+        .sections
+        .keys()
+        .filter_map(|section| match section {
+            Section::Initial => None,
+            Section::Named(name) => Some(name),
+        })
+        .map(|name| Ok((name.as_str(), section_names.add(name)?)))
+        .collect::<Result<Vec<_>, BuildError>>()?;
+
+    let mut out = String::from("// Generated by `dlg::build`, do not edit\n");
+    write_characters(&mut out, &generated).expect("writing to string never fails");
+    write_sections(&mut out, &sections).expect("writing to string never fails");
+    Ok(out)
+}
+
+/// Character with its variant and its named states with their variants
+type Generated<'a> = (&'a Alias, String, Vec<(&'a str, String)>);
+
+fn write_characters(out: &mut String, characters: &[Generated]) -> fmt::Result {
+    writeln!(out, "\n/// Characters of the dialogs")?;
+    writeln!(out, "#[allow(dead_code)]")?;
+    writeln!(out, "#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]")?;
+    writeln!(out, "pub enum Character {{")?;
+    for (alias, variant, _) in characters {
+        writeln!(out, "    /// `@{}`", alias.0)?;
+        writeln!(out, "    {}({}State),", variant, variant)?;
+    }
+    writeln!(out, "}}")?;
+
+    for (alias, variant, states) in characters {
+        writeln!(out, "\n/// States of `@{}`", alias.0)?;
+        writeln!(out, "#[allow(dead_code)]")?;
+        writeln!(
+            out,
+            "#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Default)]"
+        )?;
+        writeln!(out, "pub enum {}State {{", variant)?;
+        writeln!(out, "    /// `@{}`", alias.0)?;
+        writeln!(out, "    #[default]")?;
+        writeln!(out, "    Default,")?;
+        for (state, state_variant) in states {
+            writeln!(out, "    /// `@{}:{}`", alias.0, state)?;
+            writeln!(out, "    {},", state_variant)?;
+        }
+        writeln!(out, "}}")?;
+
+        writeln!(
+            out,
+            "\nimpl From<{}State> for ::dlg::parser::State {{",
+            variant
+        )?;
+        writeln!(out, "    fn from(state: {}State) -> Self {{", variant)?;
+        writeln!(out, "        match state {{")?;
+        writeln!(
+            out,
+            "            {}State::Default => ::dlg::parser::State::Default,",
+            variant
+        )?;
+        for (state, state_variant) in states {
+            writeln!(
+                out,
+                "            {}State::{} => ::dlg::parser::State::Named(::std::string::String::from({:?})),",
+                variant, state_variant, state
+            )?;
+        }
+        writeln!(out, "        }}\n    }}\n}}")?;
+    }
+
+    writeln!(out, "\nimpl From<Character> for ::dlg::parser::Alias {{")?;
+    writeln!(out, "    fn from(character: Character) -> Self {{")?;
+    writeln!(out, "        match character {{")?;
+    for (alias, variant, _) in characters {
+        writeln!(
+            out,
+            "            Character::{}(_) => ::dlg::parser::Alias(::std::string::String::from({:?})),",
+            variant, alias.0
+        )?;
+    }
+    writeln!(out, "        }}\n    }}\n}}")?;
+
+    writeln!(out, "\nimpl From<Character> for ::dlg::parser::State {{")?;
+    writeln!(out, "    fn from(character: Character) -> Self {{")?;
+    writeln!(out, "        match character {{")?;
+    for (_, variant, _) in characters {
+        writeln!(
+            out,
+            "            Character::{}(state) => state.into(),",
+            variant
+        )?;
+    }
+    writeln!(out, "        }}\n    }}\n}}")?;
+
+    writeln!(out, "\nimpl From<Character> for ::dlg::parser::Speaker {{")?;
+    writeln!(out, "    fn from(character: Character) -> Self {{")?;
+    writeln!(
+        out,
+        "        ::dlg::parser::Speaker::Character(character.into(), character.into())"
+    )?;
+    writeln!(out, "    }}\n}}")?;
+
+    writeln!(
+        out,
+        "\nimpl TryFrom<&::dlg::parser::Speaker> for Character {{"
+    )?;
+    writeln!(out, "    type Error = ::dlg::parser::Speaker;\n")?;
+    writeln!(
+        out,
+        "    fn try_from(speaker: &::dlg::parser::Speaker) -> Result<Self, Self::Error> {{"
+    )?;
+    writeln!(out, "        let (alias, state) = match speaker {{")?;
+    writeln!(
+        out,
+        "            ::dlg::parser::Speaker::Character(alias, ::dlg::parser::State::Named(state)) => (alias.0.as_str(), Some(state.as_str())),"
+    )?;
+    writeln!(
+        out,
+        "            ::dlg::parser::Speaker::Character(alias, ::dlg::parser::State::Default) => (alias.0.as_str(), None),"
+    )?;
+    writeln!(
+        out,
+        "            ::dlg::parser::Speaker::Narrator => return Err(speaker.clone()),"
+    )?;
+    writeln!(out, "        }};")?;
+    writeln!(out, "        match (alias, state) {{")?;
+    for (alias, variant, states) in characters {
+        writeln!(
+            out,
+            "            ({:?}, None) => Ok(Character::{}({}State::Default)),",
+            alias.0, variant, variant
+        )?;
+        for (state, state_variant) in states {
+            writeln!(
+                out,
+                "            ({:?}, Some({:?})) => Ok(Character::{}({}State::{})),",
+                alias.0, state, variant, variant, state_variant
+            )?;
+        }
+    }
+    writeln!(out, "            _ => Err(speaker.clone()),")?;
+    writeln!(out, "        }}\n    }}\n}}")
+}
+
+fn write_sections(out: &mut String, sections: &[(&str, String)]) -> fmt::Result {
+    writeln!(out, "\n/// Sections of the dialogs")?;
+    writeln!(out, "#[allow(dead_code)]")?;
+    writeln!(out, "#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]")?;
+    writeln!(out, "pub enum SectionName {{")?;
+    writeln!(out, "    /// Lines before the first section")?;
+    writeln!(out, "    Initial,")?;
+    for (section, variant) in sections {
+        writeln!(out, "    /// `#{}`", section)?;
+        writeln!(out, "    {},", variant)?;
+    }
+    writeln!(out, "}}")?;
+
+    writeln!(
+        out,
+        "\nimpl From<SectionName> for ::dlg::parser::Section {{"
+    )?;
+    writeln!(out, "    fn from(section: SectionName) -> Self {{")?;
+    writeln!(out, "        match section {{")?;
+    writeln!(
+        out,
+        "            SectionName::Initial => ::dlg::parser::Section::Initial,"
+    )?;
+    for (section, variant) in sections {
+        writeln!(
+            out,
+            "            SectionName::{} => ::dlg::parser::Section::Named(::std::string::String::from({:?})),",
+            variant, section
+        )?;
+    }
+    writeln!(out, "        }}\n    }}\n}}")?;
+
+    writeln!(
+        out,
+        "\nimpl TryFrom<&::dlg::parser::Section> for SectionName {{"
+    )?;
+    writeln!(out, "    type Error = ::dlg::parser::Section;\n")?;
+    writeln!(
+        out,
+        "    fn try_from(section: &::dlg::parser::Section) -> Result<Self, Self::Error> {{"
+    )?;
+    writeln!(out, "        let name = match section {{")?;
+    writeln!(
+        out,
+        "            ::dlg::parser::Section::Initial => return Ok(SectionName::Initial),"
+    )?;
+    writeln!(
+        out,
+        "            ::dlg::parser::Section::Named(name) => name.as_str(),"
+    )?;
+    writeln!(out, "        }};")?;
+    writeln!(out, "        match name {{")?;
+    for (section, variant) in sections {
+        writeln!(
+            out,
+            "            {:?} => Ok(SectionName::{}),",
+            section, variant
+        )?;
+    }
+    writeln!(out, "            _ => Err(section.clone()),")?;
+    writeln!(out, "        }}\n    }}\n}}")
+}
+
+/// Rust identifiers given to names of one enum
+#[derive(Default)]
+struct Names {
+    taken: HashMap<String, String>,
+}
+
+impl Names {
+    /// Turns `snake_case` and `file::section` into `CamelCase`
+    fn add(&mut self, name: &str) -> Result<String, BuildError> {
+        let mut ident = String::new();
+        for part in name.split(['_', ':']) {
+            let mut chars = part.chars();
+            if let Some(first) = chars.next() {
+                ident.extend(first.to_uppercase());
+                ident.extend(chars);
+            }
+        }
+
+        if !ident.starts_with(|c: char| c.is_ascii_alphabetic()) || ident == "Self" {
+            return Err(BuildError::InvalidName(name.to_owned()));
+        }
+        if let Some(taken) = self.taken.insert(ident.clone(), name.to_owned()) {
+            return Err(BuildError::NameCollision(taken, name.to_owned()));
+        }
+        Ok(ident)
+    }
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::Project(error) => write!(f, "{}", error),
+            BuildError::InvalidName(name) => {
+                write!(f, "`{}` can't be turned into a Rust identifier", name)
+            }
+            BuildError::NameCollision(a, b) => {
+                write!(f, "`{}` and `{}` become the same Rust identifier", a, b)
+            }
+            BuildError::Write(path, reason) => {
+                write!(f, "can't write `{}`: {}", path.display(), reason)
+            }
+        }
+    }
+}
+
+impl Error for BuildError {}
@@ -0,0 +1,298 @@
+use std::fmt::Write;
+
+use crate::lint::reachable_sections;
+use crate::parser::{Dialog, Effect, Line, Section};
+
+/// How a section is shown in the graph
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum NodeKind {
+    /// Declared section reachable from the start of dialog
+    Section,
+    /// Declared section no `:move` or `:opt` leads to from the start of dialog
+    Unreachable,
+    /// Target of `:move` or `:opt` that is not declared
+    Dangling,
+}
+
+/// Section in the graph
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct Node {
+    /// Section of dialog
+    pub section: Section,
+    /// Whether the section is reachable and declared
+    pub kind: NodeKind,
+}
+
+/// How one section leads to another
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub enum EdgeKind {
+    /// `:opt(#section)` with the title of option
+    Option(Option<String>),
+    /// `:move #section`
+    Move,
+    /// End of section continues into the next one if [`crate::player::DialogState::fall_through`] is set
+    FallThrough,
+}
+
+/// Transition between sections
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct Edge {
+    /// Index of the source node
+    pub from: usize,
+    /// Index of the target node
+    pub to: usize,
+    /// What leads from one section to another
+    pub kind: EdgeKind,
+}
+
+/// Sections of dialog and transitions between them
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Graph {
+    /// Declared sections in order of declaration followed by dangling targets
+    pub nodes: Vec<Node>,
+    /// Transitions in order of appearance
+    pub edges: Vec<Edge>,
+}
+
+impl Graph {
+    /// Builds graph of sections. Reachability is checked like in [`crate::lint`], fall-through is not followed
+    pub fn new(dialog: &Dialog) -> Self {
+        let mut graph = Graph::default();
+
+        for section in dialog.sections.keys() {
+            graph.node(section);
+        }
+
+        for (section, lines) in &dialog.sections {
+            let from = graph.node(section);
+            graph.add_edges(from, lines);
+
+            if falls_through(lines) {
+                if let Some(next) = dialog.next_section(section) {
+                    let to = graph.node(next);
+                    graph.edges.push(Edge {
+                        from,
+                        to,
+                        kind: EdgeKind::FallThrough,
+                    });
+                }
+            }
+        }
+
+        let declared = dialog.sections.len();
+        for node in &mut graph.nodes[declared..] {
+            node.kind = NodeKind::Dangling;
+        }
+
+        let reachable = reachable_sections(dialog);
+        for node in &mut graph.nodes[..declared] {
+            if !reachable.contains(&node.section) {
+                node.kind = NodeKind::Unreachable;
+            }
+        }
+
+        graph
+    }
+
+    /// Graph in Graphviz DOT language
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph dialog {\n    node [shape=box];\n");
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            let style = match node.kind {
+                NodeKind::Section => "",
+                NodeKind::Unreachable => ", style=filled, fillcolor=orange",
+                NodeKind::Dangling => ", style=\"filled,dashed\", fillcolor=red, fontcolor=white",
+            };
+            writeln!(
+                out,
+                "    n{} [label=\"{}\"{}];",
+                index,
+                escape_dot(&node.section.to_string()),
+                style
+            )
+            .expect("writing to string never fails");
+        }
+
+        for edge in &self.edges {
+            let style = match edge.kind {
+                EdgeKind::FallThrough => ", style=dashed",
+                _ => "",
+            };
+            writeln!(
+                out,
+                "    n{} -> n{} [label=\"{}\"{}];",
+                edge.from,
+                edge.to,
+                escape_dot(&edge.label()),
+                style
+            )
+            .expect("writing to string never fails");
+        }
+
+        out.push_str("}\n");
+        out
+    }
+
+    /// Graph as Mermaid flowchart
+    pub fn to_mermaid(&self) -> String {
+        let mut out = String::from("flowchart TD\n");
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            writeln!(
+                out,
+                "    n{}[\"{}\"]",
+                index,
+                escape_mermaid(&node.section.to_string())
+            )
+            .expect("writing to string never fails");
+        }
+
+        for edge in &self.edges {
+            let arrow = match edge.kind {
+                EdgeKind::FallThrough => "-.->",
+                _ => "-->",
+            };
+            writeln!(
+                out,
+                "    n{} {}|\"{}\"| n{}",
+                edge.from,
+                arrow,
+                escape_mermaid(&edge.label()),
+                edge.to
+            )
+            .expect("writing to string never fails");
+        }
+
+        for (kind, class, style) in [
+            (NodeKind::Unreachable, "unreachable", "fill:orange"),
+            (
+                NodeKind::Dangling,
+                "dangling",
+                "fill:red,color:white,stroke-dasharray:4",
+            ),
+        ] {
+            let nodes: Vec<_> = self
+                .nodes
+                .iter()
+                .enumerate()
+                .filter(|(_, node)| node.kind == kind)
+                .map(|(index, _)| format!("n{}", index))
+                .collect();
+
+            if !nodes.is_empty() {
+                writeln!(out, "    classDef {} {}", class, style)
+                    .expect("writing to string never fails");
+                writeln!(out, "    class {} {}", nodes.join(","), class)
+                    .expect("writing to string never fails");
+            }
+        }
+
+        out
+    }
+
+    fn index(&self, section: &Section) -> Option<usize> {
+        self.nodes.iter().position(|node| &node.section == section)
+    }
+
+    /// Index of the section node. Added if there is no such node yet
+    fn node(&mut self, section: &Section) -> usize {
+        match self.index(section) {
+            Some(index) => index,
+            None => {
+                self.nodes.push(Node {
+                    section: section.clone(),
+                    kind: NodeKind::Section,
+                });
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    fn add_edges(&mut self, from: usize, lines: &[Line]) {
+        for line in lines {
+            match line {
+                Line::Move(target) => {
+                    let to = self.node(target);
+                    self.edges.push(Edge {
+                        from,
+                        to,
+                        kind: EdgeKind::Move,
+                    });
+                }
+                Line::Menu(menu) => {
+                    for option in &menu.options {
+                        for effect in &option.effects {
+                            if let Effect::Jump(target) = effect {
+                                let to = self.node(target);
+                                self.edges.push(Edge {
+                                    from,
+                                    to,
+                                    kind: EdgeKind::Option(option.title.clone()),
+                                });
+                            }
+                        }
+                    }
+                }
+                Line::Conditional(branches) => {
+                    for branch in branches {
+                        self.add_edges(from, &branch.lines);
+                    }
+                }
+                Line::Phrase { .. } | Line::Back | Line::Event { .. } => {}
+            }
+        }
+    }
+}
+
+impl Edge {
+    /// Text written on the edge
+    pub fn label(&self) -> String {
+        match &self.kind {
+            EdgeKind::Option(Some(title)) => title.clone(),
+            EdgeKind::Option(None) => String::from(":opt"),
+            EdgeKind::Move => String::from(":move"),
+            EdgeKind::FallThrough => String::from("fall-through"),
+        }
+    }
+}
+
+/// Graph of dialog sections in Graphviz DOT language
+pub fn to_dot(dialog: &Dialog) -> String {
+    Graph::new(dialog).to_dot()
+}
+
+/// Graph of dialog sections as Mermaid flowchart
+pub fn to_mermaid(dialog: &Dialog) -> String {
+    Graph::new(dialog).to_mermaid()
+}
+
+/// Whether playback can reach the end of lines without leaving the section
+fn falls_through(lines: &[Line]) -> bool {
+    match lines.last() {
+        None => true,
+        Some(Line::Move(_) | Line::Back) => false,
+        Some(Line::Menu(menu)) => menu.options.iter().any(|option| {
+            !option
+                .effects
+                .iter()
+                .any(|effect| matches!(effect, Effect::Jump(_)))
+        }),
+        Some(Line::Conditional(branches)) => {
+            // without `:else` none of branches may be entered
+            branches
+                .last()
+                .is_none_or(|branch| branch.condition.is_some())
+                || branches.iter().any(|branch| falls_through(&branch.lines))
+        }
+        Some(Line::Phrase { .. } | Line::Event { .. }) => true,
+    }
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_mermaid(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
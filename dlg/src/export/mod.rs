@@ -0,0 +1,2 @@
+/// Section graph in Graphviz DOT and Mermaid
+pub mod graph;
@@ -1,6 +1,24 @@
+//! Parser and player of `.dlg` dialog files
+
 #![warn(missing_docs)]
 
+/// Enums of characters and sections generated from dialogs in build scripts
+pub mod build;
+/// Conversion of parsed dialogs to other formats
+pub mod export;
+/// Static checks of parsed dialogs
+pub mod lint;
+/// Line IDs, gettext catalogues and translation of dialogs
+pub mod localization;
+/// Display names, colors, portraits and states of characters
+#[cfg(feature = "manifest")]
+pub mod manifest;
+/// Parsing of `.dlg` files into [`Dialog`]
 pub mod parser;
+/// Playback of parsed dialogs
 pub mod player;
+/// Dialogs assembled from several files with `:include`
+pub mod project;
 
+/// Commonly used types and macros
 pub mod prelude;
@@ -0,0 +1,405 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    path::PathBuf,
+};
+
+use crate::{
+    parser::{Dialog, Effect, Line, LinePosition, ParseErrorKind, Section, SourceMap, Span},
+    project::{link_from_root, qualify, DialogProject, FileSource, Qualifier},
+};
+
+/// How bad the problem is
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Severity {
+    /// Dialog doesn't parse or breaks on playback
+    Error,
+    /// Dialog works but is probably not what the writer meant
+    Warning,
+}
+
+/// Problem found in a dialog
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Lint {
+    /// Dialog can't be parsed
+    Parse(ParseErrorKind),
+    /// `:move` or `:opt` goes to a section that is not declared
+    DanglingLink(Section),
+    /// No `:move` or `:opt` leads to the section from the start of dialog
+    UnreachableSection(Section),
+    /// Section without lines
+    EmptySection(Section),
+    /// `:menu` without `:opt`
+    MenuWithoutOptions,
+    /// `:opt` without text to show
+    OptionWithoutTitle,
+    /// Sections start with `:move` to each other, so the dialog never goes on. Sections in the order of moves
+    MoveCycle(Vec<Section>),
+}
+
+/// Problem with its place in the source
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diagnostic {
+    /// What is wrong
+    pub lint: Lint,
+    /// Where it is written
+    pub span: Span,
+}
+
+impl Lint {
+    /// Severity of the problem
+    #[must_use]
+    pub fn severity(&self) -> Severity {
+        match self {
+            Lint::Parse(_)
+            | Lint::DanglingLink(_)
+            | Lint::MenuWithoutOptions
+            | Lint::MoveCycle(_) => Severity::Error,
+            Lint::UnreachableSection(_) | Lint::EmptySection(_) | Lint::OptionWithoutTitle => {
+                Severity::Warning
+            }
+        }
+    }
+}
+
+impl Diagnostic {
+    /// Severity of the problem
+    #[must_use]
+    pub fn severity(&self) -> Severity {
+        self.lint.severity()
+    }
+}
+
+/// Parses dialog and checks it. Parse error is reported as the only diagnostic
+#[must_use]
+pub fn lint(raw: &str) -> Vec<Diagnostic> {
+    match Dialog::parse_with_source_map(raw) {
+        Ok((dialog, source_map)) => check(&dialog, &source_map),
+        Err(error) => vec![Diagnostic {
+            lint: Lint::Parse(error.kind),
+            span: error.span,
+        }],
+    }
+}
+
+/// Checks parsed dialog. Diagnostics are sorted by their position in the source.
+/// Links with `::` go to sections of other files, so they are checked only with [`check_project`]
+#[must_use]
+pub fn check(dialog: &Dialog, source_map: &SourceMap) -> Vec<Diagnostic> {
+    check_with(dialog, source_map, |to| to.namespace().is_some())
+}
+
+/// Checks every file of the project. Links are resolved against the whole project, so links to sections
+/// of other files are not dangling and sections reached from other files are not unreachable.
+/// Diagnostics go in the order of [`DialogProject::files`]. Files that can't be read or parsed again are skipped
+#[must_use]
+pub fn check_project(
+    project: &DialogProject,
+    source: &impl FileSource,
+) -> Vec<(PathBuf, Diagnostic)> {
+    let namespaces = project.files[1..]
+        .iter()
+        .filter_map(|path| path.file_stem())
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .collect::<HashSet<_>>();
+    let unreachable = check(&project.dialog, &SourceMap::default())
+        .into_iter()
+        .filter_map(|diagnostic| match diagnostic.lint {
+            Lint::UnreachableSection(section) => Some(section),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+
+    let mut diagnostics = vec![];
+    for (index, path) in project.files.iter().enumerate() {
+        let parsed = source
+            .read(path)
+            .ok()
+            .and_then(|raw| Dialog::parse_with_source_map(&raw).ok());
+        let (dialog, source_map) = match parsed {
+            Some(parsed) => parsed,
+            None => continue,
+        };
+
+        let namespace = (index > 0)
+            .then(|| path.file_stem())
+            .flatten()
+            .map(|stem| stem.to_string_lossy().into_owned());
+        let qualifier = namespace
+            .as_deref()
+            .map(|namespace| Qualifier::new(namespace, &source_map, &namespaces));
+        let qualified = |section: &Section, link: bool| match &qualifier {
+            Some(qualifier) if link => qualifier.qualify_link(section),
+            Some(qualifier) => qualify(section, qualifier.namespace),
+            None if link => link_from_root(section),
+            None => section.clone(),
+        };
+
+        let file_diagnostics = check_with(&dialog, &source_map, |to| {
+            project.dialog.sections.contains_key(&qualified(to, true))
+        });
+        for diagnostic in file_diagnostics {
+            if let Lint::UnreachableSection(section) = &diagnostic.lint {
+                if !unreachable.contains(&qualified(section, false)) {
+                    continue;
+                }
+            }
+            diagnostics.push((path.clone(), diagnostic));
+        }
+    }
+
+    diagnostics
+}
+
+/// Sections `:move` and `:opt(#section)` lead to from the start of dialog, the initial section included.
+/// Fall-through is not followed
+pub(crate) fn reachable_sections(dialog: &Dialog) -> HashSet<&Section> {
+    fn links<'a>(lines: &'a [Line], targets: &mut Vec<&'a Section>) {
+        for line in lines {
+            match line {
+                Line::Move(to) => targets.push(to),
+                Line::Menu(menu) => {
+                    for option in &menu.options {
+                        for effect in &option.effects {
+                            if let Effect::Jump(to) = effect {
+                                targets.push(to);
+                            }
+                        }
+                    }
+                }
+                Line::Conditional(branches) => {
+                    for branch in branches {
+                        links(&branch.lines, targets);
+                    }
+                }
+                Line::Phrase { .. } | Line::Back | Line::Event { .. } => {}
+            }
+        }
+    }
+
+    let mut reachable = HashSet::from([&Section::Initial]);
+    let mut queue = vec![&Section::Initial];
+    while let Some(section) = queue.pop() {
+        let mut targets = vec![];
+        if let Some(lines) = dialog.sections.get(section) {
+            links(lines, &mut targets);
+        }
+        for to in targets {
+            if reachable.insert(to) {
+                queue.push(to);
+            }
+        }
+    }
+    reachable
+}
+
+/// Checks dialog. Links that are not declared in it and for which `external` is `true` are not dangling
+fn check_with(
+    dialog: &Dialog,
+    source_map: &SourceMap,
+    external: impl Fn(&Section) -> bool,
+) -> Vec<Diagnostic> {
+    let mut checker = Checker {
+        source_map,
+        diagnostics: vec![],
+        links: vec![],
+    };
+
+    for (section, lines) in &dialog.sections {
+        checker.check_lines(lines, section, &[]);
+    }
+
+    for (section, lines) in &dialog.sections {
+        if lines.is_empty() && *section != Section::Initial {
+            let span = source_map
+                .sections
+                .get(section)
+                .copied()
+                .unwrap_or_default();
+            checker.report(Lint::EmptySection(section.clone()), span);
+        }
+    }
+
+    let reachable = reachable_sections(dialog);
+
+    let mut dangling = vec![];
+    for (to, span) in &checker.links {
+        if !dialog.sections.contains_key(*to) && !external(to) {
+            dangling.push(Diagnostic {
+                lint: Lint::DanglingLink((*to).clone()),
+                span: *span,
+            });
+        }
+    }
+    checker.diagnostics.append(&mut dangling);
+
+    for section in dialog.sections.keys() {
+        if !reachable.contains(section) {
+            let span = source_map
+                .sections
+                .get(section)
+                .copied()
+                .unwrap_or_default();
+            checker.report(Lint::UnreachableSection(section.clone()), span);
+        }
+    }
+
+    check_move_cycles(dialog, &mut checker);
+
+    let mut diagnostics = checker.diagnostics;
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.span.start, diagnostic.severity()));
+    diagnostics
+}
+
+/// Reports sections that start with `:move` and lead back to themselves. Such loops show nothing,
+/// so the player gives up on them. Each loop is reported once, at the section declared first
+fn check_move_cycles(dialog: &Dialog, checker: &mut Checker) {
+    let moves = dialog
+        .sections
+        .iter()
+        .filter_map(|(section, lines)| match lines.first() {
+            Some(Line::Move(target)) => Some((section, target)),
+            _ => None,
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut reported = HashSet::new();
+    for section in dialog.sections.keys() {
+        let mut cycle = vec![section];
+        while let Some(target) = moves.get(cycle[cycle.len() - 1]) {
+            if *target == section {
+                if cycle.iter().all(|section| reported.insert(*section)) {
+                    let position = LinePosition {
+                        section: section.clone(),
+                        blocks: vec![],
+                        line_index: 0,
+                    };
+                    let span = checker
+                        .source_map
+                        .lines
+                        .get(&position)
+                        .copied()
+                        .unwrap_or_default();
+                    let cycle = cycle.into_iter().cloned().collect();
+                    checker.report(Lint::MoveCycle(cycle), span);
+                }
+                break;
+            }
+            if cycle.contains(target) {
+                break;
+            }
+            cycle.push(target);
+        }
+    }
+}
+
+struct Checker<'a> {
+    source_map: &'a SourceMap,
+    diagnostics: Vec<Diagnostic>,
+    /// Targets of `:move` and `:opt` and places where they are written
+    links: Vec<(&'a Section, Span)>,
+}
+
+impl<'a> Checker<'a> {
+    fn report(&mut self, lint: Lint, span: Span) {
+        self.diagnostics.push(Diagnostic { lint, span });
+    }
+
+    fn link(&mut self, to: &'a Section, span: Span) {
+        self.links.push((to, span));
+    }
+
+    fn check_lines(&mut self, lines: &'a [Line], section: &'a Section, blocks: &[(usize, usize)]) {
+        for (line_index, line) in lines.iter().enumerate() {
+            let position = LinePosition {
+                section: section.clone(),
+                blocks: blocks.to_vec(),
+                line_index,
+            };
+            let span = self
+                .source_map
+                .lines
+                .get(&position)
+                .copied()
+                .unwrap_or_default();
+
+            match line {
+                Line::Move(target) => self.link(target, span),
+                Line::Menu(menu) => {
+                    if menu.options.is_empty() {
+                        self.report(Lint::MenuWithoutOptions, span);
+                    }
+
+                    for (option_index, option) in menu.options.iter().enumerate() {
+                        let option_span = self
+                            .source_map
+                            .options
+                            .get(&(position.clone(), option_index))
+                            .copied()
+                            .unwrap_or(span);
+
+                        if option.title.is_none() {
+                            self.report(Lint::OptionWithoutTitle, option_span);
+                        }
+
+                        for effect in &option.effects {
+                            if let Effect::Jump(target) = effect {
+                                self.link(target, option_span);
+                            }
+                        }
+                    }
+                }
+                Line::Conditional(branches) => {
+                    for (branch_index, branch) in branches.iter().enumerate() {
+                        let mut inner = blocks.to_vec();
+                        inner.push((line_index, branch_index));
+                        self.check_lines(&branch.lines, section, &inner);
+                    }
+                }
+                Line::Phrase { .. } | Line::Back | Line::Event { .. } => {}
+            }
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+impl Display for Lint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Lint::Parse(kind) => write!(f, "{}", kind),
+            Lint::DanglingLink(section) => write!(f, "section {} is not declared", section),
+            Lint::UnreachableSection(section) => {
+                write!(f, "section {} can't be reached from the start", section)
+            }
+            Lint::EmptySection(section) => write!(f, "section {} has no lines", section),
+            Lint::MenuWithoutOptions => write!(f, "menu has no options"),
+            Lint::OptionWithoutTitle => write!(f, "option has no title"),
+            Lint::MoveCycle(cycle) => {
+                let sections = cycle.iter().map(ToString::to_string).collect::<Vec<_>>();
+                write!(
+                    f,
+                    "`:move` loops through {} without showing anything",
+                    sections.join(", ")
+                )
+            }
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}: {}", self.span, self.severity(), self.lint)
+    }
+}
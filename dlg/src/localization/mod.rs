@@ -0,0 +1,203 @@
+mod po;
+
+use std::{
+    collections::HashSet,
+    fmt::Write,
+    hash::{Hash, Hasher},
+};
+
+use crate::parser::{Dialog, FingerprintHasher, FormattedText, Line, Section, Speaker, State};
+
+pub use po::{Catalog, PoError};
+
+/// Where translatable text is written
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub enum MessageKind {
+    /// Phrase of the speaker
+    Phrase(Speaker),
+    /// Title of `:menu`
+    MenuTitle,
+    /// Title of `:opt`
+    OptionTitle,
+}
+
+/// Translatable text of a dialog
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct Message {
+    /// `[#id]` written after the text or the one generated by [`generate_id`]
+    pub id: String,
+    /// Text in the source language with markup. Parts of a phrase are separated by line breaks
+    pub text: String,
+    /// Section the text is written in
+    pub section: Section,
+    /// Phrase, menu title or option title
+    pub kind: MessageKind,
+}
+
+/// ID of a line without `[#id]`: hash of section, speaker and text. Doesn't depend on the version of Rust
+#[must_use]
+pub fn generate_id(section: &Section, kind: &MessageKind, text: &str) -> String {
+    let mut hasher = FingerprintHasher::default();
+    section.hash(&mut hasher);
+    kind.hash(&mut hasher);
+    text.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Phrases, menu titles and option titles in the order they are written. Lines with the same ID are listed once
+#[must_use]
+pub fn messages(dialog: &Dialog) -> Vec<Message> {
+    let mut messages = vec![];
+    for (section, lines) in &dialog.sections {
+        collect(section, lines, &mut messages);
+    }
+
+    let mut ids = HashSet::new();
+    messages.retain(|message| ids.insert(message.id.clone()));
+    messages
+}
+
+/// Gettext POT catalogue of dialog text. Line IDs are written as `msgctxt`
+#[must_use]
+pub fn to_pot(dialog: &Dialog) -> String {
+    let mut out =
+        String::from("msgid \"\"\nmsgstr \"\"\n\"Content-Type: text/plain; charset=UTF-8\\n\"\n");
+
+    for message in messages(dialog) {
+        let place = match &message.kind {
+            MessageKind::Phrase(Speaker::Narrator) => String::from("narrator"),
+            MessageKind::Phrase(Speaker::Character(alias, State::Default)) => {
+                format!("@{}", alias.0)
+            }
+            MessageKind::Phrase(Speaker::Character(alias, State::Named(state))) => {
+                format!("@{}:{}", alias.0, state)
+            }
+            MessageKind::MenuTitle => String::from("menu"),
+            MessageKind::OptionTitle => String::from("option"),
+        };
+
+        write!(
+            out,
+            "\n#. {}, {}\nmsgctxt {}\nmsgid {}\nmsgstr \"\"\n",
+            message.section,
+            place,
+            po::quote(&message.id),
+            po::quote(&message.text)
+        )
+        .expect("writing to string never fails");
+    }
+
+    out
+}
+
+impl Dialog {
+    /// Replaces text of phrases, menus and options with translations from the catalog.
+    /// Text without translation or with translation that can't be parsed stays in the source language
+    pub fn translate(&mut self, catalog: &Catalog) {
+        for (section, lines) in &mut self.sections {
+            translate_lines(section, lines, catalog);
+        }
+    }
+}
+
+fn collect(section: &Section, lines: &[Line], messages: &mut Vec<Message>) {
+    let message = |kind: MessageKind, text: String, id: &Option<String>| Message {
+        id: line_id(section, &kind, &text, id),
+        text,
+        section: section.clone(),
+        kind,
+    };
+
+    for line in lines {
+        match line {
+            Line::Phrase { speaker, lines, id } => {
+                messages.push(message(
+                    MessageKind::Phrase(speaker.clone()),
+                    phrase_text(lines),
+                    id,
+                ));
+            }
+            Line::Menu(menu) => {
+                if let Some(title) = &menu.title {
+                    messages.push(message(MessageKind::MenuTitle, title.clone(), &menu.id));
+                }
+                for option in &menu.options {
+                    if let Some(title) = &option.title {
+                        messages.push(message(MessageKind::OptionTitle, title.clone(), &option.id));
+                    }
+                }
+            }
+            Line::Conditional(branches) => {
+                for branch in branches {
+                    collect(section, &branch.lines, messages);
+                }
+            }
+            Line::Move(_) | Line::Back | Line::Event { .. } => {}
+        }
+    }
+}
+
+fn translate_lines(section: &Section, lines: &mut [Line], catalog: &Catalog) {
+    let translation = |kind: &MessageKind, text: &str, id: &Option<String>| {
+        catalog
+            .get(&line_id(section, kind, text, id))
+            .map(str::to_owned)
+    };
+
+    for line in lines {
+        match line {
+            Line::Phrase { speaker, lines, id } => {
+                let kind = MessageKind::Phrase(speaker.clone());
+                let translated = translation(&kind, &phrase_text(lines), id).and_then(|text| {
+                    text.split('\n')
+                        .map(str::parse::<FormattedText>)
+                        .collect::<Result<Vec<_>, _>>()
+                        .ok()
+                });
+                if let Some(translated) = translated {
+                    *lines = translated;
+                }
+            }
+            Line::Menu(menu) => {
+                if let Some(title) = &mut menu.title {
+                    if let Some(translated) = translation(&MessageKind::MenuTitle, title, &menu.id)
+                    {
+                        *title = translated;
+                    }
+                }
+                for option in &mut menu.options {
+                    if let Some(title) = &mut option.title {
+                        if let Some(translated) =
+                            translation(&MessageKind::OptionTitle, title, &option.id)
+                        {
+                            *title = translated;
+                        }
+                    }
+                }
+            }
+            Line::Conditional(branches) => {
+                for branch in branches {
+                    translate_lines(section, &mut branch.lines, catalog);
+                }
+            }
+            Line::Move(_) | Line::Back | Line::Event { .. } => {}
+        }
+    }
+}
+
+/// Explicit ID or the generated one
+fn line_id(section: &Section, kind: &MessageKind, text: &str, id: &Option<String>) -> String {
+    match id {
+        Some(id) => id.clone(),
+        None => generate_id(section, kind, text),
+    }
+}
+
+/// Parts of a phrase on separate lines
+fn phrase_text(lines: &[FormattedText]) -> String {
+    lines
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,236 @@
+use std::{collections::HashMap, error::Error, fmt::Display, str::FromStr};
+
+/// Translations read from a gettext PO file. Keyed by `msgctxt`, or by `msgid` for entries without context
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Catalog {
+    translations: HashMap<String, String>,
+}
+
+/// PO file can't be read
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct PoError {
+    /// Line number, starting from 1
+    pub line: usize,
+    /// What is wrong
+    pub message: String,
+}
+
+impl Catalog {
+    /// Translation of the line with this ID
+    #[must_use]
+    pub fn get(&self, id: &str) -> Option<&str> {
+        self.translations.get(id).map(String::as_str)
+    }
+
+    /// Adds or replaces translation of the line
+    pub fn insert(&mut self, id: impl Into<String>, translation: impl Into<String>) {
+        self.translations.insert(id.into(), translation.into());
+    }
+
+    /// Count of translated lines
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.translations.len()
+    }
+
+    /// `true` if nothing is translated
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.translations.is_empty()
+    }
+}
+
+/// Field of PO entry that the following string continues
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum Field {
+    Context,
+    Id,
+    IdPlural,
+    Translation,
+    /// `msgstr[1]` and further plural forms are not used
+    OtherPlural,
+}
+
+#[derive(Default)]
+struct Entry {
+    context: Option<String>,
+    id: String,
+    translation: String,
+    fuzzy: bool,
+    /// At least one keyword was read
+    started: bool,
+}
+
+impl Entry {
+    /// Adds translation to the catalog. The header, fuzzy and untranslated entries are skipped
+    fn finish(self, catalog: &mut Catalog) {
+        if !self.started || self.fuzzy || self.translation.is_empty() {
+            return;
+        }
+
+        match self.context {
+            Some(context) => catalog.insert(context, self.translation),
+            None if !self.id.is_empty() => catalog.insert(self.id, self.translation),
+            None => {}
+        }
+    }
+
+    fn field(&mut self, field: Field) -> Option<&mut String> {
+        match field {
+            Field::Context => self.context.as_mut(),
+            Field::Id => Some(&mut self.id),
+            Field::Translation => Some(&mut self.translation),
+            Field::IdPlural | Field::OtherPlural => None,
+        }
+    }
+}
+
+impl FromStr for Catalog {
+    type Err = PoError;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let mut catalog = Catalog::default();
+        let mut entry = Entry::default();
+        let mut field = None;
+        // `#, fuzzy` goes before the entry it belongs to
+        let mut fuzzy = false;
+
+        for (index, line) in raw.lines().enumerate() {
+            let error = |message: String| PoError {
+                line: index + 1,
+                message,
+            };
+            let line = line.trim();
+
+            if line.is_empty() {
+                std::mem::take(&mut entry).finish(&mut catalog);
+                field = None;
+                continue;
+            }
+
+            if let Some(comment) = line.strip_prefix('#') {
+                if let Some(flags) = comment.strip_prefix(',') {
+                    fuzzy |= flags.split(',').any(|flag| flag.trim() == "fuzzy");
+                }
+                continue;
+            }
+
+            if line.starts_with('"') {
+                let text = unquote(line).map_err(error)?;
+                match field {
+                    Some(field) => {
+                        if let Some(value) = entry.field(field) {
+                            value.push_str(&text);
+                        }
+                    }
+                    None => return Err(error(String::from("string without keyword"))),
+                }
+                continue;
+            }
+
+            let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let next = match keyword {
+                "msgctxt" => Field::Context,
+                "msgid" => Field::Id,
+                "msgid_plural" => Field::IdPlural,
+                "msgstr" | "msgstr[0]" => Field::Translation,
+                keyword if keyword.starts_with("msgstr[") => Field::OtherPlural,
+                keyword => return Err(error(format!("unknown keyword `{}`", keyword))),
+            };
+
+            // entries may be written without blank lines between them
+            if matches!(next, Field::Context | Field::Id)
+                && matches!(field, Some(Field::Translation | Field::OtherPlural))
+            {
+                std::mem::take(&mut entry).finish(&mut catalog);
+            }
+            if !entry.started {
+                entry.fuzzy = std::mem::take(&mut fuzzy);
+            }
+
+            let text = unquote(rest.trim()).map_err(error)?;
+            if next == Field::Context {
+                entry.context = Some(String::new());
+            }
+            if let Some(value) = entry.field(next) {
+                *value = text;
+            }
+            entry.started = true;
+            field = Some(next);
+        }
+
+        entry.finish(&mut catalog);
+        Ok(catalog)
+    }
+}
+
+/// Text of a quoted PO string with escape sequences replaced
+fn unquote(raw: &str) -> Result<String, String> {
+    let inner = raw
+        .strip_prefix('"')
+        .and_then(|raw| raw.strip_suffix('"'))
+        .ok_or_else(|| format!("`{}` is not a quoted string", raw))?;
+
+    let mut text = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c == '"' {
+            return Err(format!("unescaped quote in `{}`", raw));
+        }
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => text.push('\n'),
+            Some('t') => text.push('\t'),
+            Some('r') => text.push('\r'),
+            Some('"') => text.push('"'),
+            Some('\\') => text.push('\\'),
+            Some(other) => return Err(format!("unknown escape `\\{}` in `{}`", other, raw)),
+            None => return Err(format!("`{}` ends with `\\`", raw)),
+        }
+    }
+
+    Ok(text)
+}
+
+/// PO string for the text. Text with line breaks is split into one string per line after an empty one
+pub(crate) fn quote(text: &str) -> String {
+    let escape = |line: &str| {
+        let mut escaped = String::with_capacity(line.len() + 2);
+        escaped.push('"');
+        for c in line.chars() {
+            match c {
+                '"' => escaped.push_str("\\\""),
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\t' => escaped.push_str("\\t"),
+                '\r' => escaped.push_str("\\r"),
+                c => escaped.push(c),
+            }
+        }
+        escaped.push('"');
+        escaped
+    };
+
+    if !text.contains('\n') {
+        return escape(text);
+    }
+
+    let mut quoted = String::from("\"\"");
+    for line in text.split_inclusive('\n') {
+        quoted.push('\n');
+        quoted.push_str(&escape(line));
+    }
+    quoted
+}
+
+impl Display for PoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.line, self.message)
+    }
+}
+
+impl Error for PoError {}
@@ -0,0 +1,280 @@
+use std::{collections::HashMap, error::Error, fmt::Display, str::FromStr};
+
+use indexmap::IndexMap;
+
+use crate::{
+    lint::Severity,
+    parser::{is_ident, Alias, Requirements, State},
+};
+
+pub use crate::player::Color;
+
+/// Character declared in the manifest
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct CharacterInfo {
+    /// Name shown instead of the alias
+    pub name: Option<String>,
+    /// Color of the name and the phrases
+    pub color: Option<Color>,
+    /// Portrait IDs by state. `default` key of the manifest is [`State::Default`]
+    pub portraits: IndexMap<State, String>,
+    /// Named states the character may be in
+    pub states: Vec<String>,
+}
+
+/// Characters of a project: their display names, colors, portraits and states.
+/// Read from TOML with a table for every alias:
+///
+/// ```toml
+/// [alice]
+/// name = "Alice Liddell"
+/// color = "#3366ff"
+/// states = ["happy", "angry"]
+/// portraits = { default = "alice", happy = "alice_smiles" }
+/// ```
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Manifest {
+    /// Declared characters in the order they are written
+    pub characters: IndexMap<Alias, CharacterInfo>,
+}
+
+/// Manifest can't be read
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct ManifestError {
+    /// What is wrong, starting with the key it is found in
+    pub message: String,
+}
+
+/// Mismatch between characters of dialogs and the manifest
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub enum ManifestIssue {
+    /// Character is used in dialogs but not declared
+    UnknownAlias(Alias),
+    /// State is used in dialogs but not declared for the character. The declared state it is probably a typo of is suggested
+    UnknownState {
+        /// Character in the state
+        alias: Alias,
+        /// Name of the state
+        state: String,
+        /// Declared state with a similar name
+        suggestion: Option<String>,
+    },
+    /// State is declared but no dialog uses it
+    UnusedState {
+        /// Character the state is declared for
+        alias: Alias,
+        /// Name of the state
+        state: String,
+    },
+}
+
+impl Manifest {
+    /// Declared character
+    #[must_use]
+    pub fn get(&self, alias: &Alias) -> Option<&CharacterInfo> {
+        self.characters.get(alias)
+    }
+
+    /// Compares characters used in dialogs with the declared ones.
+    /// Pass characters of the whole project, otherwise states used in other files are reported as unused
+    #[must_use]
+    pub fn validate(&self, characters: &HashMap<Alias, Requirements>) -> Vec<ManifestIssue> {
+        let mut issues = vec![];
+
+        let mut used = characters.iter().collect::<Vec<_>>();
+        used.sort_by(|(a, _), (b, _)| a.0.cmp(&b.0));
+        for (alias, requirements) in used {
+            let info = match self.characters.get(alias) {
+                Some(info) => info,
+                None => {
+                    issues.push(ManifestIssue::UnknownAlias(alias.clone()));
+                    continue;
+                }
+            };
+
+            for state in &requirements.states {
+                if let State::Named(state) = state {
+                    if !info.states.contains(state) {
+                        issues.push(ManifestIssue::UnknownState {
+                            alias: alias.clone(),
+                            state: state.clone(),
+                            suggestion: suggest(state, &info.states),
+                        });
+                    }
+                }
+            }
+        }
+
+        for (alias, info) in &self.characters {
+            let requirements = characters.get(alias);
+            for state in &info.states {
+                let named = State::Named(state.clone());
+                if !requirements.is_some_and(|requirements| requirements.states.contains(&named)) {
+                    issues.push(ManifestIssue::UnusedState {
+                        alias: alias.clone(),
+                        state: state.clone(),
+                    });
+                }
+            }
+        }
+
+        issues
+    }
+}
+
+impl ManifestIssue {
+    /// Severity of the issue
+    #[must_use]
+    pub fn severity(&self) -> Severity {
+        match self {
+            ManifestIssue::UnknownAlias(_) | ManifestIssue::UnknownState { .. } => Severity::Error,
+            ManifestIssue::UnusedState { .. } => Severity::Warning,
+        }
+    }
+}
+
+/// Declared state that differs from the given one by at most two edits
+fn suggest(state: &str, declared: &[String]) -> Option<String> {
+    declared
+        .iter()
+        .map(|candidate| (distance(state, candidate), candidate))
+        .filter(|(distance, candidate)| *distance <= 2 && *distance < candidate.chars().count())
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.clone())
+}
+
+/// Levenshtein distance between words
+fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+impl FromStr for Manifest {
+    type Err = ManifestError;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let table = raw.parse::<toml::Table>().map_err(|e| ManifestError {
+            message: e.to_string().trim_end().to_owned(),
+        })?;
+
+        let mut manifest = Manifest::default();
+        for (alias, value) in table {
+            let error = |key: &str, message: String| ManifestError {
+                message: match key {
+                    "" => format!("{}: {}", alias, message),
+                    key => format!("{}.{}: {}", alias, key, message),
+                },
+            };
+
+            if !is_ident(&alias) {
+                return Err(error("", String::from("alias must be an identifier")));
+            }
+            let fields = match value {
+                toml::Value::Table(fields) => fields,
+                _ => return Err(error("", String::from("expected a table of the character"))),
+            };
+
+            let mut info = CharacterInfo::default();
+            // portraits are checked after all states are read
+            let mut portraits = None;
+            for (key, value) in fields {
+                match (key.as_str(), value) {
+                    ("name", toml::Value::String(name)) => info.name = Some(name),
+                    ("color", toml::Value::String(color)) => {
+                        info.color = Some(color.parse().map_err(|e| error(&key, e))?);
+                    }
+                    ("states", toml::Value::Array(states)) => {
+                        for state in states {
+                            match state {
+                                toml::Value::String(state)
+                                    if is_ident(&state) && state != "default" =>
+                                {
+                                    if !info.states.contains(&state) {
+                                        info.states.push(state);
+                                    }
+                                }
+                                state => {
+                                    return Err(error(&key, format!("invalid state {}", state)))
+                                }
+                            }
+                        }
+                    }
+                    ("portraits", toml::Value::Table(table)) => portraits = Some(table),
+                    ("name" | "color", value) => {
+                        return Err(error(&key, format!("expected a string, got {}", value)))
+                    }
+                    ("states", value) => {
+                        return Err(error(&key, format!("expected an array, got {}", value)))
+                    }
+                    ("portraits", value) => {
+                        return Err(error(&key, format!("expected a table, got {}", value)))
+                    }
+                    (key, _) => return Err(error(key, String::from("unknown key"))),
+                }
+            }
+
+            for (state, portrait) in portraits.unwrap_or_default() {
+                let key = format!("portraits.{}", state);
+                let portrait = match portrait {
+                    toml::Value::String(portrait) => portrait,
+                    value => return Err(error(&key, format!("expected a string, got {}", value))),
+                };
+                let state = match state.as_str() {
+                    "default" => State::Default,
+                    state if info.states.iter().any(|declared| declared == state) => {
+                        State::Named(state.to_owned())
+                    }
+                    _ => return Err(error(&key, String::from("state is not declared"))),
+                };
+                info.portraits.insert(state, portrait);
+            }
+
+            manifest.characters.insert(Alias(alias), info);
+        }
+
+        Ok(manifest)
+    }
+}
+
+impl Display for ManifestIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ManifestIssue::UnknownAlias(alias) => {
+                write!(f, "character `{}` is not declared in the manifest", alias.0)
+            }
+            ManifestIssue::UnknownState {
+                alias,
+                state,
+                suggestion,
+            } => {
+                write!(f, "state `{}` of `{}` is not declared", state, alias.0)?;
+                match suggestion {
+                    Some(suggestion) => write!(f, ", did you mean `{}`?", suggestion),
+                    None => Ok(()),
+                }
+            }
+            ManifestIssue::UnusedState { alias, state } => {
+                write!(f, "state `{}` of `{}` is never used", state, alias.0)
+            }
+        }
+    }
+}
+
+impl Display for ManifestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for ManifestError {}
@@ -0,0 +1,290 @@
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
+
+use super::{value::is_ident, Value};
+use crate::player::Variables;
+
+/// Comparison operator in condition
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Comparison {
+    /// `==`
+    Equal,
+    /// `!=`
+    NotEqual,
+    /// `<`
+    Less,
+    /// `<=`
+    LessOrEqual,
+    /// `>`
+    Greater,
+    /// `>=`
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn matches(self, ordering: Option<Ordering>) -> bool {
+        match self {
+            Comparison::Equal => ordering == Some(Ordering::Equal),
+            Comparison::NotEqual => ordering != Some(Ordering::Equal),
+            Comparison::Less => ordering == Some(Ordering::Less),
+            Comparison::LessOrEqual => {
+                matches!(ordering, Some(Ordering::Less | Ordering::Equal))
+            }
+            Comparison::Greater => ordering == Some(Ordering::Greater),
+            Comparison::GreaterOrEqual => {
+                matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+            }
+        }
+    }
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = match self {
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+/// Condition of `:if` and `:elseif` commands
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Condition {
+    /// Literal value
+    Value(Value),
+    /// Value of dialog variable. Unset variable is false
+    Variable(String),
+    /// `!condition`
+    Not(Box<Condition>),
+    /// `left && right`
+    And(Box<Condition>, Box<Condition>),
+    /// `left || right`
+    Or(Box<Condition>, Box<Condition>),
+    /// `left == right`, `left < right` and so on
+    Compare(Box<Condition>, Comparison, Box<Condition>),
+}
+
+impl Condition {
+    /// Evaluates condition with values of dialog variables
+    #[must_use]
+    pub fn evaluate(&self, variables: &Variables) -> bool {
+        match self.value(variables) {
+            Some(value) => value.is_truthy(),
+            None => false,
+        }
+    }
+
+    /// Value of expression. `None` if it reads an unset variable
+    #[must_use]
+    pub fn value(&self, variables: &Variables) -> Option<Value> {
+        match self {
+            Condition::Value(value) => Some(value.clone()),
+            Condition::Variable(name) => variables.get(name).cloned(),
+            Condition::Not(c) => Some(Value::Bool(!c.evaluate(variables))),
+            Condition::And(a, b) => {
+                Some(Value::Bool(a.evaluate(variables) && b.evaluate(variables)))
+            }
+            Condition::Or(a, b) => {
+                Some(Value::Bool(a.evaluate(variables) || b.evaluate(variables)))
+            }
+            Condition::Compare(a, op, b) => {
+                let ordering = match (a.value(variables), b.value(variables)) {
+                    (Some(a), Some(b)) => a.compare(&b),
+                    _ => None,
+                };
+                Some(Value::Bool(op.matches(ordering)))
+            }
+        }
+    }
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Condition::Value(value) => write!(f, "{}", value),
+            Condition::Variable(name) => write!(f, "{}", name),
+            Condition::Not(c) => write!(f, "!{}", c),
+            Condition::And(a, b) => write!(f, "({} && {})", a, b),
+            Condition::Or(a, b) => write!(f, "({} || {})", a, b),
+            Condition::Compare(a, op, b) => {
+                write!(f, "{} {} {}", Operand(a), op, Operand(b))
+            }
+        }
+    }
+}
+
+/// Side of comparison. Only values, variables and parentheses are parsed there,
+/// so `!` and other comparisons are put in parentheses
+struct Operand<'a>(&'a Condition);
+
+impl Display for Operand<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Condition::Not(_) | Condition::Compare(..) => write!(f, "({})", self.0),
+            condition => write!(f, "{}", condition),
+        }
+    }
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let mut parser = ConditionParser {
+            raw,
+            position: 0,
+            depth: 0,
+        };
+
+        let condition = parser.parse_or()?;
+        parser.skip_whitespace();
+        if parser.position < raw.len() {
+            return Err(format!("unexpected `{}`", &raw[parser.position..]));
+        }
+
+        Ok(condition)
+    }
+}
+
+/// Deepest nesting of `!`, parentheses and operators in a condition. Deeper conditions are errors,
+/// so a broken file can't overflow the stack of the parser
+const MAX_DEPTH: usize = 256;
+
+/// Recursive descent parser of conditions. `||` has the lowest priority, then `&&`, `!` and comparisons
+struct ConditionParser<'a> {
+    raw: &'a str,
+    position: usize,
+    /// Nesting of the expression being parsed
+    depth: usize,
+}
+
+impl<'a> ConditionParser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.raw[self.position..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.position = self.raw.len() - self.rest().trim_start().len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(token) {
+            self.position += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Goes one level deeper. Every `!`, `(` and chained `&&` or `||` nests the expression
+    fn enter(&mut self) -> Result<(), String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(format!("nested deeper than {} levels", MAX_DEPTH));
+        }
+        Ok(())
+    }
+
+    fn parse_or(&mut self) -> Result<Condition, String> {
+        let depth = self.depth;
+        let mut left = self.parse_and()?;
+        while self.eat("||") {
+            self.enter()?;
+            left = Condition::Or(Box::new(left), Box::new(self.parse_and()?));
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Condition, String> {
+        let depth = self.depth;
+        let mut left = self.parse_not()?;
+        while self.eat("&&") {
+            self.enter()?;
+            left = Condition::And(Box::new(left), Box::new(self.parse_not()?));
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Condition, String> {
+        if self.eat("!") {
+            self.enter()?;
+            let condition = Condition::Not(Box::new(self.parse_not()?));
+            self.depth -= 1;
+            return Ok(condition);
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Condition, String> {
+        let left = self.parse_atom()?;
+
+        // two-char operators go first so `<=` isn't taken for `<`
+        let operators = [
+            ("==", Comparison::Equal),
+            ("!=", Comparison::NotEqual),
+            ("<=", Comparison::LessOrEqual),
+            (">=", Comparison::GreaterOrEqual),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+        ];
+
+        for (token, op) in operators {
+            if self.eat(token) {
+                let right = self.parse_atom()?;
+                return Ok(Condition::Compare(Box::new(left), op, Box::new(right)));
+            }
+        }
+
+        Ok(left)
+    }
+
+    fn parse_atom(&mut self) -> Result<Condition, String> {
+        self.skip_whitespace();
+        let rest = self.rest();
+
+        if self.eat("(") {
+            self.enter()?;
+            let condition = self.parse_or()?;
+            if !self.eat(")") {
+                return Err("expected `)`".to_owned());
+            }
+            self.depth -= 1;
+            return Ok(condition);
+        }
+
+        if let Some(string) = rest.strip_prefix('"') {
+            let end = string
+                .find('"')
+                .ok_or_else(|| "unclosed string".to_owned())?;
+            self.position += end + 2;
+            return Ok(Condition::Value(Value::String(string[..end].to_owned())));
+        }
+
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(rest.len());
+        let word = &rest[..len];
+        if word.is_empty() {
+            return Err(match rest.chars().next() {
+                Some(c) => format!("unexpected `{}`", c),
+                None => "unexpected end of condition".to_owned(),
+            });
+        }
+        self.position += len;
+
+        match Value::from_literal(word) {
+            Some(value) => Ok(Condition::Value(value)),
+            None if is_ident(word) => Ok(Condition::Variable(word.to_owned())),
+            None => Err(format!("invalid identifier `{}`", word)),
+        }
+    }
+}
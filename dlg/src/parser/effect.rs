@@ -0,0 +1,74 @@
+use std::{fmt::Display, str::FromStr};
+
+use super::{section::is_section_name, value::is_ident, Alias, Section, Speaker, State, Value};
+
+/// Effect of chosen menu option
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Effect {
+    /// `#section` or `#file::section`. Go to the section
+    Jump(Section),
+    /// `@alice:calm`. Set current speaking character
+    Speaker(Speaker),
+    /// `=value`. Set the variable of menu to the value
+    Assign(Value),
+}
+
+impl Effect {
+    /// Parses effects of option separated by `;`: `=value; @alice:calm; #section`
+    pub(crate) fn parse_list(args: &str) -> Result<Vec<Effect>, String> {
+        args.split(';')
+            .map(str::trim)
+            .filter(|arg| !arg.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+}
+
+impl FromStr for Effect {
+    type Err = String;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        if let Some(name) = raw.strip_prefix('#') {
+            if is_section_name(name) {
+                return Ok(Effect::Jump(Section::Named(name.to_owned())));
+            }
+        } else if let Some(mention) = raw.strip_prefix('@') {
+            let speaker = match mention.split_once(':') {
+                None if mention.is_empty() => Some(Speaker::Narrator),
+                None if is_ident(mention) => Some(Speaker::Character(
+                    Alias(mention.to_owned()),
+                    State::Default,
+                )),
+                Some((name, state)) if is_ident(name) && is_ident(state) => Some(
+                    Speaker::Character(Alias(name.to_owned()), State::Named(state.to_owned())),
+                ),
+                _ => None,
+            };
+
+            if let Some(speaker) = speaker {
+                return Ok(Effect::Speaker(speaker));
+            }
+        } else if let Some(value) = raw.strip_prefix('=') {
+            return value.parse().map(Effect::Assign);
+        }
+
+        Err(format!("invalid effect `{}`", raw))
+    }
+}
+
+impl Display for Effect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Effect::Jump(section) => write!(f, "{}", section),
+            Effect::Speaker(Speaker::Narrator) => write!(f, "@"),
+            Effect::Speaker(Speaker::Character(alias, State::Default)) => {
+                write!(f, "@{}", alias.0)
+            }
+            Effect::Speaker(Speaker::Character(alias, State::Named(state))) => {
+                write!(f, "@{}:{}", alias.0, state)
+            }
+            Effect::Assign(value) => write!(f, "={}", value),
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use std::{error::Error, fmt::Display, ops::Range};
+
+/// Position of a piece of source text
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    /// Byte offset of the first character
+    pub start: usize,
+    /// Byte offset right after the last character
+    pub end: usize,
+    /// Line number, starting from 1
+    pub line: usize,
+    /// Column number in characters, starting from 1
+    pub column: usize,
+}
+
+impl Span {
+    /// Byte range of the span in the source text
+    #[must_use]
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Maps byte offsets of the source text to lines and columns
+pub(crate) struct LineIndex<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub(crate) fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    pub(crate) fn span(&self, range: Range<usize>) -> Span {
+        let line = match self.line_starts.binary_search(&range.start) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        };
+        let line_start = self.line_starts[line];
+        let column = self.source[line_start..range.start].chars().count();
+
+        Span {
+            start: range.start,
+            end: range.end,
+            line: line + 1,
+            column: column + 1,
+        }
+    }
+}
+
+/// Kind of error that occurred while parsing a dialog
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ParseErrorKind {
+    /// State was set with `@:state` while the narrator is speaking
+    NarratorState(String),
+    /// Mention doesn't match `@`, `@name`, `@:state` or `@name:state`
+    InvalidMention(String),
+    /// `:opt` command without the preceding `:menu`
+    OptionWithoutMenu,
+    /// Text or command after the options of a menu. Menu must be the last statement of a section or branch
+    TextAfterMenu,
+    /// Effect of `:opt` can't be parsed
+    InvalidEffect(String),
+    /// `=value` effect in menu without variable: `:menu` instead of `:menu(var_name)`
+    AssignWithoutVariable,
+    /// Variable name of `:menu(var_name)` is not an identifier
+    InvalidVariable(String),
+    /// `:if` or `:elseif` without condition
+    MissingCondition(String),
+    /// `:move` without `#section` on the same line
+    MissingMoveTarget,
+    /// Rest of `:event` line is not `"name" args`
+    InvalidEvent(String),
+    /// Condition can't be parsed
+    InvalidCondition(String),
+    /// `:if` without the matching `:endif`
+    UnclosedCondition,
+    /// `:elseif`, `:else` or `:endif` in the wrong place
+    UnexpectedCommand(String),
+    /// Inline block `{...}` is unclosed or its expression is invalid
+    InvalidInlineBlock(String),
+    /// Markup tag like `[b]` is unclosed, closed by a wrong tag or has an invalid value
+    InvalidMarkup(String),
+    /// `/*` without `*/`
+    UnclosedComment,
+    /// The same `[#id]` is written after two lines
+    DuplicateLineId(String),
+    /// Rest of `:include` line is not a quoted path
+    InvalidInclude(String),
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseErrorKind::NarratorState(state) => {
+                write!(f, "can't set state `{}` to the narrator", state)
+            }
+            ParseErrorKind::InvalidMention(mention) => {
+                write!(f, "invalid mention `{}`", mention)
+            }
+            ParseErrorKind::OptionWithoutMenu => write!(f, "`opt` without the preceding `menu`"),
+            ParseErrorKind::TextAfterMenu => write!(
+                f,
+                "text after menu is not allowed, menu must be the last statement of a section"
+            ),
+            ParseErrorKind::InvalidEffect(message) => write!(f, "{}", message),
+            ParseErrorKind::AssignWithoutVariable => {
+                write!(f, "`=value` in menu without variable, use `menu(var_name)`")
+            }
+            ParseErrorKind::InvalidVariable(name) => {
+                write!(f, "invalid variable name `{}`", name)
+            }
+            ParseErrorKind::MissingCondition(command) => {
+                write!(f, "`{}` without condition", command)
+            }
+            ParseErrorKind::MissingMoveTarget => write!(f, "`move` without `#section`"),
+            ParseErrorKind::InvalidEvent(message) => write!(f, "invalid event: {}", message),
+            ParseErrorKind::InvalidCondition(message) => {
+                write!(f, "invalid condition: {}", message)
+            }
+            ParseErrorKind::UnclosedCondition => write!(f, "`if` without the matching `endif`"),
+            ParseErrorKind::UnexpectedCommand(command) => {
+                write!(f, "unexpected `{}`", command)
+            }
+            ParseErrorKind::InvalidInlineBlock(message) => {
+                write!(f, "invalid inline block {}", message)
+            }
+            ParseErrorKind::InvalidMarkup(message) => {
+                write!(f, "invalid markup {}", message)
+            }
+            ParseErrorKind::UnclosedComment => write!(f, "unclosed comment"),
+            ParseErrorKind::DuplicateLineId(id) => write!(f, "line id `{}` is used twice", id),
+            ParseErrorKind::InvalidInclude(message) => write!(f, "invalid include: {}", message),
+        }
+    }
+}
+
+/// Error that occurred while parsing a dialog
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct ParseError {
+    /// What went wrong
+    pub kind: ParseErrorKind,
+    /// Where it went wrong
+    pub span: Span,
+}
+
+impl ParseError {
+    pub(crate) fn new(kind: ParseErrorKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.span, self.kind)
+    }
+}
+
+impl Error for ParseError {}
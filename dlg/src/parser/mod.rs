@@ -1,22 +1,45 @@
+mod condition;
+mod effect;
+mod error;
+mod note;
+mod printer;
 mod section;
+mod source_map;
+mod syntax;
+mod text;
 mod tokens;
-
-use crate::{
-    parser::tokens::{SemanticToken, Token},
-    player::Cursor,
+mod value;
+
+use crate::{parser::tokens::SemanticToken, player::Cursor};
+use error::LineIndex;
+use indexmap::IndexMap;
+use std::{
+    collections::{HashMap, HashSet},
+    hash::{Hash, Hasher},
+    ops::Range,
+    str::FromStr,
 };
-use logos::Lexer;
-use std::{collections::HashMap, str::FromStr};
 use tokens::MentionToken;
+pub(crate) use value::is_ident;
 
+pub use condition::{Comparison, Condition};
+pub use effect::Effect;
+pub use error::{ParseError, ParseErrorKind, Span};
+pub use note::Note;
 pub use section::Section;
+pub use source_map::{LinePosition, SourceMap};
+pub use syntax::{NodeKind, SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken, SyntaxTree};
+pub use text::{FormattedText, Fragment, Markup, Segment, Style, TextEffect};
+pub use value::Value;
 
 /// Character alias in dialog that maps to real character
 #[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Alias(pub String);
 
 /// State of character
 #[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum State {
     /// Default state
     Default,
@@ -26,6 +49,7 @@ pub enum State {
 
 /// Requirements for character in dialog. Contains list of states that used in dialog
 #[derive(PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Requirements {
     /// Required states
     pub states: Vec<State>,
@@ -33,6 +57,7 @@ pub struct Requirements {
 
 /// Speaker in dialog. Can be Narrator of Character with alias and state
 #[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Speaker {
     /// Narrator speaks
     Narrator,
@@ -42,48 +67,290 @@ pub enum Speaker {
 
 /// Menu in dialog. Contains title and vec of options
 #[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Menu {
+    /// Variable that is set by `=value` effects of options: `:menu(var_name)`
+    pub variable: Option<String>,
     /// Menu title
     pub title: Option<String>,
+    /// Line ID written as `[#id]` after the title
+    pub id: Option<String>,
     /// Menu options
     pub options: Vec<MenuOption>,
 }
 
 /// Menu option
 #[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MenuOption {
     /// Option title
     pub title: Option<String>,
-    /// Option args. Will be replaced to collection of option effects
-    pub args: String, // TODO: replace to set of effects
+    /// Line ID written as `[#id]` after the title
+    pub id: Option<String>,
+    /// Effects that are applied when option is chosen
+    pub effects: Vec<Effect>,
+}
+
+/// Branch of conditional block
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Branch {
+    /// Condition of `:if` or `:elseif`. `None` for `:else`
+    pub condition: Option<Condition>,
+    /// Lines that will be played if this branch is chosen
+    pub lines: Vec<Line>,
 }
 
 /// Line in dialog
-#[derive(Eq, Hash, PartialEq, Debug)]
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Line {
     /// CHaracted's phrases in dialog
     Phrase {
         /// The one who utters the phrase
         speaker: Speaker,
         /// A phrase. May consist of several parts that will be presented sequentially so as not to output too much text at a time
-        lines: Vec<String>,
-    }, // TODO: replace String to FormattedText,
+        lines: Vec<FormattedText>,
+        /// Line ID written as `[#id]` at the end of the phrase. Translations are looked up by it instead of the generated one
+        id: Option<String>,
+    },
     /// Menu with options in dialog
     Menu(Menu),
+    /// `:if` / `:elseif` / `:else` / `:endif` block. The first branch whose condition is true is played
+    Conditional(Vec<Branch>),
+    /// `:move #section`. Goes to the section and remembers where to return with `:back`
+    Move(Section),
+    /// `:back`. Returns to the line after the last `:move`
+    Back,
+    /// `:event "name" args`. Host is notified when player reaches it
+    Event {
+        /// Name of event
+        name: String,
+        /// Values after the name
+        args: Vec<Value>,
+    },
 }
 
+/// Parsed dialog
 #[derive(PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Dialog {
+    /// Characters used in dialog and their requirements
     pub characters: HashMap<Alias, Requirements>,
-    pub sections: HashMap<Section, Vec<Line>>,
+    /// Lines of every section in dialog in the order of declaration. [`Section::Initial`] goes first
+    pub sections: IndexMap<Section, Vec<Line>>,
+    /// Paths of `:include "file.dlg"` commands in the order they are written. They are resolved by [`crate::project::DialogProject`]
+    pub includes: Vec<String>,
+}
+
+/// Parses the rest of `:event` line: `"name" arg_1 arg_2`
+fn parse_event(raw: &str) -> Result<(String, Vec<Value>), String> {
+    let mut values = Value::parse_list(raw)?.into_iter();
+
+    match values.next() {
+        Some(Value::String(name)) => Ok((name, values.collect())),
+        _ => Err(format!("event name must be a string, got `{}`", raw)),
+    }
+}
+
+/// Conditional block that is not closed with `:endif` yet
+struct OpenBlock {
+    branches: Vec<Branch>,
+    has_else: bool,
+    /// Speaker before `:if`. Every branch starts with it
+    speaker: Speaker,
+    span: Span,
+}
+
+/// Returns lines of the innermost open branch or lines of the section
+fn target_lines<'a>(
+    sections: &'a mut IndexMap<Section, Vec<Line>>,
+    section: &Section,
+    blocks: &'a mut [OpenBlock],
+) -> &'a mut Vec<Line> {
+    match blocks.last_mut().and_then(|b| b.branches.last_mut()) {
+        Some(branch) => &mut branch.lines,
+        None => sections.entry(section.clone()).or_default(),
+    }
+}
+
+/// Position of the line that will be added next to the section or to the innermost open branch
+fn next_line_position(
+    sections: &IndexMap<Section, Vec<Line>>,
+    section: &Section,
+    blocks: &[OpenBlock],
+) -> LinePosition {
+    let mut line_index = sections.get(section).map_or(0, Vec::len);
+    let mut path = vec![];
+
+    for block in blocks {
+        path.push((line_index, block.branches.len().saturating_sub(1)));
+        line_index = block.branches.last().map_or(0, |branch| branch.lines.len());
+    }
+
+    LinePosition {
+        section: section.clone(),
+        blocks: path,
+        line_index,
+    }
+}
+
+/// Parses the rest of `:include` line: `"path.dlg"`
+fn parse_include(raw: &str) -> Result<String, String> {
+    match raw.parse() {
+        Ok(Value::String(path)) if !path.is_empty() => Ok(path),
+        _ => Err(format!("path must be a non-empty string, got `{}`", raw)),
+    }
+}
+
+/// Error for `:if`, `:elseif`, `:move`, `:event` or `:include` without the rest of the line
+fn missing_argument(command: String, span: Span) -> ParseError {
+    let kind = match command.as_str() {
+        "move" => ParseErrorKind::MissingMoveTarget,
+        "event" => ParseErrorKind::InvalidEvent("`event` without name".to_owned()),
+        "include" => ParseErrorKind::InvalidInclude("`include` without path".to_owned()),
+        _ => ParseErrorKind::MissingCondition(command),
+    };
+
+    ParseError::new(kind, span)
+}
+
+/// Splits `[#id]` off the end of a phrase or a title. The marker alone is kept as text
+fn split_line_id(text: &str) -> (&str, Option<String>) {
+    if let Some(rest) = text.trim_end().strip_suffix(']') {
+        if let Some(open) = rest.rfind("[#") {
+            let id = &rest[open + 2..];
+            let before = rest[..open].trim_end();
+            if is_ident(id) && !before.is_empty() {
+                return (before, Some(id.to_owned()));
+            }
+        }
+    }
+
+    (text, None)
+}
+
+/// Remembers the explicit line ID. Every ID may be used only once in a dialog
+fn use_line_id(
+    ids: &mut HashSet<String>,
+    id: &Option<String>,
+    span: Span,
+) -> Result<(), ParseError> {
+    match id {
+        Some(id) if !ids.insert(id.clone()) => Err(ParseError::new(
+            ParseErrorKind::DuplicateLineId(id.clone()),
+            span,
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn finish_menu(menu: &mut Option<Menu>, option: &mut Option<MenuOption>) -> Option<Line> {
+    let mut menu = menu.take()?;
+    if let Some(option) = option.take() {
+        menu.options.push(option);
+    }
+
+    Some(Line::Menu(menu))
 }
 
 impl Dialog {
+    /// Returns the line the cursor points to
     #[must_use]
     pub fn get_line_by_cursor(&self, cursor: &Cursor) -> Option<&Line> {
+        self.line_at(cursor.section(), cursor.blocks(), cursor.line_index())
+    }
+
+    /// Returns the line at the position
+    #[must_use]
+    pub fn get_line(&self, position: &LinePosition) -> Option<&Line> {
+        self.line_at(&position.section, &position.blocks, position.line_index)
+    }
+
+    fn line_at(
+        &self,
+        section: &Section,
+        blocks: &[(usize, usize)],
+        line_index: usize,
+    ) -> Option<&Line> {
+        let mut lines = self.sections.get(section)?;
+
+        for &(line_index, branch_index) in blocks {
+            lines = match lines.get(line_index) {
+                Some(Line::Conditional(branches)) => &branches.get(branch_index)?.lines,
+                _ => return None,
+            };
+        }
+
+        lines.get(line_index)
+    }
+
+    /// Speaker a phrase without a mention gets at the position: the speaker of the previous phrase of the block,
+    /// or the speaker before `:if` if there is none. Sections start with the narrator
+    pub(crate) fn inherited_speaker(
+        &self,
+        section: &Section,
+        blocks: &[(usize, usize)],
+        line_index: usize,
+    ) -> Speaker {
+        fn last_speaker(lines: &[Line]) -> Option<&Speaker> {
+            lines.iter().rev().find_map(|line| match line {
+                Line::Phrase { speaker, .. } => Some(speaker),
+                _ => None,
+            })
+        }
+
+        let mut speaker = &Speaker::Narrator;
+        let mut lines = match self.sections.get(section) {
+            Some(lines) => lines.as_slice(),
+            None => return speaker.clone(),
+        };
+
+        for &(block_index, branch_index) in blocks {
+            speaker = last_speaker(&lines[..block_index.min(lines.len())]).unwrap_or(speaker);
+            lines = match lines.get(block_index) {
+                Some(Line::Conditional(branches)) => match branches.get(branch_index) {
+                    Some(branch) => &branch.lines,
+                    None => return speaker.clone(),
+                },
+                _ => return speaker.clone(),
+            };
+        }
+
+        last_speaker(&lines[..line_index.min(lines.len())])
+            .unwrap_or(speaker)
+            .clone()
+    }
+
+    /// Parses dialog and keeps its comments as writer notes
+    pub fn parse_with_notes(raw: &str) -> Result<(Dialog, Vec<Note>), ParseError> {
+        Self::parse(raw, true).map(|(dialog, notes, _)| (dialog, notes))
+    }
+
+    /// Parses dialog and remembers where its sections, lines and menu options are written
+    pub fn parse_with_source_map(raw: &str) -> Result<(Dialog, SourceMap), ParseError> {
+        Self::parse(raw, false).map(|(dialog, _, source_map)| (dialog, source_map))
+    }
+
+    /// Hash of all sections and their lines. It is stable between runs and platforms,
+    /// so it can be stored in saves to detect that the dialog has changed
+    #[must_use]
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = FingerprintHasher::default();
+        for (section, lines) in &self.sections {
+            section.hash(&mut hasher);
+            lines.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Section declared right after the given one
+    #[must_use]
+    pub fn next_section(&self, section: &Section) -> Option<&Section> {
+        let index = self.sections.get_index_of(section)?;
         self.sections
-            .get(cursor.section())
-            .and_then(|s| s.get(cursor.line_index()))
+            .get_index(index + 1)
+            .map(|(section, _)| section)
     }
 
     fn ensure_character_requirement(&mut self, mention: &Speaker) {
@@ -98,16 +365,230 @@ impl Dialog {
         }
     }
 
-    fn parse_semantics(&mut self, semantics: Vec<SemanticToken>) -> Result<(), String> {
+    fn parse(raw: &str, keep_notes: bool) -> Result<(Dialog, Vec<Note>, SourceMap), ParseError> {
+        Self::lower(raw, syntax::lex(raw), keep_notes)
+    }
+
+    /// Builds dialog from tokens of syntax tree. Ranges of tokens point into `raw`
+    pub(crate) fn lower(
+        raw: &str,
+        tokens: Vec<(SyntaxKind, Range<usize>)>,
+        keep_notes: bool,
+    ) -> Result<(Dialog, Vec<Note>, SourceMap), ParseError> {
+        let index = LineIndex::new(raw);
+        let mut buf = String::new();
+        let mut buf_start = 0;
+        let mut semantics = vec![];
+        let mut comments = vec![];
+        let mut note_tokens = vec![];
+        // text after a command on its line is the argument of the command
+        let mut command_line = false;
+        for (kind, range) in tokens {
+            let slice = &raw[range.clone()];
+
+            match kind {
+                SyntaxKind::Comment | SyntaxKind::UnclosedComment => {
+                    if kind == SyntaxKind::UnclosedComment {
+                        return Err(ParseError::new(
+                            ParseErrorKind::UnclosedComment,
+                            index.span(range.start..range.start + 2),
+                        ));
+                    }
+
+                    if keep_notes {
+                        let text = match slice.strip_prefix("//") {
+                            Some(text) => text,
+                            None => &slice[2..slice.len() - 2],
+                        };
+                        note_tokens.push((
+                            SemanticToken::Note(text.trim().to_owned()),
+                            index.span(range.clone()),
+                        ));
+                    }
+
+                    // comments stay in the text buffer, so blank lines around them are still detected
+                    if buf.is_empty() {
+                        buf_start = range.start;
+                    }
+                    buf.push_str(slice);
+                    comments.push(range);
+                    continue;
+                }
+                SyntaxKind::InlineBlock
+                | SyntaxKind::Text
+                | SyntaxKind::Whitespace
+                | SyntaxKind::Newline => {
+                    // inline blocks are a part of text, here only their expressions are checked
+                    if kind == SyntaxKind::InlineBlock {
+                        text::parse_block(slice).map_err(|e| {
+                            ParseError::new(
+                                ParseErrorKind::InvalidInlineBlock(e),
+                                index.span(range.clone()),
+                            )
+                        })?;
+                    }
+
+                    if command_line && kind == SyntaxKind::Newline {
+                        command_line = false;
+                        push_text(&mut semantics, &index, &buf, buf_start, &comments);
+                        buf.clear();
+                    }
+
+                    if buf.is_empty() {
+                        buf_start = range.start;
+                    }
+                    buf.push_str(slice);
+                    continue;
+                }
+                SyntaxKind::Mention | SyntaxKind::Link | SyntaxKind::Command => {}
+            }
+
+            if !buf.is_empty() {
+                push_text(&mut semantics, &index, &buf, buf_start, &comments);
+                buf.clear();
+            }
+
+            if kind == SyntaxKind::Command {
+                command_line = true;
+            }
+
+            let span = index.span(range);
+
+            let semantic_token = match kind {
+                SyntaxKind::Mention => SemanticToken::Mention(
+                    parse_mention(slice).map_err(|kind| ParseError::new(kind, span))?,
+                ),
+                SyntaxKind::Link => SemanticToken::Link(String::from(&slice[1..])),
+                _ => {
+                    if let Some(index) = slice.find('(') {
+                        let name = &slice[1..index];
+                        let args = &slice[index + 1..slice.len() - 1];
+
+                        SemanticToken::Command(name.to_string(), args.to_string())
+                    } else {
+                        SemanticToken::Command(slice[1..].into(), String::new())
+                    }
+                }
+            };
+
+            semantics.push((semantic_token, span));
+        }
+
+        if !buf.is_empty() {
+            push_text(&mut semantics, &index, &buf, buf_start, &comments);
+        }
+
+        // every note goes before the first token that starts after it
+        semantics.extend(note_tokens);
+        semantics.sort_by_key(|(_, span)| span.start);
+
+        let mut dlg: Dialog = Dialog::default();
+        let mut notes = vec![];
+        let mut source_map = SourceMap::default();
+        source_map
+            .sections
+            .insert(Section::Initial, index.span(0..0));
+
+        dlg.parse_semantics(semantics, &mut notes, &mut source_map)?;
+
+        Ok((dlg, notes, source_map))
+    }
+
+    fn parse_semantics(
+        &mut self,
+        semantics: Vec<(SemanticToken, Span)>,
+        notes: &mut Vec<Note>,
+        source_map: &mut SourceMap,
+    ) -> Result<(), ParseError> {
         let mut current_mention = Speaker::Narrator;
         let mut current_section = Section::Initial;
 
         let mut current_menu: Option<Menu> = None;
         let mut current_option: Option<MenuOption> = None;
-        for token in semantics {
-            let current_lines = self.sections.entry(current_section.clone()).or_default();
+        let mut menu_position = next_line_position(&self.sections, &current_section, &[]);
+
+        let mut blocks: Vec<OpenBlock> = vec![];
+        let mut line_ids = HashSet::new();
+        // `:if`, `:elseif`, `:move`, `:event` or `:include` that waits for the rest of its line
+        let mut pending_command: Option<(String, Span)> = None;
+
+        if !semantics.is_empty() {
+            self.sections.entry(Section::Initial).or_default();
+        }
+
+        for (token, span) in semantics {
+            let token = match (pending_command.take(), token) {
+                // notes don't interrupt commands waiting for the rest of their line
+                (pending, SemanticToken::Note(text)) => {
+                    pending_command = pending;
+                    notes.push(Note {
+                        text,
+                        span,
+                        position: next_line_position(&self.sections, &current_section, &blocks),
+                    });
+                    continue;
+                }
+                (None, token) => token,
+                (Some((command, command_span)), SemanticToken::Link(name))
+                    if command == "move" && span.line == command_span.line =>
+                {
+                    let position = next_line_position(&self.sections, &current_section, &blocks);
+                    source_map.lines.insert(position, command_span);
+                    target_lines(&mut self.sections, &current_section, &mut blocks)
+                        .push(Line::Move(Section::Named(name)));
+                    continue;
+                }
+                (Some((command, command_span)), SemanticToken::Text(lines))
+                    if command != "move" && span.line == command_span.line =>
+                {
+                    let (argument, left) = lines.split_first().expect("text is not empty");
+                    let argument_span = Span {
+                        end: span.start + argument.len(),
+                        ..span
+                    };
+
+                    if command == "include" {
+                        let path = parse_include(argument).map_err(|e| {
+                            ParseError::new(ParseErrorKind::InvalidInclude(e), argument_span)
+                        })?;
+
+                        self.includes.push(path);
+                        source_map.includes.push(command_span);
+                    } else if command == "event" {
+                        let (name, args) = parse_event(argument).map_err(|e| {
+                            ParseError::new(ParseErrorKind::InvalidEvent(e), argument_span)
+                        })?;
+
+                        let position =
+                            next_line_position(&self.sections, &current_section, &blocks);
+                        source_map.lines.insert(position, command_span);
+                        target_lines(&mut self.sections, &current_section, &mut blocks)
+                            .push(Line::Event { name, args });
+                    } else {
+                        let condition = argument.parse().map_err(|e| {
+                            ParseError::new(ParseErrorKind::InvalidCondition(e), argument_span)
+                        })?;
+
+                        if let Some(block) = blocks.last_mut() {
+                            block.branches.push(Branch {
+                                condition: Some(condition),
+                                lines: vec![],
+                            });
+                        }
+                    }
+
+                    if left.is_empty() {
+                        continue;
+                    }
+                    SemanticToken::Text(left.to_vec())
+                }
+                (Some((command, command_span)), _) => {
+                    return Err(missing_argument(command, command_span));
+                }
+            };
 
             match token {
+                SemanticToken::Note(_) => unreachable!("notes are handled above"),
                 SemanticToken::Mention(variant) => {
                     match &variant {
                         MentionToken::Name(name) => {
@@ -125,7 +606,10 @@ impl Dialog {
 
                                 self.ensure_character_requirement(&current_mention);
                             } else {
-                                return Err("Нельзя устанавливать состояние рассказчику".to_owned());
+                                return Err(ParseError::new(
+                                    ParseErrorKind::NarratorState(new_state.clone()),
+                                    span,
+                                ));
                             }
                         }
                         MentionToken::NameState(name, state) => {
@@ -139,193 +623,362 @@ impl Dialog {
                     };
                 }
                 SemanticToken::Link(name) => {
-                    if let Some(mut menu) = current_menu {
-                        if let Some(option) = current_option {
-                            menu.options.push(option);
-                        }
+                    if let Some(block) = blocks.last() {
+                        return Err(ParseError::new(
+                            ParseErrorKind::UnclosedCondition,
+                            block.span,
+                        ));
+                    }
 
+                    if let Some(menu) = finish_menu(&mut current_menu, &mut current_option) {
                         let lines = self.sections.entry(current_section.clone()).or_default();
-                        lines.push(Line::Menu(menu));
+                        lines.push(menu);
                     }
 
-                    current_menu = None;
-                    current_option = None;
-
                     current_section = Section::Named(name);
                     current_mention = Speaker::Narrator;
+                    // empty sections are kept too, so sections are listed in the order of the source
+                    self.sections.entry(current_section.clone()).or_default();
+                    source_map
+                        .sections
+                        .entry(current_section.clone())
+                        .or_insert(span);
                 }
 
                 SemanticToken::Text(lines) => {
-                    // TODO: if lines length > 1 and current_menu is not None - stop filling menu
                     if let Some(menu) = &mut current_menu {
-                        if lines.is_empty() {
-                            panic!("0 lines in text token")
-                        } else {
-                            let title = lines[0].clone();
-                            let left = &lines[1..];
-
-                            if menu.title.is_none() {
-                                menu.title = Some(title);
-                            // FIXME: .unwrap() & .clone()
-                            } else if let Some(option) = &mut current_option {
-                                option.title = Some(title);
-                            } else {
-                                panic!("text after `menu` tag is not allowed cause menu is final statement of section");
-                            }
+                        let (title, left) = match lines.split_first() {
+                            Some((title, left)) => (title, left),
+                            None => continue,
+                        };
 
-                            if !left.is_empty() {
-                                if let Some(option) = &current_option {
-                                    menu.options.push(option.clone());
-                                    current_lines.push(Line::Menu(menu.clone()));
-                                    current_menu = None;
-                                }
-                            }
+                        if !left.is_empty() {
+                            return Err(ParseError::new(ParseErrorKind::TextAfterMenu, span));
                         }
+
+                        let (title, id) = split_line_id(title);
+                        let (title_slot, id_slot) = match &mut current_option {
+                            None if menu.title.is_none() => (&mut menu.title, &mut menu.id),
+                            Some(option) if option.title.is_none() => {
+                                (&mut option.title, &mut option.id)
+                            }
+                            _ => return Err(ParseError::new(ParseErrorKind::TextAfterMenu, span)),
+                        };
+                        use_line_id(&mut line_ids, &id, span)?;
+                        *title_slot = Some(title.to_owned());
+                        *id_slot = id;
                     } else {
-                        current_lines.push(Line::Phrase {
-                            speaker: current_mention.clone(),
-                            lines,
-                        });
+                        let (last, first) = lines.split_last().expect("text is not empty");
+                        let (last, id) = split_line_id(last);
+                        use_line_id(&mut line_ids, &id, span)?;
+
+                        let lines = first
+                            .iter()
+                            .map(String::as_str)
+                            .chain(std::iter::once(last))
+                            .map(str::parse)
+                            .collect::<Result<_, _>>()
+                            .map_err(|kind| ParseError::new(kind, span))?;
+
+                        let position =
+                            next_line_position(&self.sections, &current_section, &blocks);
+                        source_map.lines.insert(position, span);
+                        target_lines(&mut self.sections, &current_section, &mut blocks).push(
+                            Line::Phrase {
+                                speaker: current_mention.clone(),
+                                lines,
+                                id,
+                            },
+                        );
                     }
                 }
                 SemanticToken::Command(command, args) => match command.as_str() {
                     "menu" => {
+                        let variable = match args.trim() {
+                            "" => None,
+                            name if is_ident(name) => Some(name.to_owned()),
+                            name => {
+                                return Err(ParseError::new(
+                                    ParseErrorKind::InvalidVariable(name.to_owned()),
+                                    span,
+                                ))
+                            }
+                        };
+
+                        let position =
+                            next_line_position(&self.sections, &current_section, &blocks);
+                        source_map.lines.insert(position.clone(), span);
+                        menu_position = position;
+
                         current_menu = Some(Menu {
+                            variable,
                             title: None,
+                            id: None,
                             options: vec![],
                         });
                     }
                     "opt" => {
                         if let Some(menu) = &mut current_menu {
-                            if let Some(option) = &current_option {
-                                menu.options.push(option.clone());
+                            if let Some(option) = current_option.take() {
+                                menu.options.push(option);
+                            }
+                            let effects = Effect::parse_list(&args).map_err(|e| {
+                                ParseError::new(ParseErrorKind::InvalidEffect(e), span)
+                            })?;
+                            for effect in &effects {
+                                match effect {
+                                    Effect::Speaker(speaker) => {
+                                        self.ensure_character_requirement(speaker)
+                                    }
+                                    Effect::Assign(_) if menu.variable.is_none() => {
+                                        return Err(ParseError::new(
+                                            ParseErrorKind::AssignWithoutVariable,
+                                            span,
+                                        ));
+                                    }
+                                    _ => {}
+                                }
                             }
-                            current_option = Some(MenuOption { title: None, args });
+
+                            let option_index =
+                                menu.options.len() + usize::from(current_option.is_some());
+                            source_map
+                                .options
+                                .insert((menu_position.clone(), option_index), span);
+
+                            current_option = Some(MenuOption {
+                                title: None,
+                                id: None,
+                                effects,
+                            });
+                        } else {
+                            return Err(ParseError::new(ParseErrorKind::OptionWithoutMenu, span));
+                        }
+                    }
+                    "if" => {
+                        if current_menu.is_some() {
+                            return Err(ParseError::new(ParseErrorKind::TextAfterMenu, span));
+                        }
+
+                        blocks.push(OpenBlock {
+                            branches: vec![],
+                            has_else: false,
+                            speaker: current_mention.clone(),
+                            span,
+                        });
+                        pending_command = Some((command, span));
+                    }
+                    "move" | "back" | "event" => {
+                        if current_menu.is_some() {
+                            return Err(ParseError::new(ParseErrorKind::TextAfterMenu, span));
+                        }
+
+                        if command != "back" {
+                            pending_command = Some((command, span));
                         } else {
-                            panic!("`opt` without the preceding `menu`");
+                            let position =
+                                next_line_position(&self.sections, &current_section, &blocks);
+                            source_map.lines.insert(position, span);
+                            target_lines(&mut self.sections, &current_section, &mut blocks)
+                                .push(Line::Back);
+                        }
+                    }
+                    "include" => {
+                        if current_menu.is_some() {
+                            return Err(ParseError::new(ParseErrorKind::TextAfterMenu, span));
+                        }
+                        // included files are loaded unconditionally
+                        if !blocks.is_empty() {
+                            return Err(ParseError::new(
+                                ParseErrorKind::UnexpectedCommand(command),
+                                span,
+                            ));
+                        }
+
+                        pending_command = Some((command, span));
+                    }
+                    "elseif" | "else" | "endif" => {
+                        let menu = finish_menu(&mut current_menu, &mut current_option);
+                        let block = match blocks.last_mut() {
+                            Some(block) if !(block.has_else && command != "endif") => block,
+                            _ => {
+                                return Err(ParseError::new(
+                                    ParseErrorKind::UnexpectedCommand(command),
+                                    span,
+                                ))
+                            }
+                        };
+
+                        if let (Some(menu), Some(branch)) = (menu, block.branches.last_mut()) {
+                            branch.lines.push(menu);
+                        }
+                        current_mention = block.speaker.clone();
+
+                        match command.as_str() {
+                            "elseif" => pending_command = Some((command, span)),
+                            "else" => {
+                                block.has_else = true;
+                                block.branches.push(Branch {
+                                    condition: None,
+                                    lines: vec![],
+                                });
+                            }
+                            _ => {
+                                let block = blocks.pop().expect("block is checked above");
+                                let position =
+                                    next_line_position(&self.sections, &current_section, &blocks);
+                                source_map.lines.insert(position, block.span);
+                                target_lines(&mut self.sections, &current_section, &mut blocks)
+                                    .push(Line::Conditional(block.branches));
+                            }
                         }
                     }
                     _ => {}
                 },
-                SemanticToken::InlineBlock(v) => {
-                    panic!("inline block `{}` is not yet implemented :(", v);
-                }
             }
         }
 
-        if let Some(mut menu) = current_menu {
-            if let Some(option) = current_option {
-                menu.options.push(option);
-            }
+        if let Some((command, span)) = pending_command {
+            return Err(missing_argument(command, span));
+        }
+
+        if let Some(block) = blocks.last() {
+            return Err(ParseError::new(
+                ParseErrorKind::UnclosedCondition,
+                block.span,
+            ));
+        }
 
+        if let Some(menu) = finish_menu(&mut current_menu, &mut current_option) {
             let lines = self.sections.entry(current_section).or_default();
-            lines.push(Line::Menu(menu));
+            lines.push(menu);
         }
 
         Ok(())
     }
 }
 
-impl FromStr for Dialog {
-    type Err = String;
-    fn from_str(raw: &str) -> Result<Dialog, Self::Err> {
-        let mut lex = Lexer::<Token>::new(raw);
+/// Splits text into phrases separated by blank lines. Every non-empty line of text becomes a part of phrase.
+/// Comments are cut out of text, lines with only comments are skipped and don't separate phrases
+fn push_text(
+    semantics: &mut Vec<(SemanticToken, Span)>,
+    index: &LineIndex,
+    text: &str,
+    offset: usize,
+    comments: &[Range<usize>],
+) {
+    let mut lines = vec![];
+    let mut start = offset;
+    let mut end = offset;
+
+    let mut position = offset;
+    for line in text.split_inclusive('\n') {
+        let line_range = position..position + line.len();
+        let mut content = String::new();
+        let mut has_comment = false;
+        let mut cursor = line_range.start;
+        // comments are sorted and don't overlap, so the ones crossing the line go in a row
+        let first = comments.partition_point(|comment| comment.end <= line_range.start);
+        for comment in comments[first..]
+            .iter()
+            .take_while(|comment| comment.start < line_range.end)
+        {
+            has_comment = true;
+            let cut = comment.start.max(line_range.start);
+            join_text(&mut content, &text[cursor - offset..cut - offset]);
+            cursor = comment.end.min(line_range.end);
+        }
+        join_text(
+            &mut content,
+            &text[cursor - offset..line_range.end - offset],
+        );
+
+        let trimmed = content.trim();
+        if trimmed.is_empty() {
+            if !has_comment && !lines.is_empty() {
+                semantics.push((
+                    SemanticToken::Text(std::mem::take(&mut lines)),
+                    index.span(start..end),
+                ));
+            }
+        } else {
+            let leading = line.len() - line.trim_start().len();
+            if lines.is_empty() {
+                start = position + leading;
+            }
+            end = position + line.trim_end().len();
+            lines.push(trimmed.to_owned());
+        }
 
-        let mut buf = String::new();
-        let mut semantics = vec![];
-        while let Some(token) = lex.next() {
-            let value = lex.slice().trim();
-
-            if let Token::Text = token {
-                buf.push_str(lex.slice());
-            } else if !buf.is_empty() {
-                let lines = buf
-                    .lines()
-                    .collect::<Vec<_>>()
-                    .chunks(2)
-                    .filter_map(|e| {
-                        let str = e.join("\n").trim().to_owned();
-                        if str.is_empty() {
-                            None
-                        } else {
-                            Some(str)
-                        }
-                    })
-                    .collect::<Vec<_>>();
+        position += line.len();
+    }
 
-                if !lines.is_empty() {
-                    semantics.push(SemanticToken::Text(lines));
-                }
-                buf.clear();
-            }
+    if !lines.is_empty() {
+        semantics.push((SemanticToken::Text(lines), index.span(start..end)));
+    }
+}
 
-            let semantic_token = match token {
-                Token::Mention => {
-                    Some(if value.len() == 1 {
-                        SemanticToken::Mention(MentionToken::Narrator)
-                    } else {
-                        let splitted = value[1..].split(':').collect::<Vec<_>>();
-                        match splitted.len() {
-                            1 => SemanticToken::Mention(MentionToken::Name(splitted[0].into())), // no state?
-                            2 => {
-                                if splitted[0].trim().is_empty() {
-                                    SemanticToken::Mention(MentionToken::State(splitted[1].into()))
-                                } else {
-                                    SemanticToken::Mention(MentionToken::NameState(
-                                        splitted[0].into(),
-                                        splitted[1].into(),
-                                    ))
-                                }
-                            }
-                            _ => panic!("Ты чево наделол"),
-                        }
-                    })
-                }
-                Token::InlineBlock => Some(SemanticToken::InlineBlock(String::from(value))),
-                Token::Link => Some(SemanticToken::Link(String::from(&value[1..]))),
-                Token::Command => {
-                    if let Some(index) = value.find('(') {
-                        let name = &value[1..index];
-                        let args = &value[index + 1..value.len() - 1];
-
-                        Some(SemanticToken::Command(name.to_string(), args.to_string()))
-                    } else {
-                        Some(SemanticToken::Command(value[1..].into(), String::new()))
-                    }
-                }
-                _ => None,
-            };
+/// Appends part of text that was separated by a comment. Whitespace around the comment becomes a single space
+fn join_text(content: &mut String, part: &str) {
+    if content.is_empty() {
+        content.push_str(part);
+    } else if content.ends_with(char::is_whitespace) || part.starts_with(char::is_whitespace) {
+        content.truncate(content.trim_end().len());
+        content.push(' ');
+        content.push_str(part.trim_start());
+    } else {
+        content.push_str(part);
+    }
+}
 
-            if let Some(token) = semantic_token {
-                semantics.push(token);
-            }
-        }
+fn parse_mention(value: &str) -> Result<MentionToken, ParseErrorKind> {
+    if value.len() == 1 {
+        return Ok(MentionToken::Narrator);
+    }
 
-        if !buf.is_empty() {
-            let lines = buf
-                .lines()
-                .collect::<Vec<_>>()
-                .chunks(2)
-                .filter_map(|e| {
-                    let str = e.join("\n").trim().to_owned();
-                    if str.is_empty() {
-                        None
-                    } else {
-                        Some(str)
-                    }
-                })
-                .collect::<Vec<_>>();
+    let splitted = value[1..].split(':').collect::<Vec<_>>();
+    match splitted[..] {
+        [name] => Ok(MentionToken::Name(name.into())), // no state?
+        [name, state] if name.trim().is_empty() => Ok(MentionToken::State(state.into())),
+        [name, state] => Ok(MentionToken::NameState(name.into(), state.into())),
+        _ => Err(ParseErrorKind::InvalidMention(value.to_owned())),
+    }
+}
 
-            if !lines.is_empty() {
-                semantics.push(SemanticToken::Text(lines));
-            }
-            buf.clear();
+/// FNV-1a hasher. Unlike `DefaultHasher` its output does not depend on the version of Rust
+pub(crate) struct FingerprintHasher(u64);
+
+impl Default for FingerprintHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for FingerprintHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
         }
+    }
 
-        let mut dlg: Dialog = Dialog::default();
+    // lengths and enum discriminants are hashed with the same width on every platform
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
 
-        dlg.parse_semantics(semantics)?;
+    fn write_isize(&mut self, i: isize) {
+        self.write_i64(i as i64);
+    }
+}
 
-        Ok(dlg)
+impl FromStr for Dialog {
+    type Err = ParseError;
+    fn from_str(raw: &str) -> Result<Dialog, Self::Err> {
+        Dialog::parse(raw, false).map(|(dialog, _, _)| dialog)
     }
 }
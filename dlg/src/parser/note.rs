@@ -0,0 +1,21 @@
+use super::{Dialog, Line, LinePosition, Span};
+
+/// Comment kept as a writer note by [`Dialog::parse_with_notes`]. It belongs to the first line of dialog that starts after it
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Note {
+    /// Text of comment without `//` or `/* */`
+    pub text: String,
+    /// Position of comment in source
+    pub span: Span,
+    /// Position of the line the note belongs to
+    pub position: LinePosition,
+}
+
+impl Note {
+    /// Line the note belongs to. `None` if there are no lines after the note
+    #[must_use]
+    pub fn line<'a>(&self, dialog: &'a Dialog) -> Option<&'a Line> {
+        dialog.get_line(&self.position)
+    }
+}
@@ -0,0 +1,225 @@
+use std::fmt::{Display, Write};
+
+use super::{
+    Branch, Condition, Dialog, Effect, Line, LinePosition, Menu, Note, Section, Speaker, State,
+    Value,
+};
+
+const INDENT: &str = "    ";
+
+impl Dialog {
+    /// Canonical `.dlg` source of the dialog. Parsing it gives an equal dialog
+    #[must_use]
+    pub fn to_dlg_string(&self) -> String {
+        self.to_dlg_string_with_notes(&[])
+    }
+
+    /// Canonical `.dlg` source with writer notes as comments before the lines they belong to
+    #[must_use]
+    pub fn to_dlg_string_with_notes(&self, notes: &[Note]) -> String {
+        let mut printer = Printer {
+            out: String::new(),
+            notes,
+            speaker: Speaker::Narrator,
+        };
+
+        for path in &self.includes {
+            writeln!(printer.out, ":include {}", Value::String(path.clone()))
+                .expect("writing to string never fails");
+        }
+
+        for (section, lines) in &self.sections {
+            if *section == Section::Initial && !lines.is_empty() {
+                printer.separate();
+            }
+            if let Section::Named(name) = section {
+                printer.separate();
+                writeln!(printer.out, "#{}", name).expect("writing to string never fails");
+            }
+
+            printer.speaker = Speaker::Narrator;
+            printer.lines(lines, section, &[], "");
+        }
+
+        printer.out
+    }
+}
+
+impl Display for Dialog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_dlg_string())
+    }
+}
+
+struct Printer<'a> {
+    out: String,
+    notes: &'a [Note],
+    /// Speaker of the next phrase without mention
+    speaker: Speaker,
+}
+
+impl Printer<'_> {
+    /// Puts a blank line between statements
+    fn separate(&mut self) {
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+    }
+
+    fn line(&mut self, indent: &str, text: &str) {
+        self.out.push_str(indent);
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn notes(&mut self, position: &LinePosition, indent: &str) {
+        for note in self.notes.iter().filter(|note| &note.position == position) {
+            let comment = if note.text.contains('\n') {
+                format!("/* {} */", note.text)
+            } else {
+                format!("// {}", note.text)
+            };
+            self.out.push_str(indent);
+            self.out.push_str(comment.trim_end());
+            self.out.push('\n');
+        }
+    }
+
+    fn lines(
+        &mut self,
+        lines: &[Line],
+        section: &Section,
+        blocks: &[(usize, usize)],
+        indent: &str,
+    ) {
+        for line_index in 0..=lines.len() {
+            let position = LinePosition {
+                section: section.clone(),
+                blocks: blocks.to_vec(),
+                line_index,
+            };
+            let line = match lines.get(line_index) {
+                Some(line) => line,
+                None => {
+                    self.notes(&position, indent);
+                    break;
+                }
+            };
+
+            if line_index > 0 {
+                self.separate();
+            }
+            self.notes(&position, indent);
+
+            match line {
+                Line::Phrase { speaker, lines, id } => {
+                    let mut texts = lines.iter().map(ToString::to_string).collect::<Vec<_>>();
+                    if let (Some(last), Some(id)) = (texts.last_mut(), id) {
+                        write!(last, " [#{}]", id).expect("writing to string never fails");
+                    }
+                    let mut texts = texts.into_iter();
+                    let first = texts.next().unwrap_or_default();
+                    // without the mention such text would be read as a command, a mention or a section
+                    let sigil = first.starts_with([':', '@', '#']);
+                    if *speaker == self.speaker && !sigil {
+                        self.line(indent, &first);
+                    } else {
+                        self.line(indent, &format!("{} {}", mention(speaker), first));
+                        self.speaker = speaker.clone();
+                    }
+                    for text in texts {
+                        self.line(indent, &text);
+                    }
+                }
+                Line::Menu(menu) => self.menu(menu, indent),
+                Line::Conditional(branches) => {
+                    self.conditional(branches, &position, indent);
+                }
+                Line::Move(section) => self.line(indent, &format!(":move {}", section)),
+                Line::Back => self.line(indent, ":back"),
+                Line::Event { name, args } => {
+                    let mut event = format!(":event \"{}\"", name);
+                    for arg in args {
+                        write!(event, " {}", arg).expect("writing to string never fails");
+                    }
+                    self.line(indent, &event);
+                }
+            }
+        }
+    }
+
+    fn menu(&mut self, menu: &Menu, indent: &str) {
+        let mut command = String::from(":menu");
+        if let Some(variable) = &menu.variable {
+            write!(command, "({})", variable).expect("writing to string never fails");
+        }
+        if let Some(title) = &menu.title {
+            write!(command, " {}", with_id(title, &menu.id))
+                .expect("writing to string never fails");
+        }
+        self.line(indent, &command);
+
+        for option in &menu.options {
+            let effects = option
+                .effects
+                .iter()
+                .map(Effect::to_string)
+                .collect::<Vec<_>>();
+            let mut command = format!(":opt({})", effects.join("; "));
+            if let Some(title) = &option.title {
+                write!(command, " {}", with_id(title, &option.id))
+                    .expect("writing to string never fails");
+            }
+            self.line(indent, &command);
+        }
+    }
+
+    fn conditional(&mut self, branches: &[Branch], position: &LinePosition, indent: &str) {
+        let speaker = self.speaker.clone();
+        let inner = format!("{}{}", indent, INDENT);
+
+        for (branch_index, branch) in branches.iter().enumerate() {
+            let command = match (&branch.condition, branch_index) {
+                (Some(condition), 0) => format!(":if {}", top_level(condition)),
+                (Some(condition), _) => format!(":elseif {}", top_level(condition)),
+                (None, _) => String::from(":else"),
+            };
+            self.line(indent, &command);
+
+            // every branch starts with the speaker that was set before `:if`
+            self.speaker = speaker.clone();
+            let mut blocks = position.blocks.clone();
+            blocks.push((position.line_index, branch_index));
+            self.lines(&branch.lines, &position.section, &blocks, &inner);
+        }
+
+        self.line(indent, ":endif");
+        self.speaker = speaker;
+    }
+}
+
+/// Condition without parentheses around the whole of it
+fn top_level(condition: &Condition) -> String {
+    match condition {
+        Condition::And(a, b) => format!("{} && {}", a, b),
+        Condition::Or(a, b) => format!("{} || {}", a, b),
+        condition => condition.to_string(),
+    }
+}
+
+/// Title with `[#id]` after it
+fn with_id(title: &str, id: &Option<String>) -> String {
+    match id {
+        Some(id) => format!("{} [#{}]", title, id),
+        None => title.to_owned(),
+    }
+}
+
+/// Mention that sets the speaker: `@`, `@alice` or `@alice:calm`
+fn mention(speaker: &Speaker) -> String {
+    match speaker {
+        Speaker::Narrator => String::from("@"),
+        Speaker::Character(alias, State::Default) => format!("@{}", alias.0),
+        Speaker::Character(alias, State::Named(state)) => format!("@{}:{}", alias.0, state),
+    }
+}
@@ -1,5 +1,7 @@
 use std::fmt::Display;
 
+use super::value::is_ident;
+
 /// Section in dialog
 #[derive(Clone, Eq, Hash, PartialEq, Debug)]
 pub enum Section {
@@ -17,3 +19,45 @@ impl Display for Section {
         }
     }
 }
+
+impl Section {
+    /// Name of the included file for links like `#file::section`. Empty for links to the root file like `#::section`
+    #[must_use]
+    pub fn namespace(&self) -> Option<&str> {
+        match self {
+            Section::Initial => None,
+            Section::Named(name) => name.split_once("::").map(|(namespace, _)| namespace),
+        }
+    }
+}
+
+/// Section name: identifier, optionally qualified with names of files: `file::section`, or with `::` for the root file
+pub(crate) fn is_section_name(name: &str) -> bool {
+    name.strip_prefix("::")
+        .unwrap_or(name)
+        .split("::")
+        .all(is_ident)
+}
+
+// sections are map keys in `Dialog`, so they are stored as strings: `initial` or `#name`
+#[cfg(feature = "serde")]
+impl serde::Serialize for Section {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Section {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        match raw.strip_prefix('#') {
+            Some(name) => Ok(Section::Named(name.to_owned())),
+            None if raw == "initial" => Ok(Section::Initial),
+            None => Err(serde::de::Error::invalid_value(
+                serde::de::Unexpected::Str(&raw),
+                &"`initial` or `#name`",
+            )),
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use std::collections::HashMap;
+
+use super::{Section, Span};
+
+/// Position of a line in dialog
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LinePosition {
+    /// Section of the line
+    pub section: Section,
+    /// Index of conditional line and index of its branch for every block around the line, from the outermost to the innermost
+    pub blocks: Vec<(usize, usize)>,
+    /// Index of the line in its section or branch
+    pub line_index: usize,
+}
+
+/// Where parts of dialog are written in source
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct SourceMap {
+    /// `#section` declarations, the first one if section is declared several times.
+    /// Initial section is declared at the start of source
+    pub sections: HashMap<Section, Span>,
+    /// Start of every line: the first part of phrase, `:menu`, `:if`, `:move`, `:back` or `:event`
+    pub lines: HashMap<LinePosition, Span>,
+    /// `:opt` commands by position of their menu and index of option
+    pub options: HashMap<(LinePosition, usize), Span>,
+    /// `:include` commands in the order of [`Dialog::includes`](super::Dialog::includes)
+    pub includes: Vec<Span>,
+}
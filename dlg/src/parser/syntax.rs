@@ -0,0 +1,483 @@
+use std::{fmt::Display, ops::Range};
+
+use logos::Lexer;
+
+use super::{tokens::Token, Dialog, ParseError};
+
+/// Kind of a token in syntax tree
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SyntaxKind {
+    /// `@alice:calm` at the start of a line
+    Mention,
+    /// `#section` at the start of a line or right after a command
+    Link,
+    /// `:menu(var)` at the start of a line
+    Command,
+    /// `{expression}` inside text
+    InlineBlock,
+    /// Text of a line without whitespace around it
+    Text,
+    /// `// comment` or `/* comment */`
+    Comment,
+    /// `/*` without matching `*/`. Lasts till the end of source
+    UnclosedComment,
+    /// Spaces and tabs
+    Whitespace,
+    /// `\n` or `\r\n`
+    Newline,
+}
+
+/// Kind of a node in syntax tree. Nodes follow the layout of source and don't check it,
+/// so misplaced commands still get their own nodes
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NodeKind {
+    /// Whole source
+    Dialog,
+    /// Lines before the first `#section` or from `#section` till the next one
+    Section,
+    /// Mention and lines of text after it till a blank line
+    Phrase,
+    /// `:menu` with its title and options
+    Menu,
+    /// `:opt` with its title
+    MenuOption,
+    /// `:if` with all its branches and `:endif`
+    Conditional,
+    /// `:if`, `:elseif` or `:else` with lines after it
+    Branch,
+    /// Any other command line: `:move`, `:back`, `:event`
+    Command,
+}
+
+/// Piece of source with its kind
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SyntaxToken {
+    /// Kind of token
+    pub kind: SyntaxKind,
+    /// Exact text of token
+    pub text: String,
+}
+
+/// Child of a node
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SyntaxElement {
+    /// Nested node
+    Node(SyntaxNode),
+    /// Token
+    Token(SyntaxToken),
+}
+
+/// Group of tokens and nodes
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SyntaxNode {
+    /// Kind of node
+    pub kind: NodeKind,
+    /// Tokens and nodes in source order
+    pub children: Vec<SyntaxElement>,
+}
+
+/// Lossless syntax tree of a `.dlg` source. Every byte of source belongs to a token,
+/// so printing the tree gives the source back, with edits if the tree was changed
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SyntaxTree {
+    /// Node of [`NodeKind::Dialog`]
+    pub root: SyntaxNode,
+}
+
+impl SyntaxTree {
+    /// Builds the tree of source. It never fails, errors are reported by [`SyntaxTree::lower`]
+    #[must_use]
+    pub fn parse(raw: &str) -> Self {
+        let tokens = lex(raw)
+            .into_iter()
+            .map(|(kind, range)| SyntaxToken {
+                kind,
+                text: raw[range].to_owned(),
+            })
+            .collect();
+
+        Self {
+            root: build(tokens),
+        }
+    }
+
+    /// Parses dialog from tokens of the tree. Kinds of tokens are taken as they are in the tree
+    pub fn lower(&self) -> Result<Dialog, ParseError> {
+        let source = self.to_string();
+        let mut position = 0;
+        let tokens = self
+            .tokens()
+            .into_iter()
+            .map(|token| {
+                let range = position..position + token.text.len();
+                position = range.end;
+                (token.kind, range)
+            })
+            .collect();
+
+        Dialog::lower(&source, tokens, false).map(|(dialog, _, _)| dialog)
+    }
+
+    /// All tokens in source order
+    #[must_use]
+    pub fn tokens(&self) -> Vec<&SyntaxToken> {
+        self.root.tokens()
+    }
+}
+
+impl SyntaxNode {
+    fn new(kind: NodeKind) -> Self {
+        Self {
+            kind,
+            children: vec![],
+        }
+    }
+
+    /// All tokens of node and nested nodes in source order
+    #[must_use]
+    pub fn tokens(&self) -> Vec<&SyntaxToken> {
+        let mut tokens = vec![];
+        self.collect_tokens(&mut tokens);
+        tokens
+    }
+
+    fn collect_tokens<'a>(&'a self, tokens: &mut Vec<&'a SyntaxToken>) {
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => node.collect_tokens(tokens),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+    }
+
+    /// Nested nodes of node, not including itself, in source order
+    #[must_use]
+    pub fn descendants(&self) -> Vec<&SyntaxNode> {
+        let mut nodes = vec![];
+        self.collect_nodes(&mut nodes);
+        nodes
+    }
+
+    fn collect_nodes<'a>(&'a self, nodes: &mut Vec<&'a SyntaxNode>) {
+        for child in &self.children {
+            if let SyntaxElement::Node(node) = child {
+                nodes.push(node);
+                node.collect_nodes(nodes);
+            }
+        }
+    }
+
+    /// Mutable tokens of node and nested nodes in source order. Use it to edit the tree
+    pub fn tokens_mut(&mut self) -> Vec<&mut SyntaxToken> {
+        let mut tokens = vec![];
+        for child in &mut self.children {
+            match child {
+                SyntaxElement::Node(node) => tokens.append(&mut node.tokens_mut()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+        tokens
+    }
+
+    /// Length of node text in bytes
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.tokens().iter().map(|token| token.text.len()).sum()
+    }
+
+    /// Node has no text
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Display for SyntaxNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for token in self.tokens() {
+            write!(f, "{}", token.text)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for SyntaxElement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyntaxElement::Node(node) => write!(f, "{}", node),
+            SyntaxElement::Token(token) => write!(f, "{}", token.text),
+        }
+    }
+}
+
+impl Display for SyntaxTree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.root)
+    }
+}
+
+/// Splits source into tokens. Ranges of tokens cover the whole source without gaps
+pub(crate) fn lex(raw: &str) -> Vec<(SyntaxKind, Range<usize>)> {
+    let mut lex = Lexer::<Token>::new(raw);
+    let mut tokens = vec![];
+    let mut comments = vec![];
+    // text is split into whitespace, newlines and text when it ends
+    let mut text: Option<Range<usize>> = None;
+
+    while let Some(token) = lex.next() {
+        let range = lex.span();
+
+        let kind = match token {
+            Token::Mention | Token::Link | Token::Command
+                if !is_token_boundary(raw, range.start, &token, &tokens, &comments) =>
+            {
+                None
+            }
+            Token::Mention => Some(SyntaxKind::Mention),
+            Token::Link => Some(SyntaxKind::Link),
+            Token::Command => Some(SyntaxKind::Command),
+            Token::InlineBlock => Some(SyntaxKind::InlineBlock),
+            Token::SinglelineComment | Token::MultilineComment(true) => Some(SyntaxKind::Comment),
+            Token::MultilineComment(false) => Some(SyntaxKind::UnclosedComment),
+            Token::Text => None,
+        };
+
+        match kind {
+            None => text = Some(text.map_or(range.clone(), |text| text.start..range.end)),
+            Some(kind) => {
+                if let Some(text) = text.take() {
+                    push_text(raw, text, &mut tokens);
+                }
+                if let SyntaxKind::Comment | SyntaxKind::UnclosedComment = kind {
+                    comments.push(range.clone());
+                }
+                tokens.push((kind, range));
+            }
+        }
+    }
+
+    if let Some(text) = text {
+        push_text(raw, text, &mut tokens);
+    }
+
+    tokens
+}
+
+/// Splits text into lines, and lines into indentation, text and trailing whitespace
+fn push_text(raw: &str, range: Range<usize>, tokens: &mut Vec<(SyntaxKind, Range<usize>)>) {
+    let mut position = range.start;
+
+    for line in raw[range].split_inclusive('\n') {
+        let newline = if line.ends_with("\r\n") {
+            2
+        } else {
+            usize::from(line.ends_with('\n'))
+        };
+        let content = &line[..line.len() - newline];
+        let text = content.trim();
+        let leading = content.len() - content.trim_start().len();
+
+        let mut push = |kind, len| {
+            if len > 0 {
+                tokens.push((kind, position..position + len));
+                position += len;
+            }
+        };
+        if text.is_empty() {
+            push(SyntaxKind::Whitespace, content.len());
+        } else {
+            push(SyntaxKind::Whitespace, leading);
+            push(SyntaxKind::Text, text.len());
+            push(SyntaxKind::Whitespace, content.len() - leading - text.len());
+        }
+        push(SyntaxKind::Newline, newline);
+    }
+}
+
+/// Mentions, links and commands are tokens only at the start of a line (after indentation and comments).
+/// A link is also a token right after a command on the same line, like in `:move #section`.
+/// Otherwise `@`, `#` and `:` are a part of text
+fn is_token_boundary(
+    raw: &str,
+    start: usize,
+    token: &Token,
+    tokens: &[(SyntaxKind, Range<usize>)],
+    comments: &[Range<usize>],
+) -> bool {
+    let line_start = raw[..start].rfind('\n').map_or(0, |i| i + 1);
+    let mut position = line_start;
+    let mut before = String::new();
+    // comments are sorted and don't overlap, so the ones ending on this line go in a row
+    let first = comments.partition_point(|c| c.end <= line_start);
+    for comment in comments[first..].iter().take_while(|c| c.end <= start) {
+        before.push_str(&raw[position..comment.start.max(position)]);
+        position = comment.end;
+    }
+    before.push_str(&raw[position..start]);
+
+    if before.trim().is_empty() {
+        return true;
+    }
+
+    let previous = tokens
+        .iter()
+        .rev()
+        .find(|(kind, _)| *kind != SyntaxKind::Whitespace);
+    match (token, previous) {
+        (Token::Link, Some((SyntaxKind::Command, range))) => {
+            raw[range.end..start].trim().is_empty() && !raw[range.end..start].contains('\n')
+        }
+        _ => false,
+    }
+}
+
+/// Groups tokens into nodes line by line
+fn build(tokens: Vec<SyntaxToken>) -> SyntaxNode {
+    let mut builder = TreeBuilder {
+        stack: vec![
+            SyntaxNode::new(NodeKind::Dialog),
+            SyntaxNode::new(NodeKind::Section),
+        ],
+    };
+
+    let mut line = vec![];
+    for token in tokens {
+        let newline = token.kind == SyntaxKind::Newline;
+        line.push(token);
+        if newline {
+            builder.line(std::mem::take(&mut line));
+        }
+    }
+    if !line.is_empty() {
+        builder.line(line);
+    }
+
+    builder.close_to(NodeKind::Dialog);
+    builder.stack.pop().expect("dialog node is never closed")
+}
+
+struct TreeBuilder {
+    /// Open nodes from the root to the innermost one
+    stack: Vec<SyntaxNode>,
+}
+
+impl TreeBuilder {
+    fn top(&self) -> NodeKind {
+        self.stack.last().expect("dialog node is never closed").kind
+    }
+
+    fn open(&mut self, kind: NodeKind) {
+        self.stack.push(SyntaxNode::new(kind));
+    }
+
+    fn close(&mut self) {
+        let node = self.stack.pop().expect("dialog node is never closed");
+        self.stack
+            .last_mut()
+            .expect("dialog node is never closed")
+            .children
+            .push(SyntaxElement::Node(node));
+    }
+
+    /// Closes nodes till the innermost one of the kinds
+    fn close_while(&mut self, kinds: &[NodeKind]) {
+        while kinds.contains(&self.top()) {
+            self.close();
+        }
+    }
+
+    fn close_to(&mut self, kind: NodeKind) {
+        while self.top() != kind && self.stack.len() > 1 {
+            self.close();
+        }
+    }
+
+    fn append(&mut self, line: Vec<SyntaxToken>) {
+        self.stack
+            .last_mut()
+            .expect("dialog node is never closed")
+            .children
+            .extend(line.into_iter().map(SyntaxElement::Token));
+    }
+
+    fn line(&mut self, line: Vec<SyntaxToken>) {
+        use NodeKind::*;
+
+        let first = line.iter().find(|token| {
+            !matches!(
+                token.kind,
+                SyntaxKind::Whitespace | SyntaxKind::Newline | SyntaxKind::Comment
+            )
+        });
+        let in_conditional = self.stack.iter().any(|node| node.kind == Branch);
+
+        match first.map(|token| (token.kind, command_name(&token.text))) {
+            None => {
+                // lines with only comments don't split phrases
+                if line.iter().all(|token| token.kind != SyntaxKind::Comment) {
+                    self.close_while(&[Phrase]);
+                }
+            }
+            Some((SyntaxKind::Link, _)) => {
+                self.close_to(Dialog);
+                self.open(Section);
+            }
+            Some((SyntaxKind::Mention, _)) => {
+                self.close_while(&[Phrase, MenuOption, Menu]);
+                self.open(Phrase);
+            }
+            Some((SyntaxKind::Command, "menu")) => {
+                self.close_while(&[Phrase, MenuOption, Menu]);
+                self.open(Menu);
+            }
+            Some((SyntaxKind::Command, "opt")) => {
+                self.close_while(&[Phrase, MenuOption]);
+                self.open(MenuOption);
+            }
+            Some((SyntaxKind::Command, "if")) => {
+                self.close_while(&[Phrase, MenuOption, Menu]);
+                self.open(Conditional);
+                self.open(Branch);
+            }
+            Some((SyntaxKind::Command, "elseif" | "else")) if in_conditional => {
+                self.close_to(Branch);
+                self.close();
+                self.open(Branch);
+            }
+            Some((SyntaxKind::Command, "endif")) if in_conditional => {
+                self.close_to(Branch);
+                self.close();
+                self.append(line);
+                self.close();
+                return;
+            }
+            Some((SyntaxKind::Command, _)) => {
+                self.close_while(&[Phrase, MenuOption, Menu]);
+                self.open(Command);
+                self.append(line);
+                self.close();
+                return;
+            }
+            // text continues phrase or is a title of menu or option
+            Some(_) => {
+                if !matches!(self.top(), Phrase | Menu | MenuOption) {
+                    self.open(Phrase);
+                }
+            }
+        }
+
+        self.append(line);
+    }
+}
+
+/// Name of command without `:` and arguments: `menu` for `:menu(var)`
+fn command_name(command: &str) -> &str {
+    let name = command.strip_prefix(':').unwrap_or(command);
+    name.split('(').next().unwrap_or(name)
+}
@@ -0,0 +1,342 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::player::Variables;
+
+use super::{Condition, ParseErrorKind, Value};
+
+/// Animation of text
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TextEffect {
+    /// `[shake]`
+    Shake,
+    /// `[wave]`
+    Wave,
+}
+
+/// Markup tag that styles the text inside it
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Markup {
+    /// `[b]bold[/b]`
+    Bold,
+    /// `[i]italic[/i]`
+    Italic,
+    /// `[color=red]red[/color]`. Color is a name or `#rrggbb`, frontends decide how to show it
+    Color(String),
+    /// `[shake]shaking[/shake]` or `[wave]waving[/wave]`
+    Effect(TextEffect),
+}
+
+/// Part of a phrase
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Segment {
+    /// Plain text
+    Text(String),
+    /// `{var}` or `{expr}`. Replaced with its value when the phrase is shown
+    Expression(Condition),
+    /// Segments inside a markup tag
+    Styled(Markup, Vec<Segment>),
+    /// `[wait=500]`. Typewriter animation stops for this count of milliseconds
+    Wait(u32),
+}
+
+/// Style of shown text. Combination of all markup tags around it
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Style {
+    /// Inside `[b]`
+    pub bold: bool,
+    /// Inside `[i]`
+    pub italic: bool,
+    /// Color of the innermost `[color]`
+    pub color: Option<String>,
+    /// Effect of the innermost `[shake]` or `[wave]`
+    pub effect: Option<TextEffect>,
+}
+
+/// Piece of a phrase ready to be shown
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Fragment {
+    /// Text with its style
+    Text(String, Style),
+    /// Pause of typewriter animation in milliseconds
+    Wait(u32),
+}
+
+/// Text of a phrase with inline blocks and markup: `You have [b]{coins}[/b] coins`
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FormattedText(pub Vec<Segment>);
+
+impl FormattedText {
+    /// Text with values of dialog variables in place of inline blocks and without markup.
+    /// Unset variables are shown as empty text
+    #[must_use]
+    pub fn resolve(&self, variables: &Variables) -> String {
+        self.fragments(variables)
+            .into_iter()
+            .filter_map(|fragment| match fragment {
+                Fragment::Text(text, _) => Some(text),
+                Fragment::Wait(_) => None,
+            })
+            .collect()
+    }
+
+    /// Styled pieces of text with values of dialog variables in place of inline blocks.
+    /// Neighbouring pieces with the same style are merged
+    #[must_use]
+    pub fn fragments(&self, variables: &Variables) -> Vec<Fragment> {
+        let mut fragments = vec![];
+        push_fragments(&mut fragments, &self.0, &Style::default(), variables);
+        fragments
+    }
+}
+
+fn push_fragments(
+    fragments: &mut Vec<Fragment>,
+    segments: &[Segment],
+    style: &Style,
+    variables: &Variables,
+) {
+    for segment in segments {
+        let text = match segment {
+            Segment::Text(text) => text.clone(),
+            Segment::Expression(expression) => match expression.value(variables) {
+                Some(Value::String(value)) => value,
+                Some(value) => value.to_string(),
+                None => continue,
+            },
+            Segment::Styled(markup, inner) => {
+                let mut style = style.clone();
+                match markup {
+                    Markup::Bold => style.bold = true,
+                    Markup::Italic => style.italic = true,
+                    Markup::Color(color) => style.color = Some(color.clone()),
+                    Markup::Effect(effect) => style.effect = Some(*effect),
+                }
+                push_fragments(fragments, inner, &style, variables);
+                continue;
+            }
+            Segment::Wait(ms) => {
+                fragments.push(Fragment::Wait(*ms));
+                continue;
+            }
+        };
+
+        match fragments.last_mut() {
+            Some(Fragment::Text(last, last_style)) if last_style == style => last.push_str(&text),
+            _ if text.is_empty() => {}
+            _ => fragments.push(Fragment::Text(text, style.clone())),
+        }
+    }
+}
+
+/// Parses expression of inline block with braces: `{coins}`
+pub(crate) fn parse_block(block: &str) -> Result<Condition, String> {
+    let expression = block[1..block.len() - 1].trim();
+    expression
+        .parse()
+        .map_err(|e| format!("`{}`: {}", block, e))
+}
+
+/// Appends text to the last text segment or adds a new one
+fn push_text(segments: &mut Vec<Segment>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+
+    match segments.last_mut() {
+        Some(Segment::Text(last)) => last.push_str(text),
+        _ => segments.push(Segment::Text(text.to_owned())),
+    }
+}
+
+/// Deepest nesting of tags. Deeper tags are errors, so a broken file can't overflow the stack
+/// when the text is shown or dropped
+const MAX_NESTING: usize = 256;
+
+/// Names of tags that are closed with `[/name]`. Other `[/name]` are text
+const TAG_NAMES: [&str; 5] = ["b", "i", "color", "shake", "wave"];
+
+impl Markup {
+    /// Name of tag: `b`, `i`, `color`, `shake` or `wave`
+    fn name(&self) -> &'static str {
+        match self {
+            Markup::Bold => "b",
+            Markup::Italic => "i",
+            Markup::Color(_) => "color",
+            Markup::Effect(TextEffect::Shake) => "shake",
+            Markup::Effect(TextEffect::Wave) => "wave",
+        }
+    }
+}
+
+impl From<&str> for FormattedText {
+    fn from(text: &str) -> Self {
+        Self::from(text.to_owned())
+    }
+}
+
+impl From<String> for FormattedText {
+    fn from(text: String) -> Self {
+        if text.is_empty() {
+            Self::default()
+        } else {
+            Self(vec![Segment::Text(text)])
+        }
+    }
+}
+
+impl FromStr for FormattedText {
+    type Err = ParseErrorKind;
+
+    /// Unknown tags like `[sighs]` or `[/sighs]` are kept as text
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        // open tags with segments before them
+        let mut stack: Vec<(Markup, Vec<Segment>)> = vec![];
+        let mut segments = vec![];
+        let mut rest = raw;
+
+        while let Some(open) = rest.find(['{', '[']) {
+            push_text(&mut segments, &rest[..open]);
+            rest = &rest[open..];
+
+            if rest.starts_with('{') {
+                let close = match rest.find('}') {
+                    Some(close) => close,
+                    None => {
+                        return Err(ParseErrorKind::InvalidInlineBlock(format!(
+                            "`{}`: unclosed",
+                            rest
+                        )))
+                    }
+                };
+
+                let expression =
+                    parse_block(&rest[..=close]).map_err(ParseErrorKind::InvalidInlineBlock)?;
+                segments.push(Segment::Expression(expression));
+                rest = &rest[close + 1..];
+                continue;
+            }
+
+            let close = match rest.find(']') {
+                Some(close) => close,
+                None => break,
+            };
+            let tag = &rest[1..close];
+
+            let markup = match tag.split_once('=') {
+                None => match tag {
+                    "b" => Some(Markup::Bold),
+                    "i" => Some(Markup::Italic),
+                    "shake" => Some(Markup::Effect(TextEffect::Shake)),
+                    "wave" => Some(Markup::Effect(TextEffect::Wave)),
+                    _ => None,
+                },
+                Some(("color", color)) if !color.is_empty() => {
+                    Some(Markup::Color(color.to_owned()))
+                }
+                Some(("wait", ms)) => match ms.parse() {
+                    Ok(ms) => {
+                        segments.push(Segment::Wait(ms));
+                        rest = &rest[close + 1..];
+                        continue;
+                    }
+                    Err(_) => {
+                        return Err(ParseErrorKind::InvalidMarkup(format!(
+                            "`[{}]`: wait time must be a number of milliseconds",
+                            tag
+                        )))
+                    }
+                },
+                _ => None,
+            };
+
+            if let Some(markup) = markup {
+                if stack.len() == MAX_NESTING {
+                    return Err(ParseErrorKind::InvalidMarkup(format!(
+                        "tags nested deeper than {} levels",
+                        MAX_NESTING
+                    )));
+                }
+                stack.push((markup, std::mem::take(&mut segments)));
+            } else if let Some(name) = tag
+                .strip_prefix('/')
+                .filter(|name| TAG_NAMES.contains(name))
+            {
+                match stack.pop() {
+                    Some((markup, outer)) if markup.name() == name => {
+                        let inner = std::mem::replace(&mut segments, outer);
+                        segments.push(Segment::Styled(markup, inner));
+                    }
+                    Some((markup, _)) => {
+                        return Err(ParseErrorKind::InvalidMarkup(format!(
+                            "`[{}]` closes `[{}]`",
+                            tag,
+                            markup.name()
+                        )))
+                    }
+                    None => {
+                        return Err(ParseErrorKind::InvalidMarkup(format!(
+                            "`[{}]` without opening tag",
+                            tag
+                        )))
+                    }
+                }
+            } else {
+                push_text(&mut segments, &rest[..=close]);
+            }
+
+            rest = &rest[close + 1..];
+        }
+
+        if let Some((markup, _)) = stack.last() {
+            return Err(ParseErrorKind::InvalidMarkup(format!(
+                "unclosed `[{}]`",
+                markup.name()
+            )));
+        }
+
+        push_text(&mut segments, rest);
+
+        Ok(Self(segments))
+    }
+}
+
+impl Display for Markup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Markup::Color(color) => write!(f, "color={}", color),
+            markup => write!(f, "{}", markup.name()),
+        }
+    }
+}
+
+impl Display for Segment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Segment::Text(text) => write!(f, "{}", text),
+            Segment::Expression(expression) => write!(f, "{{{}}}", expression),
+            Segment::Styled(markup, inner) => {
+                write!(f, "[{}]", markup)?;
+                for segment in inner {
+                    write!(f, "{}", segment)?;
+                }
+                write!(f, "[/{}]", markup.name())
+            }
+            Segment::Wait(ms) => write!(f, "[wait={}]", ms),
+        }
+    }
+}
+
+impl Display for FormattedText {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for segment in &self.0 {
+            write!(f, "{}", segment)?;
+        }
+        Ok(())
+    }
+}
@@ -1,36 +1,72 @@
 use logos::Logos;
 
 // ident regex: [a-zA-Z_][a-zA-Z_0-9]*
+// mentions, links and commands are matched anywhere, `is_token_boundary` filters out the ones in the middle of a line
 
 #[derive(Logos, Debug, PartialEq)]
 pub(crate) enum Token {
-    #[regex(r"\s@([a-zA-Z_][a-zA-Z_0-9]*)?:?([a-zA-Z_][a-zA-Z_0-9]*)?")]
-    // for handle @-ident: \s@(([a-zA-Z_][a-zA-Z_0-9]s*)?:?([a-zA-Z_][a-zA-Z_0-9]*)?|-([a-zA-Z_][a-zA-Z_0-9]*)?)
+    // extra `:state` parts are matched too, so `@alice:calm:sad` is reported as an invalid mention
+    #[regex(r"@([a-zA-Z_][a-zA-Z_0-9]*)?(:([a-zA-Z_][a-zA-Z_0-9]*)?)*")]
+    // for handle @-ident: @(([a-zA-Z_][a-zA-Z_0-9]s*)?:?([a-zA-Z_][a-zA-Z_0-9]*)?|-([a-zA-Z_][a-zA-Z_0-9]*)?)
     Mention,
 
-    #[regex(r"\s#([a-zA-Z_][a-zA-Z_0-9]*)")]
+    // links to sections of included files are qualified: `#chapter::section`, links to the root file start with `::`
+    #[regex(r"#(::)?([a-zA-Z_][a-zA-Z_0-9]*)(::[a-zA-Z_][a-zA-Z_0-9]*)*")]
     Link,
 
-    #[regex(r"\s:([a-zA-Z_][a-zA-Z_0-9]*)(\([^\)]*\))?")]
+    #[regex(r":([a-zA-Z_][a-zA-Z_0-9]*)(\([^\)]*\))?")]
     Command,
 
     #[regex(r"\{[^\}]+\}")]
     InlineBlock,
 
-    #[regex(r"//[^\n]*\n")]
-    SinglelineComment, // TODO: multiline comments
+    #[regex(r"//[^\n]*")]
+    SinglelineComment,
+
+    /// `false` if comment is not closed till the end of input
+    #[token("/*", multiline_comment)]
+    MultilineComment(bool),
 
     #[error]
     Text,
 }
 
+/// Skips the rest of `/* */` comment. Comments may be nested
+fn multiline_comment(lex: &mut logos::Lexer<Token>) -> bool {
+    let rest = lex.remainder().as_bytes();
+    let mut depth = 1;
+    let mut i = 0;
+
+    while i + 1 < rest.len() {
+        match &rest[i..i + 2] {
+            b"/*" => {
+                depth += 1;
+                i += 2;
+            }
+            b"*/" => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    lex.bump(i);
+                    return true;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+
+    lex.bump(rest.len());
+    false
+}
+
 #[derive(Debug)]
 pub(crate) enum SemanticToken {
     Mention(MentionToken),
     Text(Vec<String>),
     Link(String),
     Command(String, String),
-    InlineBlock(String),
+    /// Comment kept as a writer note
+    Note(String),
 }
 
 #[derive(PartialEq, Eq, Hash, Debug, Clone)]
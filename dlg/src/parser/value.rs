@@ -0,0 +1,134 @@
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
+
+/// Value of dialog variable
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Value {
+    /// String value: `"text"`
+    String(String),
+    /// Integer value: `42`
+    Integer(i64),
+    /// Boolean value: `true` or `false`
+    Bool(bool),
+}
+
+impl Value {
+    /// Truthiness of value: `false`, `0` and `""` are false, everything else is true
+    #[must_use]
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::String(s) => !s.is_empty(),
+            Value::Integer(i) => *i != 0,
+            Value::Bool(b) => *b,
+        }
+    }
+
+    /// Parses integer, `true` or `false`
+    pub(crate) fn from_literal(word: &str) -> Option<Value> {
+        match word {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => word.parse().ok().map(Value::Integer),
+        }
+    }
+
+    pub(crate) fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+            (Value::Integer(a), Value::Integer(b)) => Some(a.cmp(b)),
+            (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+            _ => None,
+        }
+    }
+}
+
+/// Strings are written in quotes as they are, without escapes, like they are parsed
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::String(s) => write!(f, "\"{}\"", s),
+            Value::Integer(i) => write!(f, "{}", i),
+            Value::Bool(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_owned())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Integer(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl FromStr for Value {
+    type Err = String;
+
+    /// Parses `42`, `true`, `"quoted string"` or `bare_word`. Bare word is a string
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let raw = raw.trim();
+
+        if let Some(value) = Value::from_literal(raw) {
+            return Ok(value);
+        }
+
+        if let Some(quoted) = raw.strip_prefix('"') {
+            return match quoted.strip_suffix('"') {
+                Some(string) if !string.contains('"') => Ok(Value::String(string.to_owned())),
+                _ => Err(format!("invalid string `{}`", raw)),
+            };
+        }
+
+        if is_ident(raw) {
+            Ok(Value::String(raw.to_owned()))
+        } else {
+            Err(format!("invalid value `{}`", raw))
+        }
+    }
+}
+
+impl Value {
+    /// Parses values separated by whitespace: `"quoted string" 42 bare_word`
+    pub(crate) fn parse_list(raw: &str) -> Result<Vec<Value>, String> {
+        let mut values = vec![];
+        let mut rest = raw.trim_start();
+
+        while !rest.is_empty() {
+            let len = if let Some(quoted) = rest.strip_prefix('"') {
+                match quoted.find('"') {
+                    Some(end) => end + 2,
+                    None => return Err(format!("unclosed string `{}`", rest)),
+                }
+            } else {
+                rest.find(char::is_whitespace).unwrap_or(rest.len())
+            };
+
+            values.push(rest[..len].parse()?);
+            rest = rest[len..].trim_start();
+        }
+
+        Ok(values)
+    }
+}
+
+pub(crate) fn is_ident(word: &str) -> bool {
+    let mut chars = word.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
@@ -3,57 +3,146 @@ use std::fmt::Display;
 use crate::prelude::*;
 
 // TODO: perhaps it makes sense to bind the cursor to the dialog
-#[derive(Debug)]
-pub struct Cursor(Section, usize, usize);
+/// Position in dialog: section, entered conditional branches, index of line and index of phrase in line
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cursor {
+    section: Section,
+    /// Index of conditional line and index of its chosen branch for every entered block
+    blocks: Vec<(usize, usize)>,
+    line_index: usize,
+    phrase_index: usize,
+    /// Positions to return to with `:back`, the last one is the most recent
+    history: Vec<Cursor>,
+}
 
 impl Cursor {
-    fn set_section_internal(&mut self, section: Section) {
-        self.0 = section;
-        self.1 = 0;
-        self.2 = 0;
+    pub(crate) fn set_section_internal(&mut self, section: Section) {
+        self.section = section;
+        self.blocks.clear();
+        self.line_index = 0;
+        self.phrase_index = 0;
     }
 
+    /// Moves cursor to the start of initial section and forgets the history of `:move`
     pub fn reset(&mut self) {
         self.set_section_internal(Section::Initial);
+        self.history.clear();
     }
 
+    /// Moves cursor to the start of named section
     pub fn set_section(&mut self, name: String) {
         self.set_section_internal(Section::Named(name));
     }
 
+    /// Moves cursor to the start of the section and remembers the line after the current one to return with [`Cursor::back`]
+    pub fn jump(&mut self, section: Section) {
+        self.history.push(Cursor {
+            section: self.section.clone(),
+            blocks: self.blocks.clone(),
+            line_index: self.line_index + 1,
+            phrase_index: 0,
+            history: vec![],
+        });
+        self.set_section_internal(section);
+    }
+
+    /// Returns to the position remembered by the last [`Cursor::jump`].
+    /// Returns `false` if there is nowhere to return
+    pub fn back(&mut self) -> bool {
+        if let Some(previous) = self.history.pop() {
+            self.section = previous.section;
+            self.blocks = previous.blocks;
+            self.line_index = previous.line_index;
+            self.phrase_index = previous.phrase_index;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    /// Sections the cursor can return to with [`Cursor::back`], the last one is the most recent
+    pub fn history(&self) -> impl Iterator<Item = &Section> {
+        self.history.iter().map(|c| &c.section)
+    }
+
+    /// Current section
     #[must_use]
     pub fn section(&self) -> &Section {
-        &self.0
+        &self.section
+    }
+
+    /// Entered conditional blocks from the outermost to the innermost: index of conditional line and index of chosen branch
+    #[must_use]
+    pub fn blocks(&self) -> &[(usize, usize)] {
+        &self.blocks
+    }
+
+    /// Moves cursor to the first line of the branch of current conditional line
+    pub fn enter_branch(&mut self, branch_index: usize) {
+        self.blocks.push((self.line_index, branch_index));
+        self.line_index = 0;
+        self.phrase_index = 0;
+    }
+
+    /// Moves cursor to the line after the innermost entered conditional block.
+    /// Returns `false` if cursor is not inside a block
+    pub fn exit_block(&mut self) -> bool {
+        if let Some((line_index, _)) = self.blocks.pop() {
+            self.line_index = line_index + 1;
+            self.phrase_index = 0;
+            true
+        } else {
+            false
+        }
     }
 
+    /// Moves cursor to the next line of section
     pub fn next_line_index(&mut self) {
-        self.1 += 1;
-        self.2 = 0;
+        self.line_index += 1;
+        self.phrase_index = 0;
     }
 
+    /// Index of current line in section
     #[must_use]
     pub fn line_index(&self) -> usize {
-        self.1
+        self.line_index
     }
 
+    /// Moves cursor to the next phrase of line
     pub fn next_phrase_index(&mut self) {
-        self.2 += 1;
+        self.phrase_index += 1;
     }
 
+    /// Index of current phrase in line
     #[must_use]
     pub fn phrase_index(&self) -> usize {
-        self.2
+        self.phrase_index
     }
 }
 
 impl Default for Cursor {
     fn default() -> Self {
-        Self(Section::Initial, 0, 0)
+        Self {
+            section: Section::Initial,
+            blocks: vec![],
+            line_index: 0,
+            phrase_index: 0,
+            history: vec![],
+        }
     }
 }
 
 impl Display for Cursor {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}:{}:{}", self.0, self.1, self.2)
+        write!(f, "{}:", self.section)?;
+        for (line_index, branch_index) in &self.blocks {
+            write!(f, "{}[{}]/", line_index, branch_index)?;
+        }
+        write!(f, "{}:{}", self.line_index, self.phrase_index)
     }
 }
@@ -0,0 +1,22 @@
+use crate::parser::Value;
+
+/// What player should do after event is handled
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum EventFlow {
+    /// Go on with the next line
+    Continue,
+    /// Stay on the event until host calls [`DialogRunner::advance`](super::DialogRunner::advance)
+    Pause,
+}
+
+/// Host callback for `:event "name" args` lines
+pub trait EventHandler {
+    /// Called when player reaches an event
+    fn handle_event(&mut self, name: &str, args: &[Value]) -> EventFlow;
+}
+
+impl<F: FnMut(&str, &[Value]) -> EventFlow> EventHandler for F {
+    fn handle_event(&mut self, name: &str, args: &[Value]) -> EventFlow {
+        self(name, args)
+    }
+}
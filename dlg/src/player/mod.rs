@@ -1,42 +1,212 @@
 mod cursor;
+mod event;
+mod runner;
+mod save;
+mod speaker;
+mod variables;
 
-use std::fmt::{write, Display};
+use std::fmt::Display;
 
 pub use cursor::Cursor;
+pub use event::{EventFlow, EventHandler};
+pub use runner::{DialogRunner, Step};
+pub use save::{SaveData, SaveError, SAVE_VERSION};
+pub use speaker::{AliasResolver, Color, SpeakerDisplay, SpeakerResolver};
+pub use variables::Variables;
 
-use crate::{parser::Menu, prelude::*};
+use unicode_segmentation::UnicodeSegmentation;
 
+use crate::{
+    parser::{Effect, Fragment, Menu},
+    prelude::*,
+};
+
+/// Count of conditions, `:move`s and `:back`s followed while looking for the next line.
+/// Bounds `:move` loops that never show anything
+const MAX_CONTROL_STEPS: usize = 10_000;
+
+/// Plays dialog step by step
 pub trait Player {
+    /// Plays dialog from the start to the end
     fn play(dialog: Dialog);
-    fn process_line(state: &mut DialogState, line: &Line);
+    /// Shows step and moves runner further
+    fn process_step(runner: &mut DialogRunner, step: Step);
+    /// Called when dialog is over
     fn end();
 }
 
-#[derive(Default, Debug)]
+/// Typewriter animation of current phrase
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Animation {
+    /// Count of shown graphemes
     pub current: usize,
+    /// Count of graphemes in phrase
     pub target: usize,
+    /// Count of ticks waited on `[wait]` markers before the next grapheme
     pub waited: usize,
 }
 
+impl Animation {
+    /// Moves typewriter animation by one tick of `tick_ms` milliseconds. Shows the next grapheme
+    /// or waits while `[wait]` markers before it are not over
+    pub fn tick(&mut self, fragments: &[Fragment], tick_ms: u32) {
+        if self.current >= self.target {
+            return;
+        }
+
+        let pause = u64::from(pause_before(fragments, self.current));
+        if (self.waited as u64) * u64::from(tick_ms) < pause {
+            self.waited += 1;
+        } else {
+            self.current += 1;
+            self.waited = 0;
+        }
+    }
+}
+
+/// Sum of `[wait]` markers between the grapheme with this index and the one before it
+fn pause_before(fragments: &[Fragment], grapheme_index: usize) -> u32 {
+    let mut shown = 0;
+    let mut pause = 0;
+    for fragment in fragments {
+        match fragment {
+            Fragment::Text(text, _) => {
+                shown += text.graphemes(true).count();
+                if shown > grapheme_index {
+                    break;
+                }
+            }
+            Fragment::Wait(ms) if shown == grapheme_index => pause += ms,
+            Fragment::Wait(_) => {}
+        }
+    }
+    pause
+}
+
 impl Display for Animation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}..{}", self.current, self.target)
     }
 }
 
-#[derive(Default, Debug)]
+/// State of dialog playback
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DialogState {
+    /// Current position in dialog
     pub cursor: Cursor,
+    /// Animation of current phrase
     pub animation: Animation,
+    /// Values of dialog variables. Set by menus and used in conditions
+    pub variables: Variables,
+    /// Speaker set by `@alice:calm` effect of the last chosen option. It says the following phrases
+    /// until a phrase changes the speaker with a mention
+    pub speaker: Option<Speaker>,
+    /// If `true`, reaching the end of a section continues into the next declared one instead of ending the dialog
+    pub fall_through: bool,
+}
+
+impl DialogState {
+    /// Applies effects of the chosen menu option. `=value` is written to the variable of menu, `@alice:calm` is set as [`DialogState::speaker`].
+    /// Cursor goes to the section of `#section` effect or to the line after menu if there is no such effect. Returns `false` if there is no option with this index
+    pub fn choose(&mut self, menu: &Menu, option_index: usize) -> bool {
+        let option = match menu.options.get(option_index) {
+            Some(option) => option,
+            None => return false,
+        };
+
+        let mut target = None;
+        for effect in &option.effects {
+            match effect {
+                Effect::Jump(section) => target = Some(section.clone()),
+                Effect::Speaker(speaker) => self.speaker = Some(speaker.clone()),
+                Effect::Assign(value) => {
+                    if let Some(variable) = &menu.variable {
+                        self.variables.set(variable.clone(), value.clone());
+                    }
+                }
+            }
+        }
+
+        match target {
+            Some(section) => self.cursor.set_section_internal(section),
+            None => self.cursor.next_line_index(),
+        }
+
+        true
+    }
+
+    /// Speaker of the phrase at the cursor, which has `speaker` written in the dialog. Phrases that keep the speaker
+    /// of the previous phrase are said by [`DialogState::speaker`] if it is set, a phrase with another speaker resets it
+    pub fn phrase_speaker(&mut self, dialog: &Dialog, speaker: &Speaker) -> Speaker {
+        if let Some(chosen) = &self.speaker {
+            let inherited = dialog.inherited_speaker(
+                self.cursor.section(),
+                self.cursor.blocks(),
+                self.cursor.line_index(),
+            );
+            if *speaker == inherited {
+                return chosen.clone();
+            }
+            self.speaker = None;
+        }
+        speaker.clone()
+    }
+
+    /// Returns the line that should be played now. Conditional blocks are evaluated and entered,
+    /// finished blocks are left, `:move` and `:back` are followed, so the result is always a phrase, a menu or an event.
+    /// `:back` with empty history is skipped. The end of section is the end of dialog unless [`DialogState::fall_through`] is set.
+    /// `:move` loops that show nothing end the dialog too
+    pub fn current_line<'a>(&mut self, dialog: &'a Dialog) -> Option<&'a Line> {
+        for _ in 0..MAX_CONTROL_STEPS {
+            match dialog.get_line_by_cursor(&self.cursor) {
+                Some(Line::Conditional(branches)) => {
+                    let chosen = branches.iter().position(|branch| match &branch.condition {
+                        Some(condition) => condition.evaluate(&self.variables),
+                        None => true,
+                    });
+
+                    match chosen {
+                        Some(branch_index) => self.cursor.enter_branch(branch_index),
+                        None => self.cursor.next_line_index(),
+                    }
+                }
+                Some(Line::Move(section)) => self.cursor.jump(section.clone()),
+                Some(Line::Back) => {
+                    if !self.cursor.back() {
+                        self.cursor.next_line_index();
+                    }
+                }
+                Some(line) => return Some(line),
+                None => {
+                    if self.cursor.exit_block() {
+                        continue;
+                    }
+
+                    match dialog.next_section(self.cursor.section()) {
+                        Some(next) if self.fall_through => {
+                            self.cursor.set_section_internal(next.clone());
+                        }
+                        _ => return None,
+                    }
+                }
+            }
+        }
+
+        // `:move` loop without phrases, menus or events. Its positions to return to are never used
+        self.cursor.clear_history();
+        None
+    }
 }
 
+/// Player that prints dialog to stdout, waits for Enter after every phrase and reads number of option in menus
 pub struct ConsoleDialogPlayer;
 
 impl ConsoleDialogPlayer {
     fn clear_terminal() {}
 
-    fn wait_for_enter(message: &str) {
+    fn read_line(message: &str) -> String {
         use std::io::{self, BufRead};
 
         println!();
@@ -45,47 +215,52 @@ impl ConsoleDialogPlayer {
         let stdin = io::stdin();
         let mut input = String::new();
         stdin.lock().read_line(&mut input).expect("Why?");
+        input
     }
 }
 
 impl Player for ConsoleDialogPlayer {
     fn play(dialog: Dialog) {
-        let mut state = DialogState {
-            cursor: Cursor::default(),
-            animation: Animation::default(),
-        };
+        let mut runner = DialogRunner::new(&dialog);
 
-        while let Some(line) = dialog.get_line_by_cursor(&state.cursor) {
-            Self::process_line(&mut state, line);
+        loop {
+            match runner.current() {
+                Step::End => break,
+                step => Self::process_step(&mut runner, step),
+            }
         }
 
         Self::end();
     }
 
-    fn process_line(state: &mut DialogState, line: &Line) {
+    fn process_step(runner: &mut DialogRunner, step: Step) {
         Self::clear_terminal();
 
-        match line {
-            Line::Phrase { speaker, lines } => {
-                if let Some(line) = lines.get(state.cursor.phrase_index()) {
-                    println!("{:?}: {:?}", speaker, line);
-                    state.cursor.next_phrase_index();
-                } else {
-                    state.cursor.next_line_index();
-                    return;
-                }
+        match step {
+            Step::Phrase { speaker, text, .. } => {
+                println!("{}: {:?}", runner.speaker_display(&speaker).name, text);
+                Self::read_line("Нажмите [Enter] для продолжения");
+                runner.advance();
             }
-            Line::Menu(menu) => {
-                let Menu { title, options } = menu;
+            Step::Menu(menu) => {
+                let Menu { title, options, .. } = menu;
                 println!("{:?}", title);
                 println!();
                 for (index, opt) in options.iter().enumerate() {
                     println!("{}. {:?}", index + 1, opt.title);
                 }
+
+                let input = Self::read_line("Введите номер варианта");
+                if let Ok(number) = input.trim().parse::<usize>() {
+                    runner.choose(number.wrapping_sub(1));
+                }
             }
+            Step::Event { name, args } => {
+                println!("Event: {} {:?}", name, args);
+                runner.advance();
+            }
+            Step::End => {}
         }
-
-        Self::wait_for_enter("Нажмите [Enter] для продолжения");
     }
 
     fn end() {
@@ -0,0 +1,186 @@
+use crate::{
+    parser::{Fragment, Menu, Value},
+    prelude::*,
+};
+
+use super::{
+    AliasResolver, Animation, EventFlow, EventHandler, SaveData, SaveError, SpeakerDisplay,
+    SpeakerResolver,
+};
+
+/// What player should show now
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Step<'a> {
+    /// Part of a phrase
+    Phrase {
+        /// The one who utters the phrase, see [`DialogState::phrase_speaker`]
+        speaker: Speaker,
+        /// Current part of the phrase with values of variables in place of inline blocks, without markup
+        text: String,
+        /// Current part of the phrase split into styled pieces and pauses
+        fragments: Vec<Fragment>,
+        /// `true` if this is the last part of the phrase
+        last: bool,
+    },
+    /// Menu. Playback goes on after [`DialogRunner::choose`]
+    Menu(&'a Menu),
+    /// `:event "name" args`. Playback goes on after [`DialogRunner::advance`] or [`DialogRunner::handle_event`]
+    Event {
+        /// Name of event
+        name: &'a str,
+        /// Arguments of event
+        args: &'a [Value],
+    },
+    /// Dialog is over
+    End,
+}
+
+/// Headless dialog player. Keeps the dialog and the state of playback and moves through the dialog step by step,
+/// so frontends only render [`Step`]s and pass user input back
+pub struct DialogRunner<'a> {
+    dialog: &'a Dialog,
+    state: DialogState,
+    speakers: Box<dyn SpeakerResolver + 'a>,
+}
+
+impl<'a> DialogRunner<'a> {
+    /// Runner that starts from the beginning of the dialog
+    #[must_use]
+    pub fn new(dialog: &'a Dialog) -> Self {
+        Self::with_state(dialog, DialogState::default())
+    }
+
+    /// Runner that continues playback from the state, for example restored one or with seeded variables
+    #[must_use]
+    pub fn with_state(dialog: &'a Dialog, state: DialogState) -> Self {
+        Self {
+            dialog,
+            state,
+            speakers: Box::new(AliasResolver),
+        }
+    }
+
+    /// Runner that continues playback from the save. Fails if the save does not match the dialog
+    pub fn restore(dialog: &'a Dialog, save: SaveData) -> Result<Self, SaveError> {
+        Ok(Self::with_state(dialog, save.restore(dialog)?))
+    }
+
+    /// Saves current state of playback
+    #[must_use]
+    pub fn save(&self) -> SaveData {
+        SaveData::new(self.dialog, self.state.clone())
+    }
+
+    /// Played dialog
+    #[must_use]
+    pub fn dialog(&self) -> &'a Dialog {
+        self.dialog
+    }
+
+    /// State of playback
+    #[must_use]
+    pub fn state(&self) -> &DialogState {
+        &self.state
+    }
+
+    /// Mutable state of playback, for example to set variables
+    pub fn state_mut(&mut self) -> &mut DialogState {
+        &mut self.state
+    }
+
+    /// Sets how speakers are shown, for example from a [`Manifest`](crate::manifest::Manifest). Aliases are shown by default
+    pub fn set_speaker_resolver(&mut self, resolver: impl SpeakerResolver + 'a) {
+        self.speakers = Box::new(resolver);
+    }
+
+    /// Name and style of the speaker given by the speaker resolver
+    #[must_use]
+    pub fn speaker_display(&self, speaker: &Speaker) -> SpeakerDisplay {
+        self.speakers.resolve(speaker)
+    }
+
+    /// Stops playback and returns its state
+    #[must_use]
+    pub fn into_state(self) -> DialogState {
+        self.state
+    }
+
+    /// Current step. Conditions, `:move` and `:back` are resolved here, so the cursor may move
+    pub fn current(&mut self) -> Step<'a> {
+        match self.state.current_line(self.dialog) {
+            Some(Line::Phrase { speaker, lines, .. }) => {
+                let index = self.state.cursor.phrase_index();
+                match lines.get(index) {
+                    Some(text) => Step::Phrase {
+                        speaker: self.state.phrase_speaker(self.dialog, speaker),
+                        text: text.resolve(&self.state.variables),
+                        fragments: text.fragments(&self.state.variables),
+                        last: index + 1 >= lines.len(),
+                    },
+                    None => {
+                        self.state.cursor.next_line_index();
+                        self.current()
+                    }
+                }
+            }
+            Some(Line::Menu(menu)) => Step::Menu(menu),
+            Some(Line::Event { name, args }) => Step::Event { name, args },
+            Some(Line::Conditional(_) | Line::Move(_) | Line::Back) => {
+                unreachable!("control lines are handled by `DialogState::current_line`")
+            }
+            None => Step::End,
+        }
+    }
+
+    /// Goes to the next part of the phrase, to the next line or past the event.
+    /// Does nothing on menu, use [`DialogRunner::choose`] instead
+    pub fn advance(&mut self) {
+        match self.current() {
+            Step::Phrase { last: false, .. } => self.state.cursor.next_phrase_index(),
+            Step::Phrase { last: true, .. } | Step::Event { .. } => {
+                self.state.cursor.next_line_index()
+            }
+            Step::Menu(_) | Step::End => return,
+        }
+
+        self.state.animation = Animation::default();
+    }
+
+    /// Chooses option of the current menu. Returns `false` if current step is not a menu or there is no such option
+    pub fn choose(&mut self, option_index: usize) -> bool {
+        let chosen = match self.current() {
+            Step::Menu(menu) => self.state.choose(menu, option_index),
+            _ => false,
+        };
+
+        if chosen {
+            self.state.animation = Animation::default();
+        }
+
+        chosen
+    }
+
+    /// Passes the current event to the handler. Playback goes past the event if handler returns [`EventFlow::Continue`]
+    /// and stays on it if handler returns [`EventFlow::Pause`]. Returns `None` if current step is not an event
+    pub fn handle_event(&mut self, handler: &mut impl EventHandler) -> Option<EventFlow> {
+        let flow = match self.current() {
+            Step::Event { name, args } => handler.handle_event(name, args),
+            _ => return None,
+        };
+
+        if let EventFlow::Continue = flow {
+            self.advance();
+        }
+
+        Some(flow)
+    }
+}
+
+impl std::fmt::Debug for DialogRunner<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DialogRunner")
+            .field("dialog", &self.dialog)
+            .field("state", &self.state)
+            .finish_non_exhaustive()
+    }
+}
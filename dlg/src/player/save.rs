@@ -0,0 +1,65 @@
+use std::{error::Error, fmt::Display};
+
+use crate::prelude::*;
+
+/// Version of [`SaveData`] format. Saves of other versions can't be restored
+pub const SAVE_VERSION: u32 = 1;
+
+/// Playback state stored in a save slot together with the version of format and [`Dialog::fingerprint`]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SaveData {
+    /// Version of save format, [`SAVE_VERSION`] for new saves
+    pub version: u32,
+    /// Fingerprint of the dialog the state belongs to
+    pub fingerprint: u64,
+    /// Saved state of playback
+    pub state: DialogState,
+}
+
+/// Reason why a save can't be restored
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SaveError {
+    /// Save was made with another version of format
+    UnsupportedVersion(u32),
+    /// Dialog has changed since the save was made, so the cursor may point to a wrong line
+    DialogChanged,
+}
+
+impl SaveData {
+    /// Saves state of the dialog playback
+    #[must_use]
+    pub fn new(dialog: &Dialog, state: DialogState) -> Self {
+        Self {
+            version: SAVE_VERSION,
+            fingerprint: dialog.fingerprint(),
+            state,
+        }
+    }
+
+    /// Returns the saved state if the save is compatible with the dialog
+    pub fn restore(self, dialog: &Dialog) -> Result<DialogState, SaveError> {
+        if self.version != SAVE_VERSION {
+            Err(SaveError::UnsupportedVersion(self.version))
+        } else if self.fingerprint != dialog.fingerprint() {
+            Err(SaveError::DialogChanged)
+        } else {
+            Ok(self.state)
+        }
+    }
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported save version {}, expected {}",
+                version, SAVE_VERSION
+            ),
+            SaveError::DialogChanged => write!(f, "dialog has changed since the save was made"),
+        }
+    }
+}
+
+impl Error for SaveError {}
@@ -0,0 +1,109 @@
+use std::{fmt::Display, str::FromStr};
+
+#[cfg(feature = "manifest")]
+use crate::manifest::Manifest;
+use crate::parser::{Speaker, State};
+
+/// How a speaker is shown to the player
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct SpeakerDisplay {
+    /// Name in the title of the phrase
+    pub name: String,
+    /// Color of the name
+    pub color: Option<Color>,
+    /// Portrait ID for the state of the speaker
+    pub portrait: Option<String>,
+}
+
+/// Color of a character written as `#rrggbb`
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub struct Color {
+    /// Red component
+    pub r: u8,
+    /// Green component
+    pub g: u8,
+    /// Blue component
+    pub b: u8,
+}
+
+/// Host callback that maps speakers of phrases to names and styles shown to the player
+pub trait SpeakerResolver {
+    /// Called for the speaker of every shown phrase
+    fn resolve(&self, speaker: &Speaker) -> SpeakerDisplay;
+}
+
+impl<F: Fn(&Speaker) -> SpeakerDisplay> SpeakerResolver for F {
+    fn resolve(&self, speaker: &Speaker) -> SpeakerDisplay {
+        self(speaker)
+    }
+}
+
+/// Shows speakers as they are written: `Narrator`, `alice` or `alice: happy`
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct AliasResolver;
+
+impl SpeakerResolver for AliasResolver {
+    fn resolve(&self, speaker: &Speaker) -> SpeakerDisplay {
+        let name = match speaker {
+            Speaker::Narrator => String::from("Narrator"),
+            Speaker::Character(alias, State::Default) => alias.0.clone(),
+            Speaker::Character(alias, State::Named(state)) => format!("{}: {}", alias.0, state),
+        };
+
+        SpeakerDisplay {
+            name,
+            color: None,
+            portrait: None,
+        }
+    }
+}
+
+/// Declared characters are shown with their names and colors. The portrait of the default state
+/// is used for states without their own. Narrator and undeclared characters are shown like [`AliasResolver`] does
+#[cfg(feature = "manifest")]
+impl SpeakerResolver for Manifest {
+    fn resolve(&self, speaker: &Speaker) -> SpeakerDisplay {
+        let (alias, state) = match speaker {
+            Speaker::Character(alias, state) => (alias, state),
+            Speaker::Narrator => return AliasResolver.resolve(speaker),
+        };
+        let info = match self.get(alias) {
+            Some(info) => info,
+            None => return AliasResolver.resolve(speaker),
+        };
+
+        SpeakerDisplay {
+            name: info.name.clone().unwrap_or_else(|| alias.0.clone()),
+            color: info.color,
+            portrait: info
+                .portraits
+                .get(state)
+                .or_else(|| info.portraits.get(&State::Default))
+                .cloned(),
+        }
+    }
+}
+
+impl FromStr for Color {
+    type Err = String;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let hex = raw
+            .strip_prefix('#')
+            .filter(|hex| hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+            .ok_or_else(|| format!("expected color `#rrggbb`, got `{}`", raw))?;
+        let component = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).expect("hex is checked");
+
+        Ok(Color {
+            r: component(0),
+            g: component(2),
+            b: component(4),
+        })
+    }
+}
+
+impl Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
@@ -2,6 +2,7 @@ pub use crate::parser::{Alias, Dialog, Line, Requirements, Section, Speaker, Sta
 
 pub use crate::player::{Cursor, DialogState};
 
+/// Counts expressions separated by `;` at compile time
 #[macro_export]
 macro_rules! const_expr_count {
     () => (0);
@@ -15,6 +16,7 @@ macro_rules! const_expr_count {
     );
 }
 
+/// Builds a map of character requirements: `"alias" => ["state_1", "state_2"]`
 #[macro_export]
 macro_rules! character_requirements {
     ($($key:expr => [$($val:expr),*]),*) => ({
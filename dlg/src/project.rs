@@ -0,0 +1,411 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt::Display,
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use indexmap::IndexMap;
+
+use crate::parser::{
+    is_ident, Dialog, Effect, Line, ParseErrorKind, Requirements, Section, SourceMap, Span,
+};
+
+/// Where files of a project are read from
+pub trait FileSource {
+    /// Contents of the file. Paths are relative to the root of the source and have no `.` and `..` in the middle
+    fn read(&self, path: &Path) -> io::Result<String>;
+}
+
+/// Files in a directory on disk
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct DirectorySource {
+    root: PathBuf,
+}
+
+impl DirectorySource {
+    /// Source that reads files relative to the directory
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl FileSource for DirectorySource {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(self.root.join(path))
+    }
+}
+
+/// Files kept in memory, for tests and dialogs embedded into the game
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct MemorySource {
+    files: HashMap<PathBuf, String>,
+}
+
+impl MemorySource {
+    /// Adds or replaces the file
+    pub fn insert(&mut self, path: impl AsRef<Path>, contents: impl Into<String>) {
+        self.files.insert(normalize(path.as_ref()), contents.into());
+    }
+}
+
+impl FileSource for MemorySource {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        self.files
+            .get(&normalize(path))
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such file"))
+    }
+}
+
+/// Kind of error that occurred while loading a project
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub enum ProjectErrorKind {
+    /// File can't be read: its path and the reason
+    Read(PathBuf, String),
+    /// File can't be parsed
+    Parse(ParseErrorKind),
+    /// Files include each other. The chain starts and ends with the same file
+    IncludeCycle(Vec<PathBuf>),
+    /// Name of the included file is not an identifier, so its sections can't be linked
+    InvalidNamespace(String),
+    /// Two included files in different directories have the same name
+    DuplicateNamespace(String),
+    /// Section has the same qualified name as a section of another file
+    DuplicateSection(Section),
+}
+
+/// Error that occurred while loading a project
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct ProjectError {
+    /// File the error is found in
+    pub path: PathBuf,
+    /// Where it went wrong. `None` if the root file can't be read
+    pub span: Option<Span>,
+    /// What went wrong
+    pub kind: ProjectErrorKind,
+}
+
+/// Dialog assembled from the root file and all files it includes
+#[derive(PartialEq, Debug)]
+pub struct DialogProject {
+    /// Sections and characters of all files. Sections of the root file keep their names,
+    /// sections of `file.dlg` are named `#file::section` and its initial section is `#file`
+    pub dialog: Dialog,
+    /// Paths of loaded files, the root first and the others in the order they are included
+    pub files: Vec<PathBuf>,
+}
+
+impl DialogProject {
+    /// Loads the root file and everything it includes. `:include` paths are relative to the including file
+    pub fn load(source: &impl FileSource, root: impl AsRef<Path>) -> Result<Self, ProjectError> {
+        let mut loader = Loader {
+            source,
+            files: IndexMap::new(),
+            namespaces: HashSet::new(),
+            stack: vec![],
+        };
+        loader.load(normalize(root.as_ref()), None, None)?;
+
+        let mut dialog = Dialog::default();
+        for (path, file) in &mut loader.files {
+            merge(&mut dialog, path, file, &loader.namespaces)?;
+        }
+
+        Ok(Self {
+            dialog,
+            files: loader.files.into_keys().collect(),
+        })
+    }
+}
+
+struct File {
+    /// Name that qualifies sections of the file. `None` for the root file
+    namespace: Option<String>,
+    dialog: Dialog,
+    source_map: SourceMap,
+}
+
+struct Loader<'a, S> {
+    source: &'a S,
+    files: IndexMap<PathBuf, File>,
+    namespaces: HashSet<String>,
+    /// Files that are being loaded, from the root to the current one
+    stack: Vec<PathBuf>,
+}
+
+impl<S: FileSource> Loader<'_, S> {
+    /// Loads the file and its includes. `include` is the file and the span of `:include` that refers to it
+    fn load(
+        &mut self,
+        path: PathBuf,
+        namespace: Option<String>,
+        include: Option<(&Path, Span)>,
+    ) -> Result<(), ProjectError> {
+        let error = |kind| match include {
+            Some((from, span)) => ProjectError {
+                path: from.to_owned(),
+                span: Some(span),
+                kind,
+            },
+            None => ProjectError {
+                path: path.clone(),
+                span: None,
+                kind,
+            },
+        };
+
+        if let Some(start) = self.stack.iter().position(|loading| *loading == path) {
+            let mut cycle = self.stack[start..].to_vec();
+            cycle.push(path.clone());
+            return Err(error(ProjectErrorKind::IncludeCycle(cycle)));
+        }
+        if self.files.contains_key(&path) {
+            return Ok(());
+        }
+
+        if let Some(namespace) = &namespace {
+            if !is_ident(namespace) {
+                return Err(error(ProjectErrorKind::InvalidNamespace(namespace.clone())));
+            }
+            // the same file is loaded only once, so another path with this name is a different file
+            if !self.namespaces.insert(namespace.clone()) {
+                return Err(error(ProjectErrorKind::DuplicateNamespace(
+                    namespace.clone(),
+                )));
+            }
+        }
+
+        let raw = self
+            .source
+            .read(&path)
+            .map_err(|e| error(ProjectErrorKind::Read(path.clone(), e.to_string())))?;
+        let (dialog, source_map) =
+            Dialog::parse_with_source_map(&raw).map_err(|e| ProjectError {
+                path: path.clone(),
+                span: Some(e.span),
+                kind: ProjectErrorKind::Parse(e.kind),
+            })?;
+
+        let directory = path.parent().unwrap_or(Path::new("")).to_owned();
+        let includes = dialog
+            .includes
+            .iter()
+            .cloned()
+            .zip(source_map.includes.iter().copied())
+            .collect::<Vec<_>>();
+
+        self.files.insert(
+            path.clone(),
+            File {
+                namespace,
+                dialog,
+                source_map,
+            },
+        );
+        self.stack.push(path.clone());
+        for (include, span) in includes {
+            let included = normalize(&directory.join(&include));
+            let namespace = included
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            self.load(included, Some(namespace), Some((&path, span)))?;
+        }
+        self.stack.pop();
+
+        Ok(())
+    }
+}
+
+/// Moves sections and characters of the file into the project dialog
+fn merge(
+    dialog: &mut Dialog,
+    path: &Path,
+    file: &mut File,
+    namespaces: &HashSet<String>,
+) -> Result<(), ProjectError> {
+    for (alias, requirements) in file.dialog.characters.drain() {
+        let merged: &mut Requirements = dialog.characters.entry(alias).or_default();
+        for state in requirements.states {
+            if !merged.states.contains(&state) {
+                merged.states.push(state);
+            }
+        }
+    }
+
+    let qualifier = file
+        .namespace
+        .as_deref()
+        .map(|namespace| Qualifier::new(namespace, &file.source_map, namespaces));
+
+    for (section, mut lines) in file.dialog.sections.drain(..) {
+        let qualified = match &qualifier {
+            Some(qualifier) => {
+                // files with only named sections have an empty initial one
+                if section == Section::Initial && lines.is_empty() {
+                    continue;
+                }
+                relink(&mut lines, &|target| qualifier.qualify_link(target));
+                qualify(&section, qualifier.namespace)
+            }
+            None => {
+                relink(&mut lines, &link_from_root);
+                section.clone()
+            }
+        };
+
+        if dialog.sections.contains_key(&qualified) {
+            return Err(ProjectError {
+                path: path.to_owned(),
+                span: file.source_map.sections.get(&section).copied(),
+                kind: ProjectErrorKind::DuplicateSection(qualified),
+            });
+        }
+        dialog.sections.insert(qualified, lines);
+    }
+
+    Ok(())
+}
+
+/// Name of the section of included file in the project
+pub(crate) fn qualify(section: &Section, namespace: &str) -> Section {
+    match section {
+        Section::Initial => Section::Named(namespace.to_owned()),
+        Section::Named(name) => Section::Named(format!("{}::{}", namespace, name)),
+    }
+}
+
+/// Name of the linked section in the project for links of the root file. `#::section` is the same as `#section` there
+pub(crate) fn link_from_root(target: &Section) -> Section {
+    match target {
+        Section::Named(name) => match name.strip_prefix("::") {
+            Some(name) => Section::Named(name.to_owned()),
+            None => target.clone(),
+        },
+        Section::Initial => target.clone(),
+    }
+}
+
+/// Qualifies links of included file to its own sections. `#file` that is not declared in the file
+/// goes to the initial section of `file.dlg`, qualified links are kept and `#::section` goes to the root file
+pub(crate) struct Qualifier<'a> {
+    pub(crate) namespace: &'a str,
+    /// Sections declared in the file, including the empty ones
+    declared: HashSet<Section>,
+    /// Names of all included files
+    namespaces: &'a HashSet<String>,
+}
+
+impl<'a> Qualifier<'a> {
+    pub(crate) fn new(
+        namespace: &'a str,
+        source_map: &SourceMap,
+        namespaces: &'a HashSet<String>,
+    ) -> Self {
+        Self {
+            namespace,
+            declared: source_map.sections.keys().cloned().collect(),
+            namespaces,
+        }
+    }
+
+    /// Name of the linked section in the project
+    pub(crate) fn qualify_link(&self, target: &Section) -> Section {
+        match target {
+            Section::Named(name) if name.starts_with("::") => link_from_root(target),
+            Section::Named(name) => {
+                let other_file = self.namespaces.contains(name) && !self.declared.contains(target);
+                if target.namespace().is_none() && !other_file {
+                    qualify(target, self.namespace)
+                } else {
+                    target.clone()
+                }
+            }
+            Section::Initial => target.clone(),
+        }
+    }
+}
+
+/// Replaces targets of `:move` and `#section` effects with the result of `link`
+fn relink(lines: &mut [Line], link: &impl Fn(&Section) -> Section) {
+    for line in lines {
+        match line {
+            Line::Move(target) => *target = link(target),
+            Line::Menu(menu) => {
+                for option in &mut menu.options {
+                    for effect in &mut option.effects {
+                        if let Effect::Jump(target) = effect {
+                            *target = link(target);
+                        }
+                    }
+                }
+            }
+            Line::Conditional(branches) => {
+                for branch in branches {
+                    relink(&mut branch.lines, link);
+                }
+            }
+            Line::Phrase { .. } | Line::Back | Line::Event { .. } => {}
+        }
+    }
+}
+
+/// Removes `.` and resolves `..` without touching the file system
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+impl Display for ProjectErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProjectErrorKind::Read(path, reason) => {
+                write!(f, "can't read `{}`: {}", path.display(), reason)
+            }
+            ProjectErrorKind::Parse(kind) => write!(f, "{}", kind),
+            ProjectErrorKind::IncludeCycle(cycle) => {
+                let chain = cycle
+                    .iter()
+                    .map(|path| format!("`{}`", path.display()))
+                    .collect::<Vec<_>>();
+                write!(f, "files include each other: {}", chain.join(" -> "))
+            }
+            ProjectErrorKind::InvalidNamespace(name) => {
+                write!(f, "file name `{}` is not an identifier", name)
+            }
+            ProjectErrorKind::DuplicateNamespace(name) => {
+                write!(f, "two included files are named `{}`", name)
+            }
+            ProjectErrorKind::DuplicateSection(section) => {
+                write!(f, "section {} is declared in two files", section)
+            }
+        }
+    }
+}
+
+impl Display for ProjectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.span {
+            Some(span) => write!(f, "{}:{}: {}", self.path.display(), span, self.kind),
+            None => write!(f, "{}: {}", self.path.display(), self.kind),
+        }
+    }
+}
+
+impl Error for ProjectError {}
@@ -1,7 +1,7 @@
 use std::str::FromStr;
 
-use common_macros::hash_map;
 use dlg::{character_requirements, parser::State, prelude::*};
+use indexmap::indexmap;
 
 #[test]
 fn test_all_dialog_features() {
@@ -21,26 +21,31 @@ fn test_all_dialog_features() {
         characters: character_requirements! {
             "bob" => ["state_1", "state_2"]
         },
-        sections: hash_map! {
+        sections: indexmap! {
             Section::Initial => vec![
                 Line::Phrase {
-                    speaker: Mention::Narrator,
-                    lines: vec!["Narrator's text".to_owned()]
+                    speaker: Speaker::Narrator,
+                    lines: vec!["Narrator's text".into()],
+                    id: None
                 },
                 Line::Phrase {
-                    speaker: Mention::Character(Alias("bob".to_owned()), State::Default),
-                    lines: vec!["Bob without state".to_owned()],
+                    speaker: Speaker::Character(Alias("bob".to_owned()), State::Default),
+                    lines: vec!["Bob without state".into()],
+                    id: None,
                 },
                 Line::Phrase {
-                    speaker: Mention::Character(Alias("bob".to_owned()), State::Named("state_1".to_owned())),
-                    lines: vec!["Bob with state_1".to_owned()],
+                    speaker: Speaker::Character(Alias("bob".to_owned()), State::Named("state_1".to_owned())),
+                    lines: vec!["Bob with state_1".into()],
+                    id: None,
                 },
                 Line::Phrase {
-                    speaker: Mention::Character(Alias("bob".to_owned()), State::Named("state_2".to_owned())),
-                    lines: vec!["Bob with state_2".to_owned()],
+                    speaker: Speaker::Character(Alias("bob".to_owned()), State::Named("state_2".to_owned())),
+                    lines: vec!["Bob with state_2".into()],
+                    id: None,
                 },
             ]
         },
+        includes: vec![],
     };
 
     assert_eq!(expected, actual);
@@ -2,6 +2,7 @@ use std::str::FromStr;
 
 use common_macros::hash_map;
 use dlg::{character_requirements, parser::State, prelude::*};
+use indexmap::indexmap;
 
 #[test]
 fn test_basic_dialog() {
@@ -11,7 +12,7 @@ fn test_basic_dialog() {
             @bob:state Bob's text
         ";
 
-    let actual = Dialog::from_str(&raw).unwrap();
+    let actual = Dialog::from_str(raw).unwrap();
 
     let expected = Dialog {
         characters: hash_map! {
@@ -19,17 +20,19 @@ fn test_basic_dialog() {
                 states: vec![State::Named("state".to_owned())]
             }
         },
-        sections: hash_map! {
+        sections: indexmap! {
             Section::Initial => vec![
                 Line::Phrase {
-                    speaker: Mention::Narrator, lines: vec!["Narrator's text".to_owned()]
+                    speaker: Speaker::Narrator, lines: vec!["Narrator's text".into()], id: None
                 },
                 Line::Phrase {
-                    speaker: Mention::Character(Alias("bob".to_owned()), State::Named("state".to_owned())),
-                    lines: vec!["Bob's text".to_owned()],
+                    speaker: Speaker::Character(Alias("bob".to_owned()), State::Named("state".to_owned())),
+                    lines: vec!["Bob's text".into()],
+                    id: None,
                 },
             ]
         },
+        includes: vec![],
     };
 
     assert_eq!(expected, actual);
@@ -49,30 +52,35 @@ fn test_dialog_with_sections() {
             #section_without_text
         ";
 
-    let actual = Dialog::from_str(&raw).unwrap();
+    let actual = Dialog::from_str(raw).unwrap();
 
     let expected = Dialog {
         characters: character_requirements! {
             "bob" => ["state"]
         },
-        sections: hash_map! {
+        sections: indexmap! {
             Section::Initial => vec![
                 Line::Phrase {
-                    speaker: Mention::Narrator,
-                    lines: vec!["Narrator's text".to_owned()]
+                    speaker: Speaker::Narrator,
+                    lines: vec!["Narrator's text".into()],
+                    id: None
                 },
                 Line::Phrase {
-                    speaker: Mention::Character(Alias("bob".to_owned()), State::Named("state".to_owned())),
-                    lines: vec!["Bob's text".to_owned()],
+                    speaker: Speaker::Character(Alias("bob".to_owned()), State::Named("state".to_owned())),
+                    lines: vec!["Bob's text".into()],
+                    id: None,
                 },
             ],
             Section::Named("section_with_items".to_owned()) => vec![
                 Line::Phrase {
-                    speaker: Mention::Narrator,
-                    lines: vec!["Narrator's text again".to_owned()]
+                    speaker: Speaker::Narrator,
+                    lines: vec!["Narrator's text again".into()],
+                    id: None
                 },
-            ]
+            ],
+            Section::Named("section_without_text".to_owned()) => vec![]
         },
+        includes: vec![],
     };
 
     assert_eq!(expected, actual);
@@ -84,7 +92,7 @@ fn test_dialog_with_dumb_text() {
             @bob:state шгагsgiu32:232r;asdlf@423rkjl;:dsxwasdlk:
         ";
 
-    let actual = Dialog::from_str(&raw).unwrap();
+    let actual = Dialog::from_str(raw).unwrap();
 
     let expected = Dialog {
         characters: hash_map! {
@@ -92,14 +100,16 @@ fn test_dialog_with_dumb_text() {
                 states: vec![State::Named("state".to_owned())]
             }
         },
-        sections: hash_map! {
+        sections: indexmap! {
             Section::Initial => vec![
                 Line::Phrase {
-                    speaker: Mention::Character(Alias("bob".to_owned()), State::Named("state".to_owned())),
-                    lines: vec!["шгагsgiu32:232r;asdlf@423rkjl;:dsxwasdlk:".to_owned()],
+                    speaker: Speaker::Character(Alias("bob".to_owned()), State::Named("state".to_owned())),
+                    lines: vec!["шгагsgiu32:232r;asdlf@423rkjl;:dsxwasdlk:".into()],
+                    id: None,
                 },
             ],
         },
+        includes: vec![],
     };
 
     assert_eq!(expected, actual);
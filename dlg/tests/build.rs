@@ -0,0 +1,124 @@
+use std::str::FromStr;
+
+use dlg::{
+    build::{self, BuildError},
+    prelude::*,
+};
+
+mod generated {
+    include!("generated/story.rs");
+}
+
+use generated::{AliceState, BobState, Character, OldManState, SectionName};
+
+const STORY: &str = r"
+@alice:happy Hi
+@bob Hello
+@alice:angry Where is the old man?
+@old_man:sleepy Zzz
+:menu
+:opt(#walk) Walk
+:opt(#forest::lake) Lake
+
+#walk
+@alice Nice
+:move #forest::lake
+
+#forest::lake
+@bob:sad Cold
+";
+
+#[test]
+fn test_to_rust() {
+    let dialog = Dialog::from_str(STORY).unwrap();
+
+    assert_eq!(
+        include_str!("generated/story.rs"),
+        build::to_rust(&dialog).unwrap()
+    );
+}
+
+#[test]
+fn test_conversions() {
+    let alice = Character::Alice(AliceState::Happy);
+    assert_eq!(Alias("alice".to_owned()), Alias::from(alice));
+    assert_eq!(State::Named("happy".to_owned()), State::from(alice));
+    assert_eq!(
+        Speaker::Character(Alias("old_man".to_owned()), State::Default),
+        Speaker::from(Character::OldMan(OldManState::default()))
+    );
+
+    let dialog = Dialog::from_str(STORY).unwrap();
+    let speakers = dialog.sections[&Section::Initial]
+        .iter()
+        .filter_map(|line| match line {
+            Line::Phrase { speaker, .. } => Some(Character::try_from(speaker).unwrap()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            alice,
+            Character::Bob(BobState::Default),
+            Character::Alice(AliceState::Angry),
+            Character::OldMan(OldManState::Sleepy),
+        ],
+        speakers
+    );
+    assert_eq!(
+        Err(Speaker::Narrator),
+        Character::try_from(&Speaker::Narrator)
+    );
+
+    assert_eq!(
+        Section::Named("forest::lake".to_owned()),
+        Section::from(SectionName::ForestLake)
+    );
+    for section in dialog.sections.keys() {
+        assert_eq!(
+            section,
+            &Section::from(SectionName::try_from(section).unwrap())
+        );
+    }
+    let missing = Section::Named("missing".to_owned());
+    assert_eq!(Err(missing.clone()), SectionName::try_from(&missing));
+}
+
+#[test]
+fn test_name_collisions() {
+    let error = |raw: &str| build::to_rust(&Dialog::from_str(raw).unwrap()).unwrap_err();
+
+    assert_eq!(
+        BuildError::NameCollision("oldMan".to_owned(), "old_man".to_owned()),
+        error("@oldMan Hi\n@old_man Hi")
+    );
+    assert_eq!(
+        BuildError::NameCollision("initial".to_owned(), "initial".to_owned()),
+        error("Hi\n\n#initial\nHi")
+    );
+    assert_eq!(BuildError::InvalidName("_".to_owned()), error("@_ Hi"));
+}
+
+#[test]
+fn test_generate() {
+    let directory = std::env::temp_dir().join(format!("dlg-build-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(
+        directory.join("story.dlg"),
+        ":include \"forest.dlg\"\n@alice Hi",
+    )
+    .unwrap();
+    std::fs::write(
+        directory.join("forest.dlg"),
+        "@bob:sad Hi\n\n#lake\n@bob Cold",
+    )
+    .unwrap();
+
+    let out = directory.join("dialogs.rs");
+    build::generate(&[directory.join("story.dlg")], &out).unwrap();
+    let generated = std::fs::read_to_string(&out).unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert!(generated.contains("    Bob(BobState),"));
+    assert!(generated.contains("    /// `#forest::lake`\n    ForestLake,"));
+}
@@ -0,0 +1,129 @@
+use std::str::FromStr;
+
+use dlg::{
+    character_requirements,
+    parser::{ParseErrorKind, State},
+    prelude::*,
+};
+use indexmap::indexmap;
+
+#[test]
+fn test_comments() {
+    let raw = "
+            @alice First // about the first part
+            // a line with only a comment
+            Second /* inline */ part
+            /* a comment
+               on several lines /* with a nested one */
+            */
+            Third
+
+            @bob Last // no newline at the end of input";
+
+    let actual = Dialog::from_str(raw).unwrap();
+
+    let expected = Dialog {
+        characters: character_requirements! {
+            "alice" => [],
+            "bob" => []
+        },
+        sections: indexmap! {
+            Section::Initial => vec![
+                Line::Phrase {
+                    speaker: Speaker::Character(Alias("alice".to_string()), State::Default),
+                    lines: vec!["First".into(), "Second part".into(), "Third".into()],
+                    id: None,
+                },
+                Line::Phrase {
+                    speaker: Speaker::Character(Alias("bob".to_string()), State::Default),
+                    lines: vec!["Last".into()],
+                    id: None,
+                },
+            ],
+        },
+        includes: vec![],
+    };
+
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_comments_around_commands() {
+    let raw = "/* header */ @alice Hi
+        :if met // condition
+        Again
+        :endif
+        :move #next // where to go
+        #next
+        Done";
+
+    let dialog = Dialog::from_str(raw).unwrap();
+
+    let lines = &dialog.sections[&Section::Initial];
+    assert_eq!(3, lines.len());
+    assert!(matches!(
+        &lines[0],
+        Line::Phrase { speaker: Speaker::Character(alias, _), .. } if alias.0 == "alice"
+    ));
+    assert!(matches!(&lines[1], Line::Conditional(branches) if branches.len() == 1));
+    assert!(matches!(
+        &lines[2],
+        Line::Move(Section::Named(name)) if name == "next"
+    ));
+}
+
+#[test]
+fn test_unclosed_comment() {
+    let raw = "Text\n/* outer /* inner */ still open";
+
+    let actual = Dialog::from_str(raw).unwrap_err();
+
+    assert_eq!(ParseErrorKind::UnclosedComment, actual.kind);
+    assert_eq!("2:1: unclosed comment", actual.to_string());
+}
+
+#[test]
+fn test_notes() {
+    let raw = "
+            // greeting
+            @alice Hi
+
+            :if met
+            /* only
+               for friends */
+            Again
+            :endif
+
+            #next
+
+            Bye // the end";
+
+    let (dialog, notes) = Dialog::parse_with_notes(raw).unwrap();
+    assert_eq!(dialog, Dialog::from_str(raw).unwrap());
+
+    let texts = notes.iter().map(|n| n.text.as_str()).collect::<Vec<_>>();
+    assert_eq!(
+        vec!["greeting", "only\n               for friends", "the end"],
+        texts
+    );
+
+    assert_eq!(Section::Initial, notes[0].position.section);
+    assert_eq!(0, notes[0].position.line_index);
+    assert_eq!(2, notes[0].span.line);
+    assert!(matches!(
+        notes[0].line(&dialog),
+        Some(Line::Phrase { lines, .. }) if lines[0].to_string() == "Hi"
+    ));
+
+    assert_eq!(vec![(1, 0)], notes[1].position.blocks);
+    assert!(matches!(
+        notes[1].line(&dialog),
+        Some(Line::Phrase { lines, .. }) if lines[0].to_string() == "Again"
+    ));
+
+    assert_eq!(
+        Section::Named("next".to_string()),
+        notes[2].position.section
+    );
+    assert_eq!(None, notes[2].line(&dialog));
+}
@@ -0,0 +1,166 @@
+use std::str::FromStr;
+
+use dlg::{
+    character_requirements,
+    parser::{Branch, Comparison, Condition, ParseErrorKind, State, Value},
+    prelude::*,
+};
+use indexmap::indexmap;
+
+fn phrase(speaker: Speaker, text: &str) -> Line {
+    Line::Phrase {
+        speaker,
+        lines: vec![text.into()],
+        id: None,
+    }
+}
+
+fn bob() -> Speaker {
+    Speaker::Character(Alias("bob".to_string()), State::Default)
+}
+
+#[test]
+fn test_conditional_block() {
+    let raw = r"
+            @bob Before
+
+            :if coins >= 10 && !angry
+            Rich
+            :elseif coins > 0
+            @ Poor
+            :else
+            Broke
+            :endif
+
+            After
+        ";
+
+    let actual = Dialog::from_str(raw).unwrap();
+
+    let expected = Dialog {
+        characters: character_requirements! {
+            "bob" => []
+        },
+        sections: indexmap! {
+            Section::Initial => vec![
+                phrase(bob(), "Before"),
+                Line::Conditional(vec![
+                    Branch {
+                        condition: Some(Condition::And(
+                            Box::new(Condition::Compare(
+                                Box::new(Condition::Variable("coins".to_string())),
+                                Comparison::GreaterOrEqual,
+                                Box::new(Condition::Value(Value::Integer(10))),
+                            )),
+                            Box::new(Condition::Not(Box::new(Condition::Variable("angry".to_string())))),
+                        )),
+                        lines: vec![phrase(bob(), "Rich")],
+                    },
+                    Branch {
+                        condition: Some(Condition::Compare(
+                            Box::new(Condition::Variable("coins".to_string())),
+                            Comparison::Greater,
+                            Box::new(Condition::Value(Value::Integer(0))),
+                        )),
+                        lines: vec![phrase(Speaker::Narrator, "Poor")],
+                    },
+                    Branch {
+                        condition: None,
+                        lines: vec![phrase(bob(), "Broke")],
+                    },
+                ]),
+                phrase(bob(), "After"),
+            ]
+        },
+        includes: vec![],
+    };
+
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_nested_conditional_playback() {
+    let raw = r#"
+            :if met_alice
+                :if mood == "happy"
+                    Happy
+                :endif
+                Met
+            :else
+                Not met
+            :endif
+            End
+        "#;
+
+    let dialog = Dialog::from_str(raw).unwrap();
+
+    let play = |state: &mut DialogState| {
+        let mut played = vec![];
+        while let Some(line) = state.current_line(&dialog) {
+            if let Line::Phrase { lines, .. } = line {
+                played.push(lines[0].to_string());
+            }
+            state.cursor.next_line_index();
+        }
+        played
+    };
+
+    let mut state = DialogState::default();
+    assert_eq!(vec!["Not met", "End"], play(&mut state));
+
+    let mut state = DialogState::default();
+    state.variables.set("met_alice", true);
+    state.variables.set("mood", "happy");
+    assert_eq!(vec!["Happy", "Met", "End"], play(&mut state));
+}
+
+#[test]
+fn test_conditional_errors() {
+    let cases = [
+        (
+            ":if\nText\n:endif",
+            ParseErrorKind::MissingCondition("if".to_string()),
+        ),
+        (
+            ":if a ==\nText\n:endif",
+            ParseErrorKind::InvalidCondition("unexpected end of condition".to_string()),
+        ),
+        (":if a\nText", ParseErrorKind::UnclosedCondition),
+        (":if a\nText\n#section", ParseErrorKind::UnclosedCondition),
+        (
+            ":else\nText",
+            ParseErrorKind::UnexpectedCommand("else".to_string()),
+        ),
+        (
+            ":if a\n:else\n:elseif b\n:endif",
+            ParseErrorKind::UnexpectedCommand("elseif".to_string()),
+        ),
+    ];
+
+    for (raw, kind) in cases {
+        assert_eq!(kind, Dialog::from_str(raw).unwrap_err().kind, "{}", raw);
+    }
+}
+
+#[test]
+fn test_deep_conditions() {
+    let deep = "nested deeper than 256 levels".to_string();
+    let nots = format!(":if {}a\nText\n:endif", "!".repeat(100_000));
+    let chain = format!(":if {}\nText\n:endif", vec!["a"; 100_000].join(" && "));
+    for raw in [nots, chain] {
+        assert_eq!(
+            ParseErrorKind::InvalidCondition(deep.clone()),
+            Dialog::from_str(&raw).unwrap_err().kind
+        );
+    }
+
+    let block = format!("{}a{}", "(".repeat(100_000), ")".repeat(100_000));
+    let raw = format!("Text {{{}}}", block);
+    assert_eq!(
+        ParseErrorKind::InvalidInlineBlock(format!("`{{{}}}`: {}", block, deep)),
+        Dialog::from_str(&raw).unwrap_err().kind
+    );
+
+    let shallow = format!(":if {}a{}\nText\n:endif", "(".repeat(200), ")".repeat(200));
+    assert!(Dialog::from_str(&shallow).is_ok());
+}
@@ -0,0 +1,93 @@
+use std::str::FromStr;
+
+use dlg::{
+    parser::{ParseErrorKind, Span},
+    prelude::*,
+};
+
+#[test]
+fn test_option_without_menu() {
+    let raw = r"
+Text
+:opt(#section_1) Section 1
+";
+
+    let actual = Dialog::from_str(raw).unwrap_err();
+
+    assert_eq!(ParseErrorKind::OptionWithoutMenu, actual.kind);
+    assert_eq!(
+        Span {
+            start: 6,
+            end: 22,
+            line: 3,
+            column: 1
+        },
+        actual.span
+    );
+}
+
+#[test]
+fn test_text_after_menu() {
+    let raw = r"
+            :menu Title
+            :opt(#section_1) Section 1
+
+            Text after menu
+        ";
+
+    let actual = Dialog::from_str(raw).unwrap_err();
+
+    assert_eq!(ParseErrorKind::TextAfterMenu, actual.kind);
+    assert_eq!(5, actual.span.line);
+    assert_eq!(13, actual.span.column);
+    assert_eq!("Text after menu", &raw[actual.span.range()]);
+}
+
+#[test]
+fn test_narrator_state() {
+    let raw = r"
+            @ Narrator's text
+            @:calm Calm narrator?
+        ";
+
+    let actual = Dialog::from_str(raw).unwrap_err();
+
+    assert_eq!(
+        ParseErrorKind::NarratorState("calm".to_string()),
+        actual.kind
+    );
+    assert_eq!("@:calm", &raw[actual.span.range()]);
+}
+
+#[test]
+fn test_invalid_inline_block() {
+    let raw = r"
+            @alice You have {coins >} coins
+        ";
+
+    let actual = Dialog::from_str(raw).unwrap_err();
+
+    assert!(matches!(actual.kind, ParseErrorKind::InvalidInlineBlock(_)));
+    assert_eq!(29, actual.span.column);
+    assert_eq!(2, actual.span.line);
+}
+
+#[test]
+fn test_invalid_mention() {
+    // a mention with three colons used to panic with "Ты чево наделол"
+    let raw = r"
+            @alice:calm:very:sad Hi
+        ";
+
+    let actual = Dialog::from_str(raw).unwrap_err();
+
+    assert_eq!(
+        ParseErrorKind::InvalidMention("@alice:calm:very:sad".to_string()),
+        actual.kind
+    );
+    assert_eq!("@alice:calm:very:sad", &raw[actual.span.range()]);
+    assert_eq!(
+        "invalid mention `@alice:calm:very:sad`",
+        actual.kind.to_string()
+    );
+}
@@ -0,0 +1,123 @@
+use std::str::FromStr;
+
+use dlg::{
+    character_requirements,
+    parser::{ParseErrorKind, Value},
+    player::{DialogRunner, EventFlow, Step},
+    prelude::*,
+};
+use indexmap::indexmap;
+
+#[test]
+fn test_event() {
+    let raw = r#"
+            :event "door_opened"
+            :event "play_sound" "door creak.ogg" 3 true
+            Someone came in
+        "#;
+
+    let actual = Dialog::from_str(raw).unwrap();
+
+    let expected = Dialog {
+        characters: character_requirements! {},
+        sections: indexmap! {
+            Section::Initial => vec![
+                Line::Event {
+                    name: "door_opened".to_string(),
+                    args: vec![],
+                },
+                Line::Event {
+                    name: "play_sound".to_string(),
+                    args: vec![
+                        Value::String("door creak.ogg".to_string()),
+                        Value::Integer(3),
+                        Value::Bool(true),
+                    ],
+                },
+                Line::Phrase {
+                    speaker: Speaker::Narrator,
+                    lines: vec!["Someone came in".into()],
+                    id: None,
+                },
+            ]
+        },
+        includes: vec![],
+    };
+
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_event_pauses_playback() {
+    let raw = r#"
+            :event "cutscene" 1
+            After cutscene
+        "#;
+
+    let dialog = Dialog::from_str(raw).unwrap();
+    let mut runner = DialogRunner::new(&dialog);
+    let mut events = vec![];
+
+    let mut handler = |name: &str, args: &[Value]| {
+        events.push((name.to_string(), args.to_vec()));
+        EventFlow::Pause
+    };
+
+    assert_eq!(Some(EventFlow::Pause), runner.handle_event(&mut handler));
+    assert_eq!(
+        Step::Event {
+            name: "cutscene",
+            args: &[Value::Integer(1)]
+        },
+        runner.current()
+    );
+    assert_eq!(
+        vec![("cutscene".to_string(), vec![Value::Integer(1)])],
+        events
+    );
+
+    runner.advance();
+    assert!(matches!(
+        runner.current(),
+        Step::Phrase { text, .. } if text == "After cutscene"
+    ));
+}
+
+#[test]
+fn test_event_continues_playback() {
+    let raw = r#"
+            :event "door_opened"
+            Someone came in
+        "#;
+
+    let dialog = Dialog::from_str(raw).unwrap();
+    let mut runner = DialogRunner::new(&dialog);
+
+    let flow = runner.handle_event(&mut |_: &str, _: &[Value]| EventFlow::Continue);
+
+    assert_eq!(Some(EventFlow::Continue), flow);
+    assert!(matches!(
+        runner.current(),
+        Step::Phrase { text, .. } if text == "Someone came in"
+    ));
+    assert_eq!(
+        None,
+        runner.handle_event(&mut |_: &str, _: &[Value]| EventFlow::Pause)
+    );
+}
+
+#[test]
+fn test_invalid_event() {
+    let cases = [
+        (":event\nText", "`event` without name"),
+        (":event 42", "event name must be a string, got `42`"),
+        (":event \"unclosed", "unclosed string `\"unclosed`"),
+    ];
+
+    for (raw, message) in cases {
+        assert_eq!(
+            ParseErrorKind::InvalidEvent(message.to_string()),
+            Dialog::from_str(raw).unwrap_err().kind
+        );
+    }
+}
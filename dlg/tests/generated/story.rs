@@ -0,0 +1,162 @@
+// Generated by `dlg::build`, do not edit
+
+/// Characters of the dialogs
+#[allow(dead_code)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum Character {
+    /// `@alice`
+    Alice(AliceState),
+    /// `@bob`
+    Bob(BobState),
+    /// `@old_man`
+    OldMan(OldManState),
+}
+
+/// States of `@alice`
+#[allow(dead_code)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Default)]
+pub enum AliceState {
+    /// `@alice`
+    #[default]
+    Default,
+    /// `@alice:angry`
+    Angry,
+    /// `@alice:happy`
+    Happy,
+}
+
+impl From<AliceState> for ::dlg::parser::State {
+    fn from(state: AliceState) -> Self {
+        match state {
+            AliceState::Default => ::dlg::parser::State::Default,
+            AliceState::Angry => ::dlg::parser::State::Named(::std::string::String::from("angry")),
+            AliceState::Happy => ::dlg::parser::State::Named(::std::string::String::from("happy")),
+        }
+    }
+}
+
+/// States of `@bob`
+#[allow(dead_code)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Default)]
+pub enum BobState {
+    /// `@bob`
+    #[default]
+    Default,
+    /// `@bob:sad`
+    Sad,
+}
+
+impl From<BobState> for ::dlg::parser::State {
+    fn from(state: BobState) -> Self {
+        match state {
+            BobState::Default => ::dlg::parser::State::Default,
+            BobState::Sad => ::dlg::parser::State::Named(::std::string::String::from("sad")),
+        }
+    }
+}
+
+/// States of `@old_man`
+#[allow(dead_code)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Default)]
+pub enum OldManState {
+    /// `@old_man`
+    #[default]
+    Default,
+    /// `@old_man:sleepy`
+    Sleepy,
+}
+
+impl From<OldManState> for ::dlg::parser::State {
+    fn from(state: OldManState) -> Self {
+        match state {
+            OldManState::Default => ::dlg::parser::State::Default,
+            OldManState::Sleepy => ::dlg::parser::State::Named(::std::string::String::from("sleepy")),
+        }
+    }
+}
+
+impl From<Character> for ::dlg::parser::Alias {
+    fn from(character: Character) -> Self {
+        match character {
+            Character::Alice(_) => ::dlg::parser::Alias(::std::string::String::from("alice")),
+            Character::Bob(_) => ::dlg::parser::Alias(::std::string::String::from("bob")),
+            Character::OldMan(_) => ::dlg::parser::Alias(::std::string::String::from("old_man")),
+        }
+    }
+}
+
+impl From<Character> for ::dlg::parser::State {
+    fn from(character: Character) -> Self {
+        match character {
+            Character::Alice(state) => state.into(),
+            Character::Bob(state) => state.into(),
+            Character::OldMan(state) => state.into(),
+        }
+    }
+}
+
+impl From<Character> for ::dlg::parser::Speaker {
+    fn from(character: Character) -> Self {
+        ::dlg::parser::Speaker::Character(character.into(), character.into())
+    }
+}
+
+impl TryFrom<&::dlg::parser::Speaker> for Character {
+    type Error = ::dlg::parser::Speaker;
+
+    fn try_from(speaker: &::dlg::parser::Speaker) -> Result<Self, Self::Error> {
+        let (alias, state) = match speaker {
+            ::dlg::parser::Speaker::Character(alias, ::dlg::parser::State::Named(state)) => (alias.0.as_str(), Some(state.as_str())),
+            ::dlg::parser::Speaker::Character(alias, ::dlg::parser::State::Default) => (alias.0.as_str(), None),
+            ::dlg::parser::Speaker::Narrator => return Err(speaker.clone()),
+        };
+        match (alias, state) {
+            ("alice", None) => Ok(Character::Alice(AliceState::Default)),
+            ("alice", Some("angry")) => Ok(Character::Alice(AliceState::Angry)),
+            ("alice", Some("happy")) => Ok(Character::Alice(AliceState::Happy)),
+            ("bob", None) => Ok(Character::Bob(BobState::Default)),
+            ("bob", Some("sad")) => Ok(Character::Bob(BobState::Sad)),
+            ("old_man", None) => Ok(Character::OldMan(OldManState::Default)),
+            ("old_man", Some("sleepy")) => Ok(Character::OldMan(OldManState::Sleepy)),
+            _ => Err(speaker.clone()),
+        }
+    }
+}
+
+/// Sections of the dialogs
+#[allow(dead_code)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum SectionName {
+    /// Lines before the first section
+    Initial,
+    /// `#walk`
+    Walk,
+    /// `#forest::lake`
+    ForestLake,
+}
+
+impl From<SectionName> for ::dlg::parser::Section {
+    fn from(section: SectionName) -> Self {
+        match section {
+            SectionName::Initial => ::dlg::parser::Section::Initial,
+            SectionName::Walk => ::dlg::parser::Section::Named(::std::string::String::from("walk")),
+            SectionName::ForestLake => ::dlg::parser::Section::Named(::std::string::String::from("forest::lake")),
+        }
+    }
+}
+
+impl TryFrom<&::dlg::parser::Section> for SectionName {
+    type Error = ::dlg::parser::Section;
+
+    fn try_from(section: &::dlg::parser::Section) -> Result<Self, Self::Error> {
+        let name = match section {
+            ::dlg::parser::Section::Initial => return Ok(SectionName::Initial),
+            ::dlg::parser::Section::Named(name) => name.as_str(),
+        };
+        match name {
+            "walk" => Ok(SectionName::Walk),
+            "forest::lake" => Ok(SectionName::ForestLake),
+            _ => Err(section.clone()),
+        }
+    }
+}
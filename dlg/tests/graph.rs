@@ -0,0 +1,111 @@
+use std::str::FromStr;
+
+use dlg::{
+    export::graph::{to_dot, to_mermaid, Edge, EdgeKind, Graph, Node, NodeKind},
+    prelude::*,
+};
+
+const RAW: &str = r#"
+Where to?
+:menu
+:opt(#walk) Go for a "walk"
+:opt(#nowhere) Stay
+:opt Think
+
+#walk
+:if tired
+    :move #home
+:endif
+Nice weather
+
+#home
+:back
+
+#attic
+Dust
+"#;
+
+fn node(section: &str, kind: NodeKind) -> Node {
+    let section = match section {
+        "initial" => Section::Initial,
+        name => Section::Named(name.to_owned()),
+    };
+    Node { section, kind }
+}
+
+#[test]
+fn test_graph() {
+    let dialog = Dialog::from_str(RAW).unwrap();
+    let graph = Graph::new(&dialog);
+
+    assert_eq!(
+        vec![
+            node("initial", NodeKind::Section),
+            node("walk", NodeKind::Section),
+            node("home", NodeKind::Section),
+            node("attic", NodeKind::Unreachable),
+            node("nowhere", NodeKind::Dangling),
+        ],
+        graph.nodes
+    );
+
+    let edge = |from, to, kind| Edge { from, to, kind };
+    assert_eq!(
+        vec![
+            edge(0, 1, EdgeKind::Option(Some("Go for a \"walk\"".to_owned()))),
+            edge(0, 4, EdgeKind::Option(Some("Stay".to_owned()))),
+            edge(0, 1, EdgeKind::FallThrough),
+            edge(1, 2, EdgeKind::Move),
+            edge(1, 2, EdgeKind::FallThrough),
+        ],
+        graph.edges
+    );
+}
+
+#[test]
+fn test_dot() {
+    let dialog = Dialog::from_str(RAW).unwrap();
+
+    assert_eq!(
+        r##"digraph dialog {
+    node [shape=box];
+    n0 [label="initial"];
+    n1 [label="#walk"];
+    n2 [label="#home"];
+    n3 [label="#attic", style=filled, fillcolor=orange];
+    n4 [label="#nowhere", style="filled,dashed", fillcolor=red, fontcolor=white];
+    n0 -> n1 [label="Go for a \"walk\""];
+    n0 -> n4 [label="Stay"];
+    n0 -> n1 [label="fall-through", style=dashed];
+    n1 -> n2 [label=":move"];
+    n1 -> n2 [label="fall-through", style=dashed];
+}
+"##,
+        to_dot(&dialog)
+    );
+}
+
+#[test]
+fn test_mermaid() {
+    let dialog = Dialog::from_str(RAW).unwrap();
+
+    assert_eq!(
+        r##"flowchart TD
+    n0["initial"]
+    n1["#walk"]
+    n2["#home"]
+    n3["#attic"]
+    n4["#nowhere"]
+    n0 -->|"Go for a #quot;walk#quot;"| n1
+    n0 -->|"Stay"| n4
+    n0 -.->|"fall-through"| n1
+    n1 -->|":move"| n2
+    n1 -.->|"fall-through"| n2
+    classDef unreachable fill:orange
+    class n3 unreachable
+    classDef dangling fill:red,color:white,stroke-dasharray:4
+    class n4 dangling
+"##,
+        to_mermaid(&dialog)
+    );
+}
@@ -0,0 +1,77 @@
+use std::str::FromStr;
+
+use dlg::{
+    parser::{Condition, FormattedText, ParseErrorKind, Segment, Value},
+    player::{DialogRunner, Step},
+    prelude::*,
+};
+
+#[test]
+fn test_inline_blocks() {
+    let raw = r"
+            You have {coins} coins
+            {coins >= 10}
+        ";
+
+    let actual = Dialog::from_str(raw).unwrap();
+
+    let expected = vec![Line::Phrase {
+        speaker: Speaker::Narrator,
+        lines: vec![
+            FormattedText(vec![
+                Segment::Text("You have ".to_string()),
+                Segment::Expression(Condition::Variable("coins".to_string())),
+                Segment::Text(" coins".to_string()),
+            ]),
+            FormattedText(vec![Segment::Expression("coins >= 10".parse().unwrap())]),
+        ],
+        id: None,
+    }];
+
+    assert_eq!(&expected, &actual.sections[&Section::Initial]);
+}
+
+#[test]
+fn test_resolve_inline_blocks() {
+    let raw = r#"
+            @alice {name}, you have {coins} coins
+            Rich: {coins >= 10}, unknown: "{unknown}", literal: {"text"}
+        "#;
+
+    let dialog = Dialog::from_str(raw).unwrap();
+    let mut state = DialogState::default();
+    state.variables.set("name", "Bob");
+    state.variables.set("coins", 12);
+    let mut runner = DialogRunner::with_state(&dialog, state);
+
+    assert!(matches!(
+        runner.current(),
+        Step::Phrase { text, .. } if text == "Bob, you have 12 coins"
+    ));
+
+    runner.advance();
+    assert!(matches!(
+        runner.current(),
+        Step::Phrase { text, .. } if text == r#"Rich: true, unknown: "", literal: text"#
+    ));
+}
+
+#[test]
+fn test_formatted_text_display() {
+    let text = FormattedText::from_str("You have {coins} coins, {coins > 1}").unwrap();
+
+    assert_eq!("You have {coins} coins, {coins > 1}", text.to_string());
+    assert_eq!(
+        Err(ParseErrorKind::InvalidInlineBlock(
+            "`{coins`: unclosed".to_string()
+        )),
+        FormattedText::from_str("{coins")
+    );
+    assert_eq!(
+        "42",
+        FormattedText(vec![Segment::Expression(Condition::Value(Value::Integer(
+            42
+        )))])
+        .resolve(&Default::default())
+    );
+}
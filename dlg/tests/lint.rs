@@ -0,0 +1,188 @@
+use dlg::{
+    lint::{lint, Diagnostic, Lint, Severity},
+    parser::ParseErrorKind,
+    prelude::*,
+};
+
+fn lints(raw: &str) -> Vec<(Lint, usize)> {
+    lint(raw)
+        .into_iter()
+        .map(|diagnostic| (diagnostic.lint, diagnostic.span.line))
+        .collect()
+}
+
+#[test]
+fn test_valid_dialog() {
+    let raw = r"
+            @alice Hello
+            :menu Where to?
+            :opt(#walk) Walk
+            :opt(#home) Home
+
+            #walk
+            :move #home
+
+            #home
+            @bob Welcome
+        ";
+
+    assert_eq!(Vec::<Diagnostic>::new(), lint(raw));
+}
+
+#[test]
+fn test_dangling_links() {
+    let raw = r"
+            :move #nowhere
+            :menu
+            :opt(#walk) Walk
+            :opt(@alice; #missing) Missing
+        ";
+
+    assert_eq!(
+        vec![
+            (Lint::DanglingLink(Section::Named("nowhere".to_string())), 2),
+            (Lint::DanglingLink(Section::Named("walk".to_string())), 4),
+            (Lint::DanglingLink(Section::Named("missing".to_string())), 5),
+        ],
+        lints(raw)
+    );
+}
+
+#[test]
+fn test_unreachable_and_empty_sections() {
+    let raw = r"
+            Hello
+            :if visited
+                :move #second
+            :endif
+
+            #first
+            Never shown
+
+            #second
+            Shown
+
+            #between
+
+            #section_without_text
+        ";
+
+    assert_eq!(
+        vec![
+            (
+                Lint::UnreachableSection(Section::Named("first".to_string())),
+                7
+            ),
+            (
+                Lint::EmptySection(Section::Named("between".to_string())),
+                13
+            ),
+            (
+                Lint::UnreachableSection(Section::Named("between".to_string())),
+                13
+            ),
+            (
+                Lint::EmptySection(Section::Named("section_without_text".to_string())),
+                15
+            ),
+            (
+                Lint::UnreachableSection(Section::Named("section_without_text".to_string())),
+                15
+            ),
+        ],
+        lints(raw)
+    );
+}
+
+#[test]
+fn test_menus() {
+    let raw = r"
+            :menu Empty menu
+
+            #options
+            :menu(answer)
+            :opt(=yes) Yes
+            :opt(=no)
+        ";
+
+    let diagnostics = lint(raw);
+
+    assert_eq!(
+        vec![
+            (Lint::MenuWithoutOptions, 2),
+            (
+                Lint::UnreachableSection(Section::Named("options".to_string())),
+                4
+            ),
+            (Lint::OptionWithoutTitle, 7),
+        ],
+        diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.lint.clone(), diagnostic.span.line))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![Severity::Error, Severity::Warning, Severity::Warning],
+        diagnostics
+            .iter()
+            .map(Diagnostic::severity)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        "7:13: warning: option has no title",
+        diagnostics[2].to_string()
+    );
+}
+
+#[test]
+fn test_parse_error() {
+    let diagnostics = lint("\n:opt(#walk) Walk");
+
+    assert_eq!(1, diagnostics.len());
+    assert_eq!(
+        Lint::Parse(ParseErrorKind::OptionWithoutMenu),
+        diagnostics[0].lint
+    );
+    assert_eq!(Severity::Error, diagnostics[0].severity());
+    assert_eq!(2, diagnostics[0].span.line);
+}
+
+#[test]
+fn test_move_cycles() {
+    let raw = r"
+            Start
+            :move #a
+
+            #a
+            :move #b
+
+            #b
+            :move #a
+
+            #c
+            :move #c
+
+            #d
+            Shown
+            :move #d
+        ";
+
+    let named = |name: &str| Section::Named(name.to_string());
+    let cycles = lint(raw)
+        .into_iter()
+        .filter(|diagnostic| matches!(diagnostic.lint, Lint::MoveCycle(_)))
+        .map(|diagnostic| (diagnostic.lint, diagnostic.span.line))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            (Lint::MoveCycle(vec![named("a"), named("b")]), 6),
+            (Lint::MoveCycle(vec![named("c")]), 12),
+        ],
+        cycles
+    );
+    assert_eq!(
+        "`:move` loops through #a, #b without showing anything",
+        cycles[0].0.to_string()
+    );
+    assert_eq!(Severity::Error, cycles[0].0.severity());
+}
@@ -0,0 +1,261 @@
+use std::str::FromStr;
+
+use dlg::{
+    localization::{generate_id, messages, to_pot, Catalog, MessageKind},
+    parser::{Effect, Menu, MenuOption, ParseErrorKind},
+    prelude::*,
+};
+
+const RAW: &str = r#"
+@alice Hi, [b]Bob[/b]!
+How are you? [#greeting]
+
+:menu How is Bob? [#mood]
+:opt(#fine) Fine
+:opt(#bad) Bad [#mood_bad]
+
+#fine
+@bob Fine, "thanks"
+
+#bad
+@bob Fine, "thanks"
+"#;
+
+#[test]
+fn test_explicit_ids() {
+    let dialog = Dialog::from_str(RAW).unwrap();
+
+    assert_eq!(
+        Line::Phrase {
+            speaker: Speaker::Character(Alias("alice".to_owned()), State::Default),
+            lines: vec!["Hi, [b]Bob[/b]!".parse().unwrap(), "How are you?".into()],
+            id: Some("greeting".to_owned()),
+        },
+        dialog.sections[&Section::Initial][0]
+    );
+
+    let menu = match &dialog.sections[&Section::Initial][1] {
+        Line::Menu(menu) => menu,
+        line => panic!("expected menu, got {:?}", line),
+    };
+    assert_eq!(Some("How is Bob?".to_owned()), menu.title);
+    assert_eq!(Some("mood".to_owned()), menu.id);
+    assert_eq!(None, menu.options[0].id);
+    assert_eq!(Some("Bad".to_owned()), menu.options[1].title);
+    assert_eq!(Some("mood_bad".to_owned()), menu.options[1].id);
+
+    // only the end of the last line of a phrase is checked for the marker
+    let dialog = Dialog::from_str("First [#first]\nSecond [#second]").unwrap();
+    assert_eq!(
+        Line::Phrase {
+            speaker: Speaker::Narrator,
+            lines: vec!["First [#first]".into(), "Second".into()],
+            id: Some("second".to_owned()),
+        },
+        dialog.sections[&Section::Initial][0]
+    );
+
+    // marker without text is a text
+    let dialog = Dialog::from_str("[#alone]").unwrap();
+    assert_eq!(
+        Line::Phrase {
+            speaker: Speaker::Narrator,
+            lines: vec!["[#alone]".into()],
+            id: None,
+        },
+        dialog.sections[&Section::Initial][0]
+    );
+}
+
+#[test]
+fn test_duplicate_id() {
+    let error = Dialog::from_str("Hi [#same]\n\n:menu Where? [#same]\n:opt Here").unwrap_err();
+
+    assert_eq!(
+        ParseErrorKind::DuplicateLineId("same".to_owned()),
+        error.kind
+    );
+    assert_eq!(3, error.span.line);
+}
+
+#[test]
+fn test_ids_round_trip() {
+    let dialog = Dialog::from_str(RAW).unwrap();
+    let printed = dialog.to_dlg_string();
+
+    assert!(printed.contains("@alice Hi, [b]Bob[/b]!\nHow are you? [#greeting]\n"));
+    assert!(printed.contains(":menu How is Bob? [#mood]\n"));
+    assert!(printed.contains(":opt(#bad) Bad [#mood_bad]\n"));
+    assert_eq!(dialog, Dialog::from_str(&printed).unwrap());
+}
+
+#[test]
+fn test_messages() {
+    let dialog = Dialog::from_str(RAW).unwrap();
+    let messages = messages(&dialog);
+
+    let ids = messages
+        .iter()
+        .map(|message| message.id.as_str())
+        .collect::<Vec<_>>();
+    let bob = Speaker::Character(Alias("bob".to_owned()), State::Default);
+    let fine = generate_id(
+        &Section::Named("fine".to_owned()),
+        &MessageKind::Phrase(bob.clone()),
+        "Fine, \"thanks\"",
+    );
+    let bad = generate_id(
+        &Section::Named("bad".to_owned()),
+        &MessageKind::Phrase(bob),
+        "Fine, \"thanks\"",
+    );
+    let option = generate_id(&Section::Initial, &MessageKind::OptionTitle, "Fine");
+    assert_eq!(
+        vec!["greeting", "mood", &option, "mood_bad", &fine, &bad],
+        ids
+    );
+
+    assert_eq!("Hi, [b]Bob[/b]!\nHow are you?", messages[0].text);
+    assert_eq!(16, fine.len());
+    assert_ne!(fine, bad);
+
+    // generated IDs are the same in every build
+    assert_eq!(
+        "5d96e1b2273e1497",
+        generate_id(&Section::Initial, &MessageKind::MenuTitle, "Title")
+    );
+}
+
+#[test]
+fn test_pot() {
+    let dialog = Dialog::from_str(
+        r#"
+        @alice:happy Hi, "Bob"
+        How are you? [#greeting]
+        :menu
+        :opt(#end) Bye [#bye]
+        #end
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        r#"msgid ""
+msgstr ""
+"Content-Type: text/plain; charset=UTF-8\n"
+
+#. initial, @alice:happy
+msgctxt "greeting"
+msgid ""
+"Hi, \"Bob\"\n"
+"How are you?"
+msgstr ""
+
+#. initial, option
+msgctxt "bye"
+msgid "Bye"
+msgstr ""
+"#,
+        to_pot(&dialog)
+    );
+}
+
+#[test]
+fn test_catalog() {
+    let po = r#"
+# Russian translation
+msgid ""
+msgstr ""
+"Content-Type: text/plain; charset=UTF-8\n"
+
+#. initial, @alice
+msgctxt "greeting"
+msgid "Hi"
+msgstr ""
+"Привет,\n"
+"\"Боб\""
+
+#, fuzzy
+msgctxt "fuzzy"
+msgid "Maybe"
+msgstr "Может быть"
+
+msgctxt "untranslated"
+msgid "Nothing"
+msgstr ""
+msgid "Without context"
+msgstr "Без контекста"
+"#;
+
+    let catalog = Catalog::from_str(po).unwrap();
+
+    assert_eq!(2, catalog.len());
+    assert_eq!(Some("Привет,\n\"Боб\""), catalog.get("greeting"));
+    assert_eq!(Some("Без контекста"), catalog.get("Without context"));
+    assert_eq!(None, catalog.get("fuzzy"));
+    assert_eq!(None, catalog.get("untranslated"));
+
+    let error = Catalog::from_str("msgid \"Hi\"\nmsgstr \"Привет\\q\"").unwrap_err();
+    assert_eq!(2, error.line);
+
+    let error = Catalog::from_str("msgid \"Hi\"\nmsgtext \"Привет\"").unwrap_err();
+    assert_eq!(2, error.line);
+}
+
+#[test]
+fn test_translate() {
+    let mut dialog = Dialog::from_str(RAW).unwrap();
+    let bob_line = |section: &str| {
+        generate_id(
+            &Section::Named(section.to_owned()),
+            &MessageKind::Phrase(Speaker::Character(Alias("bob".to_owned()), State::Default)),
+            "Fine, \"thanks\"",
+        )
+    };
+
+    let mut catalog = Catalog::default();
+    catalog.insert("greeting", "Привет, [b]Боб[/b]!\nКак дела?");
+    catalog.insert("mood", "Как Боб?");
+    catalog.insert("mood_bad", "Плохо");
+    catalog.insert(bob_line("bad"), "Нормально, спасибо");
+    // translation with broken markup is not used
+    catalog.insert(bob_line("fine"), "[b]Хорошо");
+
+    dialog.translate(&catalog);
+
+    assert_eq!(
+        Line::Phrase {
+            speaker: Speaker::Character(Alias("alice".to_owned()), State::Default),
+            lines: vec!["Привет, [b]Боб[/b]!".parse().unwrap(), "Как дела?".into()],
+            id: Some("greeting".to_owned()),
+        },
+        dialog.sections[&Section::Initial][0]
+    );
+    assert_eq!(
+        Line::Menu(Menu {
+            variable: None,
+            title: Some("Как Боб?".to_owned()),
+            id: Some("mood".to_owned()),
+            options: vec![
+                MenuOption {
+                    title: Some("Fine".to_owned()),
+                    id: None,
+                    effects: vec![Effect::Jump(Section::Named("fine".to_owned()))],
+                },
+                MenuOption {
+                    title: Some("Плохо".to_owned()),
+                    id: Some("mood_bad".to_owned()),
+                    effects: vec![Effect::Jump(Section::Named("bad".to_owned()))],
+                },
+            ],
+        }),
+        dialog.sections[&Section::Initial][1]
+    );
+
+    let bob = |section: &str| match &dialog.sections[&Section::Named(section.to_owned())][0] {
+        Line::Phrase { lines, .. } => lines[0].to_string(),
+        line => panic!("expected phrase, got {:?}", line),
+    };
+    assert_eq!("Fine, \"thanks\"", bob("fine"));
+    assert_eq!("Нормально, спасибо", bob("bad"));
+}
@@ -0,0 +1,148 @@
+#![cfg(feature = "manifest")]
+
+use std::str::FromStr;
+
+use dlg::{
+    character_requirements,
+    lint::Severity,
+    manifest::{CharacterInfo, Color, Manifest, ManifestIssue},
+    prelude::*,
+};
+
+const MANIFEST: &str = r##"
+[alice]
+name = "Alice Liddell"
+color = "#3366ff"
+states = ["happy", "angry", "sleepy"]
+portraits = { default = "alice", happy = "alice_smiles" }
+
+[bob]
+"##;
+
+fn alias(name: &str) -> Alias {
+    Alias(name.to_owned())
+}
+
+#[test]
+fn test_manifest() {
+    let manifest = Manifest::from_str(MANIFEST).unwrap();
+
+    assert_eq!(
+        vec![alias("alice"), alias("bob")],
+        manifest.characters.keys().cloned().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        &CharacterInfo {
+            name: Some("Alice Liddell".to_owned()),
+            color: Some(Color {
+                r: 0x33,
+                g: 0x66,
+                b: 0xff
+            }),
+            portraits: indexmap::indexmap! {
+                State::Default => "alice".to_owned(),
+                State::Named("happy".to_owned()) => "alice_smiles".to_owned(),
+            },
+            states: vec!["happy".to_owned(), "angry".to_owned(), "sleepy".to_owned()],
+        },
+        manifest.get(&alias("alice")).unwrap()
+    );
+    assert_eq!(
+        &CharacterInfo::default(),
+        manifest.get(&alias("bob")).unwrap()
+    );
+    assert_eq!(
+        "#3366ff",
+        manifest
+            .get(&alias("alice"))
+            .unwrap()
+            .color
+            .unwrap()
+            .to_string()
+    );
+}
+
+#[test]
+fn test_invalid_manifest() {
+    let error = |raw: &str| Manifest::from_str(raw).unwrap_err().message;
+
+    assert_eq!(
+        "alice.color: expected color `#rrggbb`, got `blue`",
+        error("[alice]\ncolor = \"blue\"")
+    );
+    assert_eq!(
+        "alice.colour: unknown key",
+        error("[alice]\ncolour = \"#000000\"")
+    );
+    assert_eq!(
+        "alice.portraits.sad: state is not declared",
+        error("[alice]\nportraits = { sad = \"alice_sad\" }")
+    );
+    assert_eq!(
+        "alice.states: invalid state \"default\"",
+        error("[alice]\nstates = [\"default\"]")
+    );
+    assert_eq!(
+        "alice.name: expected a string, got 42",
+        error("[alice]\nname = 42")
+    );
+    assert_eq!(
+        "alice: expected a table of the character",
+        error("alice = 1")
+    );
+    assert!(error("[alice").contains("line 1"));
+}
+
+#[test]
+fn test_validate() {
+    let manifest = Manifest::from_str(MANIFEST).unwrap();
+    let dialog = Dialog::from_str(
+        r#"
+        @alice:happy Hi
+        @alice:angyr Where is Bob?
+        @alice:scared Bob!
+        @bob Here
+        @carol Hello
+        "#,
+    )
+    .unwrap();
+
+    let issues = manifest.validate(&dialog.characters);
+    assert_eq!(
+        vec![
+            ManifestIssue::UnknownState {
+                alias: alias("alice"),
+                state: "angyr".to_owned(),
+                suggestion: Some("angry".to_owned()),
+            },
+            ManifestIssue::UnknownState {
+                alias: alias("alice"),
+                state: "scared".to_owned(),
+                suggestion: None,
+            },
+            ManifestIssue::UnknownAlias(alias("carol")),
+            ManifestIssue::UnusedState {
+                alias: alias("alice"),
+                state: "angry".to_owned(),
+            },
+            ManifestIssue::UnusedState {
+                alias: alias("alice"),
+                state: "sleepy".to_owned(),
+            },
+        ],
+        issues
+    );
+
+    assert_eq!(Severity::Error, issues[0].severity());
+    assert_eq!(Severity::Warning, issues[3].severity());
+    assert_eq!(
+        "state `angyr` of `alice` is not declared, did you mean `angry`?",
+        issues[0].to_string()
+    );
+
+    let characters = character_requirements! {
+        "alice" => ["happy", "angry", "sleepy"],
+        "bob" => []
+    };
+    assert!(manifest.validate(&characters).is_empty());
+}
@@ -0,0 +1,166 @@
+use std::str::FromStr;
+
+use dlg::{
+    parser::{
+        Condition, FormattedText, Fragment, Markup, ParseErrorKind, Segment, Style, TextEffect,
+    },
+    player::{Animation, DialogRunner, Step},
+    prelude::*,
+};
+
+#[test]
+fn test_markup() {
+    let raw = r"
+            [b]Bold [i]and italic[/i][/b], [color=#ff0000]{name}[/color] [shake]shakes[/shake]
+            Wait[wait=500]... [sighs] [wave]ok[/wave]
+        ";
+
+    let actual = Dialog::from_str(raw).unwrap();
+
+    let expected = vec![Line::Phrase {
+        speaker: Speaker::Narrator,
+        lines: vec![
+            FormattedText(vec![
+                Segment::Styled(
+                    Markup::Bold,
+                    vec![
+                        Segment::Text("Bold ".to_string()),
+                        Segment::Styled(
+                            Markup::Italic,
+                            vec![Segment::Text("and italic".to_string())],
+                        ),
+                    ],
+                ),
+                Segment::Text(", ".to_string()),
+                Segment::Styled(
+                    Markup::Color("#ff0000".to_string()),
+                    vec![Segment::Expression(Condition::Variable("name".to_string()))],
+                ),
+                Segment::Text(" ".to_string()),
+                Segment::Styled(
+                    Markup::Effect(TextEffect::Shake),
+                    vec![Segment::Text("shakes".to_string())],
+                ),
+            ]),
+            FormattedText(vec![
+                Segment::Text("Wait".to_string()),
+                Segment::Wait(500),
+                Segment::Text("... [sighs] ".to_string()),
+                Segment::Styled(
+                    Markup::Effect(TextEffect::Wave),
+                    vec![Segment::Text("ok".to_string())],
+                ),
+            ]),
+        ],
+        id: None,
+    }];
+
+    assert_eq!(&expected, &actual.sections[&Section::Initial]);
+}
+
+#[test]
+fn test_markup_fragments() {
+    let raw = r"
+            [b]Hi, [color=red]{name}[/color][/b]![wait=300] Bye
+        ";
+
+    let dialog = Dialog::from_str(raw).unwrap();
+    let mut state = DialogState::default();
+    state.variables.set("name", "Bob");
+    let mut runner = DialogRunner::with_state(&dialog, state);
+
+    let bold = Style {
+        bold: true,
+        ..Style::default()
+    };
+    let red = Style {
+        color: Some("red".to_string()),
+        ..bold.clone()
+    };
+
+    match runner.current() {
+        Step::Phrase {
+            text, fragments, ..
+        } => {
+            assert_eq!("Hi, Bob! Bye", text);
+            assert_eq!(
+                vec![
+                    Fragment::Text("Hi, ".to_string(), bold),
+                    Fragment::Text("Bob".to_string(), red),
+                    Fragment::Text("!".to_string(), Style::default()),
+                    Fragment::Wait(300),
+                    Fragment::Text(" Bye".to_string(), Style::default()),
+                ],
+                fragments
+            );
+        }
+        step => panic!("expected phrase, got {:?}", step),
+    }
+}
+
+#[test]
+fn test_markup_display() {
+    let raw = "[b]Bold[/b] [color=blue]{coins}[/color][wait=100] [unknown]";
+
+    assert_eq!(raw, FormattedText::from_str(raw).unwrap().to_string());
+}
+
+#[test]
+fn test_unknown_closing_tag() {
+    let raw = "He said [/sighs] [i]so[/x][/i]";
+    let text = FormattedText::from_str(raw).unwrap();
+
+    assert_eq!(
+        vec![
+            Segment::Text("He said [/sighs] ".to_string()),
+            Segment::Styled(Markup::Italic, vec![Segment::Text("so[/x]".to_string())]),
+        ],
+        text.0
+    );
+    assert_eq!(raw, text.to_string());
+}
+
+#[test]
+fn test_invalid_markup() {
+    let cases = [
+        ("[b]Unclosed", "unclosed `[b]`"),
+        ("[b][i]Wrong[/b][/i]", "`[/b]` closes `[i]`"),
+        ("Closed[/wave]", "`[/wave]` without opening tag"),
+        (
+            "[wait=soon]",
+            "`[wait=soon]`: wait time must be a number of milliseconds",
+        ),
+    ];
+
+    let deep = format!("{}x{}", "[b]".repeat(100_000), "[/b]".repeat(100_000));
+    let cases = cases
+        .into_iter()
+        .chain([(deep.as_str(), "tags nested deeper than 256 levels")]);
+
+    for (raw, message) in cases {
+        assert_eq!(
+            ParseErrorKind::InvalidMarkup(message.to_string()),
+            Dialog::from_str(raw).unwrap_err().kind
+        );
+    }
+}
+
+#[test]
+fn test_animation_waits() {
+    let fragments = FormattedText::from_str("ab[wait=100]c")
+        .unwrap()
+        .fragments(&Default::default());
+
+    let mut animation = Animation {
+        target: 3,
+        ..Animation::default()
+    };
+
+    let mut shown = vec![];
+    for _ in 0..6 {
+        animation.tick(&fragments, 50);
+        shown.push(animation.current);
+    }
+
+    assert_eq!(vec![1, 2, 2, 2, 3, 3], shown);
+}
@@ -0,0 +1,124 @@
+use std::str::FromStr;
+
+use dlg::{character_requirements, parser::State, prelude::*};
+use indexmap::indexmap;
+
+#[test]
+fn test_mentions() {
+    let raw = r"
+            @
+            narrator
+
+            @ narrator
+
+            @bob bob
+            
+            @:calm calm bob
+
+            @ narrator
+        ";
+
+    let actual = Dialog::from_str(raw).unwrap();
+
+    let expected = Dialog {
+        characters: character_requirements! {
+            "bob" => ["calm"]
+        },
+        sections: indexmap! {
+            Section::Initial => vec![
+                Line::Phrase {
+                    speaker: Speaker::Narrator,
+                    lines: vec![ "narrator".into() ],
+                    id: None
+                },
+                Line::Phrase {
+                    speaker: Speaker::Narrator,
+                    lines: vec![ "narrator".into() ],
+                    id: None
+                },
+                Line::Phrase {
+                    speaker: Speaker::Character(Alias("bob".to_string()), State::Default),
+                    lines: vec![ "bob".into() ],
+                    id: None
+                },
+                Line::Phrase {
+                    speaker: Speaker::Character(Alias("bob".to_string()), State::Named("calm".to_string())),
+                    lines: vec![ "calm bob".into() ],
+                    id: None
+                },
+                Line::Phrase {
+                    speaker: Speaker::Narrator,
+                    lines: vec![ "narrator".into() ],
+                    id: None
+                },
+            ],
+        },
+        includes: vec![],
+    };
+
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_tokens_at_start_of_input() {
+    let raw = "@bob:calm Hi!\n\n:move #next\n#next\n@ Bye";
+
+    let actual = Dialog::from_str(raw).unwrap();
+
+    let expected = Dialog {
+        characters: character_requirements! {
+            "bob" => ["calm"]
+        },
+        sections: indexmap! {
+            Section::Initial => vec![
+                Line::Phrase {
+                    speaker: Speaker::Character(Alias("bob".to_string()), State::Named("calm".to_string())),
+                    lines: vec![ "Hi!".into() ],
+                    id: None
+                },
+                Line::Move(Section::Named("next".to_string())),
+            ],
+            Section::Named("next".to_string()) => vec![
+                Line::Phrase {
+                    speaker: Speaker::Narrator,
+                    lines: vec![ "Bye".into() ],
+                    id: None
+                },
+            ],
+        },
+        includes: vec![],
+    };
+
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_tokens_in_middle_of_line() {
+    let raw = r"
+            @bob Mail me at bob@example.com, see #faq or ask @alice
+            Time: 10:30, note :this
+        ";
+
+    let actual = Dialog::from_str(raw).unwrap();
+
+    let expected = Dialog {
+        characters: character_requirements! {
+            "bob" => []
+        },
+        sections: indexmap! {
+            Section::Initial => vec![
+                Line::Phrase {
+                    speaker: Speaker::Character(Alias("bob".to_string()), State::Default),
+                    lines: vec![
+                        "Mail me at bob@example.com, see #faq or ask @alice".into(),
+                        "Time: 10:30, note :this".into(),
+                    ],
+                    id: None
+                },
+            ],
+        },
+        includes: vec![],
+    };
+
+    assert_eq!(expected, actual);
+}
@@ -0,0 +1,179 @@
+use std::str::FromStr;
+
+use dlg::{
+    character_requirements,
+    parser::{Effect, Menu, MenuOption, ParseErrorKind, State, Value},
+    prelude::*,
+};
+use indexmap::indexmap;
+
+#[test]
+fn test_basic_menu() {
+    let raw = r"
+            :menu Title
+            :opt(#section_1) Section 1
+            :opt(#section_2) Section 2
+
+
+            #section_1
+
+            Text in section 1
+
+
+            #section_2
+
+            Text in section 2
+        ";
+
+    let actual = Dialog::from_str(raw).unwrap();
+
+    let expected = Dialog {
+        characters: character_requirements! {},
+        sections: indexmap! {
+            Section::Initial => vec![
+                Line::Menu(Menu {
+                    variable: None,
+                    title: Some("Title".to_string()),
+                    id: None,
+                    options: vec![
+                       MenuOption {
+                           title: Some("Section 1".to_string()),
+                           id: None,
+                           effects: vec![Effect::Jump(Section::Named("section_1".to_string()))]
+                       },
+                       MenuOption {
+                           title: Some("Section 2".to_string()),
+                           id: None,
+                           effects: vec![Effect::Jump(Section::Named("section_2".to_string()))]
+                       }
+                    ]
+                }),
+            ],
+            Section::Named("section_1".to_string()) => vec![
+                Line::Phrase {
+                    speaker: Speaker::Narrator,
+                    lines: vec![
+                        "Text in section 1".into()
+                    ],
+                    id: None
+                },
+            ],
+            Section::Named("section_2".to_string()) => vec![
+                Line::Phrase {
+                    speaker: Speaker::Narrator,
+                    lines: vec![
+                        "Text in section 2".into()
+                    ],
+                    id: None
+                },
+            ]
+        },
+        includes: vec![],
+    };
+
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_menu_option_effects() {
+    let raw = r#"
+            :menu(var_name) Title
+            :opt(=value; @alice:calm; #section_1) All effects
+            :opt(= "quoted value" ; @) Narrator
+            :opt() Nothing
+        "#;
+
+    let actual = Dialog::from_str(raw).unwrap();
+
+    let expected = Dialog {
+        characters: character_requirements! {
+            "alice" => ["calm"]
+        },
+        sections: indexmap! {
+            Section::Initial => vec![
+                Line::Menu(Menu {
+                    variable: Some("var_name".to_string()),
+                    title: Some("Title".to_string()),
+                    id: None,
+                    options: vec![
+                        MenuOption {
+                            title: Some("All effects".to_string()),
+                            id: None,
+                            effects: vec![
+                                Effect::Assign(Value::String("value".to_string())),
+                                Effect::Speaker(Speaker::Character(
+                                    Alias("alice".to_string()),
+                                    State::Named("calm".to_string())
+                                )),
+                                Effect::Jump(Section::Named("section_1".to_string())),
+                            ]
+                        },
+                        MenuOption {
+                            title: Some("Narrator".to_string()),
+                            id: None,
+                            effects: vec![
+                                Effect::Assign(Value::String("quoted value".to_string())),
+                                Effect::Speaker(Speaker::Narrator),
+                            ]
+                        },
+                        MenuOption {
+                            title: Some("Nothing".to_string()),
+                            id: None,
+                            effects: vec![]
+                        },
+                    ]
+                }),
+            ],
+        },
+        includes: vec![],
+    };
+
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_invalid_menu_option_effect() {
+    let raw = r"
+            :menu Title
+            :opt(section_1) Forgot the hash
+        ";
+
+    let actual = Dialog::from_str(raw).unwrap_err();
+
+    assert_eq!(
+        ParseErrorKind::InvalidEffect("invalid effect `section_1`".to_string()),
+        actual.kind
+    );
+    assert_eq!(":opt(section_1)", &raw[actual.span.range()]);
+}
+
+#[test]
+fn test_choose_menu_option() {
+    let raw = r"
+            :menu Title
+            :opt(@bob; #section_1) Section 1
+            :opt() Stay
+
+            #section_1
+            Text in section 1
+        ";
+
+    let dialog = Dialog::from_str(raw).unwrap();
+    let mut state = DialogState::default();
+
+    let menu = match state.current_line(&dialog) {
+        Some(Line::Menu(menu)) => menu,
+        line => panic!("expected menu, got {:?}", line),
+    };
+
+    assert!(!state.choose(menu, 2));
+    assert!(state.choose(menu, 0));
+    assert_eq!(
+        &Section::Named("section_1".to_string()),
+        state.cursor.section()
+    );
+    assert_eq!(
+        Some(Speaker::Character(Alias("bob".to_string()), State::Default)),
+        state.speaker
+    );
+}
@@ -0,0 +1,214 @@
+use std::str::FromStr;
+
+use dlg::{character_requirements, parser::ParseErrorKind, prelude::*};
+use indexmap::indexmap;
+
+fn phrase(text: &str) -> Line {
+    Line::Phrase {
+        speaker: Speaker::Narrator,
+        lines: vec![text.into()],
+        id: None,
+    }
+}
+
+fn play(dialog: &Dialog) -> Vec<String> {
+    let mut state = DialogState::default();
+    let mut played = vec![];
+    while let Some(line) = state.current_line(dialog) {
+        if let Line::Phrase { lines, .. } = line {
+            played.push(lines[0].to_string());
+        }
+        state.cursor.next_line_index();
+    }
+    played
+}
+
+#[test]
+fn test_move_and_back() {
+    let raw = r"
+            Start
+            :move #detour
+
+            :back
+            Back in initial
+
+
+            #detour
+
+            In detour
+            :move #deeper
+
+            Back in detour
+            :back
+
+
+            #deeper
+
+            In deeper
+            :back
+        ";
+
+    let actual = Dialog::from_str(raw).unwrap();
+
+    let expected = Dialog {
+        characters: character_requirements! {},
+        sections: indexmap! {
+            Section::Initial => vec![
+                phrase("Start"),
+                Line::Move(Section::Named("detour".to_string())),
+                Line::Back,
+                phrase("Back in initial"),
+            ],
+            Section::Named("detour".to_string()) => vec![
+                phrase("In detour"),
+                Line::Move(Section::Named("deeper".to_string())),
+                phrase("Back in detour"),
+                Line::Back,
+            ],
+            Section::Named("deeper".to_string()) => vec![
+                phrase("In deeper"),
+                Line::Back,
+            ],
+        },
+        includes: vec![],
+    };
+
+    assert_eq!(expected, actual);
+
+    assert_eq!(
+        vec![
+            "Start",
+            "In detour",
+            "In deeper",
+            "Back in detour",
+            "Back in initial"
+        ],
+        play(&actual)
+    );
+}
+
+#[test]
+fn test_move_from_conditional_block() {
+    let raw = r"
+            :if true
+                :move #detour
+                Back in block
+            :endif
+            End
+
+            #detour
+            In detour
+            :back
+        ";
+
+    let dialog = Dialog::from_str(raw).unwrap();
+
+    assert_eq!(vec!["In detour", "Back in block", "End"], play(&dialog));
+}
+
+#[test]
+fn test_move_without_target() {
+    let raw = r"
+            :move
+            #section
+        ";
+
+    assert_eq!(
+        ParseErrorKind::MissingMoveTarget,
+        Dialog::from_str(raw).unwrap_err().kind
+    );
+}
+
+#[test]
+fn test_sections_order() {
+    let raw = r"
+            Initial
+
+            #zeta
+
+            Zeta
+
+            #alpha
+
+            Alpha
+
+            #middle
+
+            Middle
+        ";
+
+    let dialog = Dialog::from_str(raw).unwrap();
+
+    let sections = dialog
+        .sections
+        .keys()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    assert_eq!(vec!["initial", "#zeta", "#alpha", "#middle"], sections);
+
+    assert_eq!(
+        Some(&Section::Named("alpha".to_string())),
+        dialog.next_section(&Section::Named("zeta".to_string()))
+    );
+    assert_eq!(
+        None,
+        dialog.next_section(&Section::Named("middle".to_string()))
+    );
+}
+
+#[test]
+fn test_fall_through() {
+    let raw = r"
+            Initial
+            :if true
+            In block
+            :endif
+
+            #first
+
+            First
+
+            #second
+
+            Second
+        ";
+
+    let dialog = Dialog::from_str(raw).unwrap();
+    assert_eq!(vec!["Initial", "In block"], play(&dialog));
+
+    let mut state = DialogState {
+        fall_through: true,
+        ..DialogState::default()
+    };
+    let mut played = vec![];
+    while let Some(line) = state.current_line(&dialog) {
+        if let Line::Phrase { lines, .. } = line {
+            played.push(lines[0].to_string());
+        }
+        state.cursor.next_line_index();
+    }
+
+    assert_eq!(vec!["Initial", "In block", "First", "Second"], played);
+}
+
+#[test]
+fn test_move_loop() {
+    let raw = r"
+            Start
+            :move #a
+
+            #a
+            :move #b
+
+            #b
+            :move #a
+        ";
+    let dialog = Dialog::from_str(raw).unwrap();
+    assert_eq!(vec!["Start"], play(&dialog));
+
+    let dialog = Dialog::from_str("#a\n:move #a").unwrap();
+    let mut state = DialogState::default();
+    state.cursor.set_section("a".to_string());
+    assert_eq!(None, state.current_line(&dialog));
+    assert_eq!(0, state.cursor.history().count());
+}
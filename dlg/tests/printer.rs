@@ -0,0 +1,387 @@
+use std::str::FromStr;
+
+use dlg::{
+    parser::{
+        Branch, Comparison, Condition, Effect, FormattedText, Menu, MenuOption, State, Value,
+    },
+    prelude::*,
+};
+use indexmap::IndexMap;
+use proptest::{collection::vec, option, prelude::*};
+
+#[test]
+fn test_canonical_source() {
+    let raw = r#"
+            Alice came in
+            and waved
+            @alice:happy    Hi, Bob!
+            @bob
+            Hi!
+            :if coins >= 10 && !angry
+              @alice Rich: [b]{coins}[/b]
+              :move #walk
+            :else
+              :menu(answer)   Pick
+              :opt( = yes ;#walk ) Yes
+              :opt(=no)
+            :endif
+            #walk
+            @bob Walking
+            :event "play" 1 true
+            :back
+        "#;
+
+    let expected = r#"Alice came in
+and waved
+
+@alice:happy Hi, Bob!
+
+@bob Hi!
+
+:if coins >= 10 && !angry
+    @alice Rich: [b]{coins}[/b]
+
+    :move #walk
+:else
+    :menu(answer) Pick
+    :opt(="yes"; #walk) Yes
+    :opt(="no")
+:endif
+
+#walk
+@bob Walking
+
+:event "play" 1 true
+
+:back
+"#;
+
+    let dialog = Dialog::from_str(raw).unwrap();
+
+    assert_eq!(expected, dialog.to_dlg_string());
+    assert_eq!(expected, dialog.to_string());
+    assert_eq!(dialog, Dialog::from_str(expected).unwrap());
+}
+
+#[test]
+fn test_notes() {
+    let raw = r"
+            // about the start
+            Hello
+            :if visited
+                /* multi
+                line */
+                Again
+            :endif
+            // at the end
+        ";
+
+    let (dialog, notes) = Dialog::parse_with_notes(raw).unwrap();
+
+    assert_eq!(
+        "// about the start\nHello\n\n:if visited\n    /* multi\n                line */\n    Again\n:endif\n// at the end\n",
+        dialog.to_dlg_string_with_notes(&notes)
+    );
+}
+
+#[test]
+fn test_text_with_sigils() {
+    let raw = "@bob Hi\n\n@bob :opt nope\n\n@bob @alice is here\n\n@bob #walk\n";
+    let dialog = Dialog::from_str(raw).unwrap();
+
+    assert_eq!(raw, dialog.to_dlg_string());
+    assert_eq!(dialog, Dialog::from_str(&dialog.to_dlg_string()).unwrap());
+}
+
+#[test]
+fn test_empty_sections() {
+    let raw = "Hi\n\n#middle\n\n#walk\nBye\n\n#end\n";
+    let dialog = Dialog::from_str(raw).unwrap();
+
+    assert_eq!(raw, dialog.to_dlg_string());
+    assert_eq!(
+        vec!["middle", "walk", "end"],
+        dialog
+            .sections
+            .keys()
+            .filter_map(|section| match section {
+                Section::Named(name) => Some(name.as_str()),
+                Section::Initial => None,
+            })
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_strings_without_escapes() {
+    let raw = ":event \"a\\b\" \"two words\"\n\n:menu(disk) Where?\n:opt(=\"c:\\d\") Disk\n";
+    let dialog = Dialog::from_str(raw).unwrap();
+
+    assert_eq!(raw, dialog.to_dlg_string());
+    assert_eq!(dialog, Dialog::from_str(&dialog.to_dlg_string()).unwrap());
+    assert_eq!("\"a\\b\"", Value::from("a\\b").to_string());
+}
+
+#[test]
+fn test_nested_comparisons() {
+    let raw = ":if (a == b) == c\n    Same\n:endif\n\nYou see {!a < (b != c)}\n";
+    let dialog = Dialog::from_str(raw).unwrap();
+
+    assert_eq!(raw, dialog.to_dlg_string());
+    assert_eq!(dialog, Dialog::from_str(&dialog.to_dlg_string()).unwrap());
+}
+
+fn word() -> impl Strategy<Value = String> {
+    "[A-Za-z]{1,8}[,.!?]?"
+}
+
+/// Word that starts a phrase. Words with `:`, `@` and `#` are commands, mentions and sections
+/// at the start of a line, so they are kept in the text only after the mention
+fn first_word() -> impl Strategy<Value = String> {
+    prop_oneof![
+        3 => word(),
+        1 => "[:@#][a-z]{1,5}",
+    ]
+}
+
+fn formatted_text() -> impl Strategy<Value = FormattedText> {
+    text(word())
+}
+
+/// First line of a phrase, which may start with a sigil
+fn first_formatted_text() -> impl Strategy<Value = FormattedText> {
+    text(first_word())
+}
+
+fn text(first: impl Strategy<Value = String>) -> impl Strategy<Value = FormattedText> {
+    let piece = prop_oneof![
+        3 => word(),
+        1 => "v_[a-z]{1,3}".prop_map(|name| format!("{{{}}}", name)),
+        1 => word().prop_map(|word| format!("[b]{}[/b]", word)),
+        1 => (0..1000u32).prop_map(|ms| format!("[wait={}]", ms)),
+    ];
+
+    (first, vec(piece, 0..4)).prop_map(|(first, pieces)| {
+        let mut text = first;
+        for piece in pieces {
+            text.push(' ');
+            text.push_str(&piece);
+        }
+        text.parse().unwrap()
+    })
+}
+
+fn title() -> impl Strategy<Value = String> {
+    vec(word(), 1..4).prop_map(|words| words.join(" "))
+}
+
+fn speaker() -> impl Strategy<Value = Speaker> {
+    prop_oneof![
+        Just(Speaker::Narrator),
+        "[a-z]{1,5}".prop_map(|alias| Speaker::Character(Alias(alias), State::Default)),
+        ("[a-z]{1,5}", "[a-z]{1,5}")
+            .prop_map(|(alias, state)| Speaker::Character(Alias(alias), State::Named(state))),
+    ]
+}
+
+fn value() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        (0..100i64).prop_map(Value::Integer),
+        any::<bool>().prop_map(Value::Bool),
+        r"[a-z\\ ]{1,5}".prop_map(Value::String),
+    ]
+}
+
+fn section() -> impl Strategy<Value = Section> {
+    (0..4usize).prop_map(|index| Section::Named(format!("s_{}", index)))
+}
+
+fn comparison() -> impl Strategy<Value = Comparison> {
+    prop::sample::select(vec![
+        Comparison::Equal,
+        Comparison::NotEqual,
+        Comparison::Less,
+        Comparison::LessOrEqual,
+        Comparison::Greater,
+        Comparison::GreaterOrEqual,
+    ])
+}
+
+fn condition() -> impl Strategy<Value = Condition> {
+    let leaf = prop_oneof![
+        value().prop_map(Condition::Value),
+        "v_[a-c]".prop_map(Condition::Variable),
+    ];
+
+    leaf.prop_recursive(4, 16, 2, |inner| {
+        prop_oneof![
+            inner.clone().prop_map(|c| Condition::Not(Box::new(c))),
+            (inner.clone(), inner.clone())
+                .prop_map(|(a, b)| Condition::And(Box::new(a), Box::new(b))),
+            (inner.clone(), inner.clone())
+                .prop_map(|(a, b)| Condition::Or(Box::new(a), Box::new(b))),
+            (inner.clone(), comparison(), inner).prop_map(|(a, op, b)| Condition::Compare(
+                Box::new(a),
+                op,
+                Box::new(b)
+            )),
+        ]
+    })
+}
+
+/// Placeholder of explicit line ID. Placeholders are replaced with unique IDs by [`number_ids`]
+fn line_id(has_id: bool) -> Option<String> {
+    has_id.then(String::new)
+}
+
+/// Gives every explicit line ID a unique name, because the same ID can't be used twice
+fn number_ids(lines: &mut [Line], count: &mut usize) {
+    for line in lines {
+        match line {
+            Line::Phrase { id, .. } => number_id(id, count),
+            Line::Menu(menu) => {
+                number_id(&mut menu.id, count);
+                for menu_option in &mut menu.options {
+                    number_id(&mut menu_option.id, count);
+                }
+            }
+            Line::Conditional(branches) => {
+                for branch in branches {
+                    number_ids(&mut branch.lines, count);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn number_id(id: &mut Option<String>, count: &mut usize) {
+    if let Some(id) = id {
+        *count += 1;
+        *id = format!("line_{}", count);
+    }
+}
+
+fn menu() -> BoxedStrategy<Line> {
+    (
+        option::of("v_[a-z]{1,3}"),
+        option::of(title()),
+        any::<bool>(),
+    )
+        .prop_flat_map(|(variable, menu_title, has_id)| {
+            let effect = if variable.is_some() {
+                prop_oneof![
+                    section().prop_map(Effect::Jump),
+                    speaker().prop_map(Effect::Speaker),
+                    value().prop_map(Effect::Assign),
+                ]
+                .boxed()
+            } else {
+                prop_oneof![
+                    section().prop_map(Effect::Jump),
+                    speaker().prop_map(Effect::Speaker),
+                ]
+                .boxed()
+            };
+            let menu_option = (option::of(title()), vec(effect, 0..3), any::<bool>()).prop_map(
+                |(title, effects, has_id)| MenuOption {
+                    id: line_id(has_id && title.is_some()),
+                    title,
+                    effects,
+                },
+            );
+
+            vec(menu_option, 0..3).prop_map(move |options| {
+                Line::Menu(Menu {
+                    variable: variable.clone(),
+                    title: menu_title.clone(),
+                    id: line_id(has_id && menu_title.is_some()),
+                    options,
+                })
+            })
+        })
+        .boxed()
+}
+
+fn line() -> impl Strategy<Value = Line> + Clone {
+    let leaf = prop_oneof![
+        4 => (speaker(), first_formatted_text(), vec(formatted_text(), 0..2), any::<bool>())
+            .prop_map(|(speaker, first, mut lines, has_id)| {
+                lines.insert(0, first);
+                Line::Phrase { speaker, lines, id: line_id(has_id) }
+            }),
+        1 => section().prop_map(Line::Move),
+        1 => Just(Line::Back),
+        1 => (r"[a-z\\ ]{1,5}", vec(value(), 0..3)).prop_map(|(name, args)| Line::Event { name, args }),
+    ];
+
+    leaf.prop_recursive(2, 16, 3, |inner| {
+        let branch_lines = lines(inner);
+        (
+            vec((condition(), branch_lines.clone()), 1..3),
+            option::of(branch_lines),
+        )
+            .prop_map(|(branches, otherwise)| {
+                let mut branches: Vec<_> = branches
+                    .into_iter()
+                    .map(|(condition, lines)| Branch {
+                        condition: Some(condition),
+                        lines,
+                    })
+                    .collect();
+                if let Some(lines) = otherwise {
+                    branches.push(Branch {
+                        condition: None,
+                        lines,
+                    });
+                }
+                Line::Conditional(branches)
+            })
+    })
+}
+
+/// Lines of a section or a branch. Menu may be only the last of them
+fn lines(line: impl Strategy<Value = Line> + Clone) -> impl Strategy<Value = Vec<Line>> + Clone {
+    (vec(line, 0..3), option::of(menu())).prop_map(|(mut lines, menu)| {
+        lines.extend(menu);
+        lines
+    })
+}
+
+fn sections() -> impl Strategy<Value = IndexMap<Section, Vec<Line>>> {
+    let initial = lines(line()).prop_filter("section is not empty", |lines| !lines.is_empty());
+
+    (initial, vec(lines(line()), 0..3)).prop_map(|(initial, named)| {
+        let mut count = 0;
+        std::iter::once(initial)
+            .chain(named)
+            .enumerate()
+            .map(|(index, mut lines)| {
+                number_ids(&mut lines, &mut count);
+                match index {
+                    0 => (Section::Initial, lines),
+                    _ => (Section::Named(format!("s_{}", index)), lines),
+                }
+            })
+            .collect()
+    })
+}
+
+proptest! {
+    #[test]
+    fn test_round_trip(sections in sections()) {
+        let dialog = Dialog {
+            characters: Default::default(),
+            sections,
+            includes: vec![],
+        };
+
+        let printed = dialog.to_dlg_string();
+        let parsed = Dialog::from_str(&printed).unwrap();
+        prop_assert_eq!(&dialog.sections, &parsed.sections, "{}", printed);
+
+        let reprinted = parsed.to_dlg_string();
+        prop_assert_eq!(&printed, &reprinted);
+        prop_assert_eq!(parsed, Dialog::from_str(&reprinted).unwrap());
+    }
+}
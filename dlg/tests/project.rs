@@ -0,0 +1,339 @@
+use std::{path::PathBuf, str::FromStr};
+
+use dlg::{
+    character_requirements,
+    parser::{Effect, ParseErrorKind},
+    player::{DialogRunner, Step},
+    prelude::*,
+    project::{DialogProject, MemorySource, ProjectError, ProjectErrorKind},
+};
+
+fn source(files: &[(&str, &str)]) -> MemorySource {
+    let mut source = MemorySource::default();
+    for (path, contents) in files {
+        source.insert(path, *contents);
+    }
+    source
+}
+
+fn named(name: &str) -> Section {
+    Section::Named(name.to_owned())
+}
+
+fn load_error(files: &[(&str, &str)]) -> ProjectError {
+    DialogProject::load(&source(files), "main.dlg").unwrap_err()
+}
+
+#[test]
+fn test_include() {
+    let dialog =
+        Dialog::from_str(":include \"chapters/two.dlg\"\n@alice Hi\n:move #two::intro").unwrap();
+
+    assert_eq!(vec!["chapters/two.dlg".to_owned()], dialog.includes);
+    assert_eq!(
+        Line::Move(named("two::intro")),
+        dialog.sections[&Section::Initial][1]
+    );
+
+    let printed = dialog.to_dlg_string();
+    assert_eq!(
+        ":include \"chapters/two.dlg\"\n\n@alice Hi\n\n:move #two::intro\n",
+        printed
+    );
+    assert_eq!(dialog, Dialog::from_str(&printed).unwrap());
+}
+
+#[test]
+fn test_invalid_include() {
+    let error = Dialog::from_str(":include chapter.dlg").unwrap_err();
+    assert!(matches!(error.kind, ParseErrorKind::InvalidInclude(_)));
+
+    let error = Dialog::from_str(":include\nHi").unwrap_err();
+    assert!(matches!(error.kind, ParseErrorKind::InvalidInclude(_)));
+
+    let error = Dialog::from_str(":if true\n:include \"a.dlg\"\n:endif").unwrap_err();
+    assert_eq!(
+        ParseErrorKind::UnexpectedCommand("include".to_owned()),
+        error.kind
+    );
+}
+
+#[test]
+fn test_project() {
+    let source = source(&[
+        (
+            "main.dlg",
+            r#"
+            :include "chapters/one.dlg"
+            @alice:happy Hello
+            :menu
+            :opt(#one) First chapter
+            :opt(#two::intro) Second chapter
+            "#,
+        ),
+        (
+            "chapters/one.dlg",
+            r#"
+            :include "../chapters/./two.dlg"
+            @bob:sad Chapter one
+            :move #outro
+
+            #outro
+            :move #two
+            "#,
+        ),
+        (
+            "chapters/two.dlg",
+            r#"
+            @alice:angry Chapter two
+
+            #intro
+            :menu
+            :opt(#intro) Again
+            "#,
+        ),
+    ]);
+
+    let project = DialogProject::load(&source, "main.dlg").unwrap();
+
+    assert_eq!(
+        vec![
+            PathBuf::from("main.dlg"),
+            PathBuf::from("chapters/one.dlg"),
+            PathBuf::from("chapters/two.dlg"),
+        ],
+        project.files
+    );
+    assert_eq!(
+        vec![
+            Section::Initial,
+            named("one"),
+            named("one::outro"),
+            named("two"),
+            named("two::intro"),
+        ],
+        project.dialog.sections.keys().cloned().collect::<Vec<_>>()
+    );
+    assert!(project.dialog.includes.is_empty());
+    assert_eq!(
+        character_requirements! {
+            "alice" => ["happy", "angry"],
+            "bob" => ["sad"]
+        },
+        project.dialog.characters
+    );
+
+    // links to sections of the same file are qualified with its name, `#two` goes to another file
+    assert_eq!(
+        Line::Move(named("one::outro")),
+        project.dialog.sections[&named("one")][1]
+    );
+    assert_eq!(
+        Line::Move(named("two")),
+        project.dialog.sections[&named("one::outro")][0]
+    );
+    let menu = match &project.dialog.sections[&named("two::intro")][0] {
+        Line::Menu(menu) => menu,
+        line => panic!("expected menu, got {:?}", line),
+    };
+    assert_eq!(
+        vec![Effect::Jump(named("two::intro"))],
+        menu.options[0].effects
+    );
+
+    let diagnostics = dlg::lint::check(&project.dialog, &Default::default());
+    assert!(diagnostics
+        .iter()
+        .all(|diagnostic| !matches!(diagnostic.lint, dlg::lint::Lint::DanglingLink(_))));
+}
+
+#[test]
+fn test_shared_include() {
+    let project = DialogProject::load(
+        &source(&[
+            ("main.dlg", ":include \"a.dlg\"\n:include \"b.dlg\"\nStart"),
+            ("a.dlg", ":include \"common.dlg\"\nA"),
+            ("b.dlg", ":include \"common.dlg\"\nB"),
+            ("common.dlg", "#shared\nCommon"),
+        ]),
+        "main.dlg",
+    )
+    .unwrap();
+
+    assert_eq!(
+        vec![
+            Section::Initial,
+            named("a"),
+            named("common::shared"),
+            named("b"),
+        ],
+        project.dialog.sections.keys().cloned().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_project_errors() {
+    let error = load_error(&[("main.dlg", "Hi\n:include \"missing.dlg\"")]);
+    assert_eq!(PathBuf::from("main.dlg"), error.path);
+    assert_eq!(2, error.span.unwrap().line);
+    assert!(matches!(
+        error.kind,
+        ProjectErrorKind::Read(ref path, _) if path.as_path() == std::path::Path::new("missing.dlg")
+    ));
+
+    let error = DialogProject::load(&MemorySource::default(), "main.dlg").unwrap_err();
+    assert_eq!(None, error.span);
+    assert!(matches!(error.kind, ProjectErrorKind::Read(..)));
+
+    let error = load_error(&[
+        ("main.dlg", ":include \"a.dlg\""),
+        ("a.dlg", ":include \"b.dlg\""),
+        ("b.dlg", ":include \"a.dlg\""),
+    ]);
+    assert_eq!(PathBuf::from("b.dlg"), error.path);
+    assert_eq!(
+        ProjectErrorKind::IncludeCycle(vec![
+            PathBuf::from("a.dlg"),
+            PathBuf::from("b.dlg"),
+            PathBuf::from("a.dlg"),
+        ]),
+        error.kind
+    );
+    assert_eq!(
+        "b.dlg:1:1: files include each other: `a.dlg` -> `b.dlg` -> `a.dlg`",
+        error.to_string()
+    );
+
+    let error = load_error(&[("main.dlg", ":include \"main.dlg\"")]);
+    assert!(matches!(error.kind, ProjectErrorKind::IncludeCycle(_)));
+
+    let error = load_error(&[
+        ("main.dlg", ":include \"chapter.dlg\"\n\n#chapter\nHi"),
+        ("chapter.dlg", "Chapter"),
+    ]);
+    assert_eq!(PathBuf::from("chapter.dlg"), error.path);
+    assert_eq!(
+        ProjectErrorKind::DuplicateSection(named("chapter")),
+        error.kind
+    );
+
+    let error = load_error(&[
+        (
+            "main.dlg",
+            ":include \"a/intro.dlg\"\n:include \"b/intro.dlg\"",
+        ),
+        ("a/intro.dlg", "A"),
+        ("b/intro.dlg", "B"),
+    ]);
+    assert_eq!(2, error.span.unwrap().line);
+    assert_eq!(
+        ProjectErrorKind::DuplicateNamespace("intro".to_owned()),
+        error.kind
+    );
+
+    let error = load_error(&[
+        ("main.dlg", ":include \"chapter-2.dlg\""),
+        ("chapter-2.dlg", "Hi"),
+    ]);
+    assert_eq!(
+        ProjectErrorKind::InvalidNamespace("chapter-2".to_owned()),
+        error.kind
+    );
+
+    let error = load_error(&[
+        ("main.dlg", ":include \"broken.dlg\""),
+        ("broken.dlg", "Hi\n:opt Bye"),
+    ]);
+    assert_eq!(PathBuf::from("broken.dlg"), error.path);
+    assert_eq!(2, error.span.unwrap().line);
+    assert_eq!(
+        ProjectErrorKind::Parse(ParseErrorKind::OptionWithoutMenu),
+        error.kind
+    );
+}
+
+#[test]
+fn test_lint_of_single_file() {
+    // sections of included files are unknown while the file is checked alone
+    let diagnostics = dlg::lint::lint(":include \"two.dlg\"\n:move #two::intro\n:move #missing");
+
+    assert_eq!(1, diagnostics.len());
+    assert_eq!(
+        dlg::lint::Lint::DanglingLink(named("missing")),
+        diagnostics[0].lint
+    );
+}
+
+#[test]
+fn test_root_links() {
+    let files = source(&[
+        (
+            "main.dlg",
+            ":include \"forest.dlg\"\nStart\n:move #forest\n\n#end\nEnd\n:move #::end",
+        ),
+        (
+            "forest.dlg",
+            "Trees\n:move #end\n\n#end\n:menu\n:opt(#end) Stay\n:opt(#::end) Leave",
+        ),
+    ]);
+    let project = DialogProject::load(&files, "main.dlg").unwrap();
+    let sections = &project.dialog.sections;
+
+    assert_eq!(Line::Move(named("end")), sections[&named("end")][1]);
+    assert_eq!(
+        Line::Move(named("forest::end")),
+        sections[&named("forest")][1]
+    );
+    match &sections[&named("forest::end")][0] {
+        Line::Menu(menu) => assert_eq!(
+            vec![
+                vec![Effect::Jump(named("forest::end"))],
+                vec![Effect::Jump(named("end"))]
+            ],
+            menu.options
+                .iter()
+                .map(|option| option.effects.clone())
+                .collect::<Vec<_>>()
+        ),
+        line => panic!("expected menu, got {:?}", line),
+    }
+    assert!(dlg::lint::check_project(&project, &files).is_empty());
+
+    let printed = Dialog::from_str("Go\n:move #::end")
+        .unwrap()
+        .to_dlg_string();
+    assert_eq!("Go\n\n:move #::end\n", printed);
+}
+
+#[test]
+fn test_fall_through_between_files() {
+    // sections of the project go file after file, so the end of a file falls through into the next one
+    let project = DialogProject::load(
+        &source(&[
+            ("main.dlg", ":include \"forest.dlg\"\nStart"),
+            ("forest.dlg", "Trees"),
+        ]),
+        "main.dlg",
+    )
+    .unwrap();
+
+    let mut runner = DialogRunner::new(&project.dialog);
+    let mut texts = vec![];
+    while let Step::Phrase { text, .. } = runner.current() {
+        texts.push(text);
+        runner.advance();
+    }
+    assert_eq!(vec!["Start".to_owned()], texts);
+
+    let state = DialogState {
+        fall_through: true,
+        ..Default::default()
+    };
+    let mut runner = DialogRunner::with_state(&project.dialog, state);
+    let mut texts = vec![];
+    while let Step::Phrase { text, .. } = runner.current() {
+        texts.push(text);
+        runner.advance();
+    }
+    assert_eq!(vec!["Start".to_owned(), "Trees".to_owned()], texts);
+}
@@ -0,0 +1,107 @@
+use std::str::FromStr;
+
+use dlg::{
+    player::{DialogRunner, Step},
+    prelude::*,
+};
+
+#[test]
+fn test_runner_phrases() {
+    let raw = r"
+            A
+            B
+
+            C
+        ";
+
+    let dialog = Dialog::from_str(raw).unwrap();
+    let mut runner = DialogRunner::new(&dialog);
+
+    let mut played = vec![];
+    loop {
+        match runner.current() {
+            Step::Phrase { text, last, .. } => played.push((text.to_string(), last)),
+            Step::End => break,
+            step => panic!("unexpected step {:?}", step),
+        }
+        runner.advance();
+    }
+
+    assert_eq!(
+        vec![
+            ("A".to_string(), false),
+            ("B".to_string(), true),
+            ("C".to_string(), true),
+        ],
+        played
+    );
+}
+
+#[test]
+fn test_runner_menu() {
+    let raw = r"
+            :menu Title
+            :opt(#first) First
+            :opt(#second) Second
+
+
+            #first
+
+            In first
+
+
+            #second
+
+            In second
+        ";
+
+    let dialog = Dialog::from_str(raw).unwrap();
+    let mut runner = DialogRunner::new(&dialog);
+
+    assert!(matches!(runner.current(), Step::Menu(_)));
+
+    runner.advance();
+    assert!(matches!(runner.current(), Step::Menu(_)));
+
+    assert!(!runner.choose(2));
+    assert!(runner.choose(1));
+    assert!(matches!(
+        runner.current(),
+        Step::Phrase { text, .. } if text == "In second"
+    ));
+    assert!(!runner.choose(0));
+
+    runner.advance();
+    assert_eq!(Step::End, runner.current());
+
+    runner.advance();
+    assert_eq!(Step::End, runner.current());
+}
+
+#[test]
+fn test_runner_with_state() {
+    let raw = r"
+            :if met_alice
+            Hello again
+            :else
+            Nice to meet you
+            :endif
+        ";
+
+    let dialog = Dialog::from_str(raw).unwrap();
+    let mut state = DialogState::default();
+    state.variables.set("met_alice", true);
+    let mut runner = DialogRunner::with_state(&dialog, state);
+
+    assert!(matches!(
+        runner.current(),
+        Step::Phrase { text, .. } if text == "Hello again"
+    ));
+
+    runner.advance();
+    assert_eq!(Step::End, runner.current());
+    assert_eq!(
+        Some(true),
+        runner.into_state().variables.get_bool("met_alice")
+    );
+}
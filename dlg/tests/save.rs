@@ -0,0 +1,100 @@
+use std::str::FromStr;
+
+use dlg::{
+    player::{DialogRunner, SaveData, SaveError, Step, SAVE_VERSION},
+    prelude::*,
+};
+
+const RAW: &str = r"
+            First
+            Second
+
+            :menu(answer) Question
+            :opt(=yes; #end) Yes
+            :opt(=no; #end) No
+
+
+            #end
+
+            The end
+        ";
+
+#[test]
+fn test_save_and_restore() {
+    let dialog = Dialog::from_str(RAW).unwrap();
+    let mut runner = DialogRunner::new(&dialog);
+    runner.advance();
+    runner.advance();
+    assert!(runner.choose(0));
+
+    let save = runner.save();
+    assert_eq!(SAVE_VERSION, save.version);
+
+    let mut restored = DialogRunner::restore(&dialog, save).unwrap();
+    assert_eq!(Some("yes"), restored.state().variables.get_str("answer"));
+    assert!(matches!(
+        restored.current(),
+        Step::Phrase { text, .. } if text == "The end"
+    ));
+
+    restored.advance();
+    assert_eq!(Step::End, restored.current());
+}
+
+#[test]
+fn test_restore_changed_dialog() {
+    let dialog = Dialog::from_str(RAW).unwrap();
+    let save = DialogRunner::new(&dialog).save();
+
+    let changed = Dialog::from_str(&RAW.replace("Second", "Changed")).unwrap();
+    assert_eq!(
+        SaveError::DialogChanged,
+        save.clone().restore(&changed).unwrap_err()
+    );
+
+    let same = Dialog::from_str(RAW).unwrap();
+    assert_eq!(dialog.fingerprint(), same.fingerprint());
+    assert!(save.restore(&same).is_ok());
+}
+
+#[test]
+fn test_restore_unsupported_version() {
+    let dialog = Dialog::from_str(RAW).unwrap();
+    let save = SaveData {
+        version: SAVE_VERSION + 1,
+        ..SaveData::new(&dialog, DialogState::default())
+    };
+
+    assert_eq!(
+        SaveError::UnsupportedVersion(SAVE_VERSION + 1),
+        save.restore(&dialog).unwrap_err()
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_save_serde() {
+    let dialog = Dialog::from_str(RAW).unwrap();
+    let mut runner = DialogRunner::new(&dialog);
+    runner.advance();
+
+    let json = serde_json::to_string(&runner.save()).unwrap();
+    let save: SaveData = serde_json::from_str(&json).unwrap();
+    let mut restored = DialogRunner::restore(&dialog, save).unwrap();
+
+    assert!(matches!(
+        restored.current(),
+        Step::Phrase { text, .. } if text == "Second"
+    ));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_dialog_serde() {
+    let dialog = Dialog::from_str(RAW).unwrap();
+
+    let json = serde_json::to_string(&dialog).unwrap();
+    let restored: Dialog = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(dialog, restored);
+}
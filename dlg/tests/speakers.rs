@@ -0,0 +1,133 @@
+use std::str::FromStr;
+
+#[cfg(feature = "manifest")]
+use dlg::manifest::{Color, Manifest};
+use dlg::{
+    player::{AliasResolver, DialogRunner, SpeakerDisplay, SpeakerResolver, Step},
+    prelude::*,
+};
+
+fn alice(state: State) -> Speaker {
+    Speaker::Character(Alias("alice".to_owned()), state)
+}
+
+fn happy() -> State {
+    State::Named("happy".to_owned())
+}
+
+#[test]
+fn test_alias_resolver() {
+    let name = |speaker: &Speaker| AliasResolver.resolve(speaker).name;
+
+    assert_eq!("Narrator", name(&Speaker::Narrator));
+    assert_eq!("alice", name(&alice(State::Default)));
+    assert_eq!("alice: happy", name(&alice(happy())));
+}
+
+#[cfg(feature = "manifest")]
+#[test]
+fn test_manifest_resolver() {
+    let manifest = Manifest::from_str(
+        r##"
+        [alice]
+        name = "Alice Liddell"
+        color = "#3366ff"
+        states = ["happy", "sad"]
+        portraits = { default = "alice", happy = "alice_smiles" }
+        "##,
+    )
+    .unwrap();
+
+    let color = Some(Color {
+        r: 0x33,
+        g: 0x66,
+        b: 0xff,
+    });
+    assert_eq!(
+        SpeakerDisplay {
+            name: "Alice Liddell".to_owned(),
+            color,
+            portrait: Some("alice_smiles".to_owned()),
+        },
+        manifest.resolve(&alice(happy()))
+    );
+    assert_eq!(
+        Some("alice".to_owned()),
+        manifest
+            .resolve(&alice(State::Named("sad".to_owned())))
+            .portrait
+    );
+
+    let bob = Speaker::Character(Alias("bob".to_owned()), State::Default);
+    assert_eq!(AliasResolver.resolve(&bob), manifest.resolve(&bob));
+    assert_eq!(
+        AliasResolver.resolve(&Speaker::Narrator),
+        manifest.resolve(&Speaker::Narrator)
+    );
+}
+
+#[test]
+fn test_runner_resolver() {
+    let dialog = Dialog::from_str("@alice:happy Hi").unwrap();
+    let mut runner = DialogRunner::new(&dialog);
+
+    let speaker = match runner.current() {
+        Step::Phrase { speaker, .. } => speaker,
+        step => panic!("expected phrase, got {:?}", step),
+    };
+    assert_eq!("alice: happy", runner.speaker_display(&speaker).name);
+
+    runner.set_speaker_resolver(|speaker: &Speaker| SpeakerDisplay {
+        name: format!("{:?}", speaker),
+        color: None,
+        portrait: None,
+    });
+    assert_eq!(
+        format!("{:?}", alice(happy())),
+        runner.speaker_display(&speaker).name
+    );
+}
+
+#[test]
+fn test_option_speaker() {
+    let raw = r"
+            @alice Choose
+            :menu Title
+            :opt(@alice:happy; #next) Go
+
+            #next
+            Said by the option speaker
+            :if true
+                Still the option speaker
+            :endif
+            @bob Hello
+            Said by bob
+            @alice
+            Said by alice
+        ";
+
+    let dialog = Dialog::from_str(raw).unwrap();
+    let mut runner = DialogRunner::new(&dialog);
+    runner.advance();
+    assert!(matches!(runner.current(), Step::Menu(_)));
+    runner.choose(0);
+
+    let bob = Speaker::Character(Alias("bob".to_owned()), State::Default);
+    let mut speakers = vec![];
+    while let Step::Phrase { speaker, .. } = runner.current() {
+        speakers.push(speaker);
+        runner.advance();
+    }
+
+    assert_eq!(
+        vec![
+            alice(happy()),
+            alice(happy()),
+            bob.clone(),
+            bob,
+            alice(State::Default)
+        ],
+        speakers
+    );
+    assert_eq!(None, runner.state().speaker);
+}
@@ -0,0 +1,159 @@
+use std::str::FromStr;
+
+use dlg::{
+    parser::{NodeKind, SyntaxElement, SyntaxKind, SyntaxTree},
+    prelude::*,
+};
+use proptest::prelude::*;
+
+const RAW: &str = "// intro
+@alice Hi, bob@example.com
+  see #faq
+
+:if coins > 1 /* rich */
+    :menu Where?
+    :opt(#walk) Walk
+:else
+    @bob:sad Poor
+:endif
+#walk
+:move #end
+Text without newline";
+
+#[test]
+fn test_lossless() {
+    let tree = SyntaxTree::parse(RAW);
+
+    assert_eq!(RAW, tree.to_string());
+    assert_eq!(Dialog::from_str(RAW).unwrap(), tree.lower().unwrap());
+}
+
+#[test]
+fn test_tokens() {
+    let tree = SyntaxTree::parse(RAW);
+
+    let tokens = tree
+        .tokens()
+        .into_iter()
+        .take(12)
+        .map(|token| (token.kind, token.text.as_str()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        vec![
+            (SyntaxKind::Comment, "// intro"),
+            (SyntaxKind::Newline, "\n"),
+            (SyntaxKind::Mention, "@alice"),
+            (SyntaxKind::Whitespace, " "),
+            (SyntaxKind::Text, "Hi, bob@example.com"),
+            (SyntaxKind::Newline, "\n"),
+            (SyntaxKind::Whitespace, "  "),
+            (SyntaxKind::Text, "see #faq"),
+            (SyntaxKind::Newline, "\n"),
+            (SyntaxKind::Newline, "\n"),
+            (SyntaxKind::Command, ":if"),
+            (SyntaxKind::Whitespace, " "),
+        ],
+        tokens
+    );
+}
+
+#[test]
+fn test_nodes() {
+    let tree = SyntaxTree::parse(RAW);
+
+    let kinds = tree
+        .root
+        .descendants()
+        .into_iter()
+        .map(|node| node.kind)
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        vec![
+            NodeKind::Section,
+            NodeKind::Phrase,
+            NodeKind::Conditional,
+            NodeKind::Branch,
+            NodeKind::Menu,
+            NodeKind::MenuOption,
+            NodeKind::Branch,
+            NodeKind::Phrase,
+            NodeKind::Section,
+            NodeKind::Command,
+            NodeKind::Phrase,
+        ],
+        kinds
+    );
+
+    let sections = tree
+        .root
+        .children
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    assert_eq!(2, sections.len());
+    assert!(sections[1].starts_with("#walk\n"));
+
+    let conditional = tree
+        .root
+        .descendants()
+        .into_iter()
+        .find(|node| node.kind == NodeKind::Conditional)
+        .unwrap();
+    assert!(conditional
+        .to_string()
+        .starts_with(":if coins > 1 /* rich */\n"));
+    assert!(conditional.to_string().ends_with(":endif\n"));
+    assert!(matches!(
+        conditional.children.last(),
+        Some(SyntaxElement::Token(token)) if token.kind == SyntaxKind::Newline
+    ));
+}
+
+#[test]
+fn test_edit() {
+    let mut tree = SyntaxTree::parse(RAW);
+
+    for token in tree.root.tokens_mut() {
+        if token.kind == SyntaxKind::Link && token.text == "#walk" {
+            token.text = "#stroll".to_owned();
+        }
+    }
+
+    // links in arguments of `:opt(#walk)` are a part of the command token
+    assert_eq!(RAW.replace("\n#walk\n", "\n#stroll\n"), tree.to_string());
+
+    let dialog = tree.lower().unwrap();
+    assert!(dialog
+        .sections
+        .contains_key(&Section::Named("stroll".to_owned())));
+}
+
+#[test]
+fn test_unclosed_comment() {
+    let raw = "Text /* unclosed\n@alice";
+    let tree = SyntaxTree::parse(raw);
+
+    assert_eq!(raw, tree.to_string());
+    assert_eq!(
+        Some(SyntaxKind::UnclosedComment),
+        tree.tokens().last().map(|token| token.kind)
+    );
+    assert!(tree.lower().is_err());
+}
+
+proptest! {
+    #[test]
+    fn test_any_source_is_lossless(raw in "[a-z@#:/*{}()\\[\\]=;\" \t\r\n]{0,80}") {
+        let tree = SyntaxTree::parse(&raw);
+
+        prop_assert_eq!(&raw, &tree.to_string());
+        prop_assert_eq!(Dialog::from_str(&raw), tree.lower());
+    }
+
+    #[test]
+    fn test_any_unicode_is_lossless(raw in any::<String>()) {
+        prop_assert_eq!(&raw, &SyntaxTree::parse(&raw).to_string());
+    }
+}
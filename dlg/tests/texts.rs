@@ -0,0 +1,78 @@
+use std::str::FromStr;
+
+use dlg::{character_requirements, prelude::*};
+use indexmap::indexmap;
+
+#[test]
+fn test_lines() {
+    let raw = r"
+            A
+            B
+            C
+            D
+
+            E
+            F
+        ";
+
+    let actual = Dialog::from_str(raw).unwrap();
+
+    let expected = Dialog {
+        characters: character_requirements! {},
+        sections: indexmap! {
+            Section::Initial => vec![
+                Line::Phrase {
+                    speaker: Speaker::Narrator,
+                    lines: vec![
+                        "A".into(),
+                        "B".into(),
+                        "C".into(),
+                        "D".into(),
+                    ],
+                    id: None
+                },
+                Line::Phrase {
+                    speaker: Speaker::Narrator,
+                    lines: vec![
+                        "E".into(),
+                        "F".into(),
+                    ],
+                    id: None
+                },
+            ],
+        },
+        includes: vec![],
+    };
+
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_blank_line_splits_phrase() {
+    let raw = "@alice First\nSecond\n\n   \nThird\n@bob Fourth";
+
+    let actual = Dialog::from_str(raw).unwrap();
+
+    let alice = Speaker::Character(Alias("alice".to_string()), State::Default);
+    let bob = Speaker::Character(Alias("bob".to_string()), State::Default);
+    assert_eq!(
+        vec![
+            Line::Phrase {
+                speaker: alice.clone(),
+                lines: vec!["First".into(), "Second".into()],
+                id: None
+            },
+            Line::Phrase {
+                speaker: alice,
+                lines: vec!["Third".into()],
+                id: None
+            },
+            Line::Phrase {
+                speaker: bob,
+                lines: vec!["Fourth".into()],
+                id: None
+            },
+        ],
+        actual.sections[&Section::Initial]
+    );
+}
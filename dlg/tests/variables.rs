@@ -0,0 +1,100 @@
+use std::str::FromStr;
+
+use dlg::{
+    parser::{ParseErrorKind, Value},
+    player::Variables,
+    prelude::*,
+};
+
+fn choose(dialog: &Dialog, state: &mut DialogState, option_index: usize) {
+    match state.current_line(dialog) {
+        Some(Line::Menu(menu)) => assert!(state.choose(menu, option_index)),
+        line => panic!("expected menu, got {:?}", line),
+    }
+}
+
+#[test]
+fn test_menu_sets_variable() {
+    let raw = r"
+            :menu(mood) How are you?
+            :opt(=happy) Happy
+            :opt(=42) Answer
+            :opt(=false; #sad) Sad
+
+            #sad
+            :if mood == false
+            So sad
+            :endif
+        ";
+
+    let dialog = Dialog::from_str(raw).unwrap();
+
+    let mut state = DialogState::default();
+    choose(&dialog, &mut state, 0);
+    assert_eq!(Some("happy"), state.variables.get_str("mood"));
+
+    let mut state = DialogState::default();
+    choose(&dialog, &mut state, 1);
+    assert_eq!(Some(42), state.variables.get_int("mood"));
+
+    let mut state = DialogState::default();
+    choose(&dialog, &mut state, 2);
+    assert_eq!(Some(false), state.variables.get_bool("mood"));
+    assert!(matches!(
+        state.current_line(&dialog),
+        Some(Line::Phrase { lines, .. }) if lines[0].to_string() == "So sad"
+    ));
+}
+
+#[test]
+fn test_seeded_variables() {
+    let raw = r"
+            :if coins >= 10
+            Rich
+            :else
+            Poor
+            :endif
+        ";
+
+    let dialog = Dialog::from_str(raw).unwrap();
+
+    let mut state = DialogState {
+        variables: [("coins", 10)].into_iter().collect(),
+        ..Default::default()
+    };
+
+    assert!(matches!(
+        state.current_line(&dialog),
+        Some(Line::Phrase { lines, .. }) if lines[0].to_string() == "Rich"
+    ));
+
+    let mut variables = Variables::default();
+    assert_eq!(None, variables.set("name", "Bob"));
+    assert_eq!(
+        Some(Value::String("Bob".to_string())),
+        variables.set("name", 1)
+    );
+    assert_eq!(None, variables.get_str("name"));
+    assert_eq!(Some(Value::Integer(1)), variables.remove("name"));
+}
+
+#[test]
+fn test_menu_variable_errors() {
+    let raw = r"
+            :menu Title
+            :opt(=value) Option
+        ";
+    assert_eq!(
+        ParseErrorKind::AssignWithoutVariable,
+        Dialog::from_str(raw).unwrap_err().kind
+    );
+
+    let raw = r"
+            :menu(not a name) Title
+            :opt(=value) Option
+        ";
+    assert_eq!(
+        ParseErrorKind::InvalidVariable("not a name".to_string()),
+        Dialog::from_str(raw).unwrap_err().kind
+    );
+}
@@ -1,7 +1,8 @@
 use crossterm::event::KeyModifiers;
-use dlg::parser::Menu;
+use dlg::manifest::Manifest;
+use dlg::parser::{Fragment, Style as TextStyle, TextEffect};
+use dlg::player::{DialogRunner, SpeakerDisplay, Step};
 use dlg::prelude::*;
-use dlg::{parser::State, player::DialogState};
 use io::Error as IoError;
 use unicode_segmentation::UnicodeSegmentation;
 
@@ -27,27 +28,45 @@ use tui::{
 
 #[derive(Debug)]
 enum InputMode {
-    NextPhrase,
-    NextLine,
-    Menu { selection: usize, menu: Menu },
+    Next,
+    Menu { selection: usize },
 }
 
 #[derive(Debug)]
 enum ViewMode {
-    Animation(f64),
+    Animation,
     Input(InputMode),
     End,
 }
 
 /// Holds the state of the application
 #[derive(Debug)]
-struct App {
+struct App<'a> {
     view_mode: ViewMode,
-    dialog: Dialog,
-    state: DialogState,
+    runner: DialogRunner<'a>,
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
+    let mut file = None;
+    let mut manifest = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--manifest" => {
+                let path = args.next().ok_or("`--manifest` needs a value")?;
+                manifest = Some(load_manifest(Path::new(&path))?);
+            }
+            _ if file.is_none() => file = Some(arg),
+            _ => return Err(format!("unexpected argument `{}`", arg).into()),
+        }
+    }
+
+    // load dialog before the terminal setup so errors are printed to the normal screen
+    let dialog = match file {
+        Some(file) => Some(load_dialog(Path::new(&file))?),
+        None => None,
+    };
+
     // setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -55,30 +74,24 @@ fn main() -> Result<(), Box<dyn Error>> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let args: Vec<String> = std::env::args().collect();
-
-    let path = args.get(1);
+    let res = match dialog {
+        Some(dialog) => Ok(Some(dialog)),
+        None => run_select_file_menu(&mut terminal),
+    }
+    .and_then(|dialog| match dialog {
+        Some(dialog) => {
+            let mut app = App {
+                view_mode: ViewMode::Animation,
+                runner: DialogRunner::new(&dialog),
+            };
+            if let Some(manifest) = manifest {
+                app.runner.set_speaker_resolver(manifest);
+            }
 
-    let dialog = if let Some(file) = path {
-        let file = PathBuf::from(file);
-        if file.exists() {
-            let raw = fs::read_to_string(file).expect("error in reading file");
-            let raw = format!("\n{}\n", raw); // FIXME: improve parsing of mentions in start of file
-            Dialog::from_str(&raw).expect("can't load dialog from file")
-        } else {
-            panic!("file is not exists");
+            run_dialog(&mut terminal, app)
         }
-    } else {
-        run_select_file_menu(&mut terminal).unwrap().unwrap()
-    };
-
-    let app = App {
-        view_mode: ViewMode::Animation(0.0),
-        dialog,
-        state: DialogState::default(),
-    };
-
-    let res = run_dialog(&mut terminal, app);
+        None => Ok(()),
+    });
 
     // restore terminal
     disable_raw_mode()?;
@@ -96,6 +109,20 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+fn load_dialog(path: &Path) -> io::Result<Dialog> {
+    let raw = fs::read_to_string(path)?;
+
+    Dialog::from_str(&raw)
+        .map_err(|e| IoError::new(ErrorKind::InvalidData, format!("{}:{}", path.display(), e)))
+}
+
+fn load_manifest(path: &Path) -> io::Result<Manifest> {
+    let raw = fs::read_to_string(path)?;
+
+    Manifest::from_str(&raw)
+        .map_err(|e| IoError::new(ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+}
+
 fn find_all_dialog_files<P: AsRef<Path>>(path: P) -> Vec<PathBuf> {
     let mut files = vec![];
 
@@ -138,17 +165,7 @@ fn run_select_file_menu<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<Op
 
             if let KeyCode::Enter = key.code {
                 if let Some(path) = files.get(selection) {
-                    let raw = fs::read_to_string(path).expect("can't read the file");
-                    let raw = format!("\n{}\n", raw); // TODO: fix mention in beginning of file
-
-                    let dialog = Dialog::from_str(&raw);
-
-                    match dialog {
-                        Ok(dialog) => return Ok(Some(dialog)),
-                        Err(_) => {
-                            return Err(IoError::new(ErrorKind::Other, "сan't parse dialog!"))
-                        }
-                    }
+                    return load_dialog(path).map(Some);
                 }
                 return Err(IoError::new(ErrorKind::NotFound, "file not found!"));
             }
@@ -220,8 +237,6 @@ fn run_dialog<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Resul
             .unwrap_or_else(|| Duration::from_secs(0));
         if crossterm::event::poll(timeout)? {
             if let Event::Key(key) = event::read()? {
-                let cursor = &mut app.state.cursor;
-
                 if let KeyCode::Esc = key.code {
                     return Ok(());
                 } else if key.modifiers.contains(KeyModifiers::CONTROL) {
@@ -231,60 +246,44 @@ fn run_dialog<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Resul
                 };
 
                 match &mut app.view_mode {
-                    ViewMode::Input(input) => {
-                        match input {
-                            InputMode::NextLine => {
-                                if let KeyCode::Enter = key.code {
-                                    cursor.next_line_index();
-                                    app.view_mode = ViewMode::Animation(0.);
-                                };
-                            }
-                            InputMode::NextPhrase => {
-                                if let KeyCode::Enter = key.code {
-                                    cursor.next_phrase_index();
-                                    app.view_mode = ViewMode::Animation(0.);
-                                };
-                            }
-                            InputMode::Menu { selection, menu } => {
-                                if let KeyCode::Enter = key.code {
-                                    let opt = menu.options.get(*selection);
-
-                                    if let Some(opt) = opt {
-                                        let section = &opt.args[1..]; // FIXME: remove hash from link
-                                        let _ = &app.state.cursor.set_section(section.to_string());
-                                        app.view_mode = ViewMode::Animation(0.);
-                                    } else {
-                                        panic!(
-                                            "Out of bounds: index `{}`, len `{}`",
-                                            *selection,
-                                            menu.options.len()
-                                        )
-                                    }
-                                    continue;
+                    ViewMode::Input(input) => match input {
+                        InputMode::Next => {
+                            if let KeyCode::Enter = key.code {
+                                app.runner.advance();
+                                app.view_mode = ViewMode::Animation;
+                            };
+                        }
+                        InputMode::Menu { selection } => {
+                            if let KeyCode::Enter = key.code {
+                                if app.runner.choose(*selection) {
+                                    app.view_mode = ViewMode::Animation;
                                 }
+                                continue;
+                            }
 
-                                let raw_selection = match key.code {
-                                    KeyCode::Up => *selection as isize - 1isize,
-                                    KeyCode::Down => *selection as isize + 1isize,
-                                    _ => *selection as isize,
-                                };
-
-                                let len = menu.options.len();
-                                let rem = raw_selection % len as isize;
-                                let new_selection =
-                                    (rem + (len * usize::from(rem < 0)) as isize) as usize;
-
-                                *input = InputMode::Menu {
-                                    selection: new_selection,
-                                    menu: menu.clone(),
-                                };
+                            let len = match app.runner.current() {
+                                Step::Menu(menu) => menu.options.len(),
+                                _ => continue,
+                            };
+                            if len == 0 {
+                                continue;
                             }
+
+                            let raw_selection = match key.code {
+                                KeyCode::Up => *selection as isize - 1isize,
+                                KeyCode::Down => *selection as isize + 1isize,
+                                _ => *selection as isize,
+                            };
+
+                            let rem = raw_selection % len as isize;
+                            *selection = (rem + (len * usize::from(rem < 0)) as isize) as usize;
                         }
-                    }
+                    },
                     ViewMode::End => return Ok(()),
-                    ViewMode::Animation(progress) => {
+                    ViewMode::Animation => {
                         if let KeyCode::Enter = key.code {
-                            *progress = 1.;
+                            let animation = &mut app.runner.state_mut().animation;
+                            animation.current = animation.target;
                         };
                     }
                 }
@@ -293,15 +292,18 @@ fn run_dialog<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Resul
 
         if last_tick.elapsed() >= tick_rate {
             last_tick = Instant::now();
-            if let ViewMode::Animation(progress) = &mut app.view_mode {
-                *progress += 0.05;
+            if let ViewMode::Animation = app.view_mode {
+                if let Step::Phrase { fragments, .. } = app.runner.current() {
+                    let tick_ms = tick_rate.as_millis() as u32;
+                    app.runner.state_mut().animation.tick(&fragments, tick_ms);
+                }
             }
         }
     }
 }
 
 fn dialog_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) -> ViewMode {
-    let cursor = &app.state.cursor;
+    let step = app.runner.current();
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -316,10 +318,9 @@ fn dialog_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) -> ViewMode {
         )
         .split(f.size());
 
-    let mut animation_progress = 1.;
     let (msg, style) = match &app.view_mode {
         ViewMode::Input(input) => match input {
-            InputMode::NextPhrase | InputMode::NextLine => (
+            InputMode::Next => (
                 vec![
                     Span::raw("Press "),
                     Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
@@ -336,17 +337,14 @@ fn dialog_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) -> ViewMode {
                 Style::default(),
             ),
         },
-        ViewMode::Animation(progress) => {
-            animation_progress = *progress;
-            (
-                vec![
-                    Span::raw("Press "),
-                    Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
-                    Span::raw(" to skip animation."),
-                ],
-                Style::default(),
-            )
-        }
+        ViewMode::Animation => (
+            vec![
+                Span::raw("Press "),
+                Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to skip animation."),
+            ],
+            Style::default(),
+        ),
         ViewMode::End => return ViewMode::End,
     };
 
@@ -355,63 +353,45 @@ fn dialog_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) -> ViewMode {
     let help_message = Paragraph::new(text);
     f.render_widget(help_message, chunks[0]);
 
-    let input = Paragraph::new(format!("Cursor: {}", &app.state.cursor))
+    let input = Paragraph::new(format!("Cursor: {}", &app.runner.state().cursor))
         .style(Style::default().fg(Color::Yellow))
         .block(Block::default().borders(Borders::ALL).title("State"));
     f.render_widget(input, chunks[1]);
 
-    // TODO: Provide line from outside
-    let line = match app.dialog.get_line_by_cursor(cursor) {
-        Some(line) => line,
-        None => return ViewMode::End,
-    };
-
-    match line {
-        Line::Phrase { speaker, lines } => {
-            let name = match speaker {
-                Speaker::Narrator => String::from("Narrator"),
-                Speaker::Character(alias, state) => match state {
-                    State::Default => alias.0.to_string(),
-                    State::Named(state) => format!("{}: {}", alias.0, state),
-                },
-            };
+    match step {
+        Step::Phrase {
+            speaker,
+            text,
+            fragments,
+            ..
+        } => {
+            let display = app.runner.speaker_display(&speaker);
+            let name = Span::styled(display.name.clone(), speaker_style(&display));
+
+            let animation = &mut app.runner.state_mut().animation;
+            animation.target = text.graphemes(true).count();
+            let line_part = styled_spans(&fragments, animation.current);
+
+            let messages =
+                Paragraph::new(line_part).block(Block::default().borders(Borders::ALL).title(name));
+            f.render_widget(messages, chunks[2]);
 
-            if let Some(line) = lines.get(cursor.phrase_index()) {
-                let progress = f64::min(animation_progress, 1.0);
-                let letters = line.graphemes(true).collect::<Vec<_>>();
-                let len = (letters.len() as f64 * progress) as usize;
-                let line_part = &letters[0..len];
-
-                let messages = Paragraph::new(line_part.join(""))
-                    .block(Block::default().borders(Borders::ALL).title(name));
-                f.render_widget(messages, chunks[2]);
-
-                if animation_progress < 1. {
-                    ViewMode::Animation(animation_progress)
-                } else if cursor.phrase_index() == lines.len() - 1 {
-                    ViewMode::Input(InputMode::NextLine)
-                } else {
-                    ViewMode::Input(InputMode::NextPhrase)
-                }
+            if animation.current < animation.target {
+                ViewMode::Animation
             } else {
-                panic!("no line");
-                // let messages = Paragraph::new("А где")
-                //     .block(Block::default().borders(Borders::ALL).title(name));
-                // f.render_widget(messages, chunks[2]);
-
-                // Some(InputMode::NextLine)
+                ViewMode::Input(InputMode::Next)
             }
         }
-        Line::Menu(menu) => {
+        Step::Menu(menu) => {
             let current_selection = match &app.view_mode {
-                ViewMode::Input(InputMode::Menu { selection, .. }) => *selection,
+                ViewMode::Input(InputMode::Menu { selection }) => *selection,
                 _ => 0,
             };
 
             let mut items = menu
                 .options
                 .iter()
-                .map(|o| Span::raw(o.title.clone().unwrap()))
+                .map(|o| Span::raw(o.title.clone().unwrap_or_default()))
                 .collect::<Vec<_>>();
 
             if let Some(item) = items.get_mut(current_selection) {
@@ -422,14 +402,91 @@ fn dialog_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) -> ViewMode {
                 .block(
                     Block::default()
                         .borders(Borders::ALL)
-                        .title(menu.title.clone().unwrap()),
+                        .title(menu.title.clone().unwrap_or_default()),
                 );
             f.render_widget(messages, chunks[2]);
 
             ViewMode::Input(InputMode::Menu {
                 selection: current_selection,
-                menu: menu.clone(),
             })
         }
+        Step::Event { .. } => {
+            app.runner.advance();
+
+            ViewMode::Animation
+        }
+        Step::End => ViewMode::End,
+    }
+}
+
+/// Converts the first `count` graphemes of the phrase into styled spans of terminal
+fn styled_spans(fragments: &[Fragment], count: usize) -> Spans<'static> {
+    let mut spans = vec![];
+    let mut left = count;
+
+    for fragment in fragments {
+        let (text, style) = match fragment {
+            Fragment::Text(text, style) => (text, style),
+            Fragment::Wait(_) => continue,
+        };
+        if left == 0 {
+            break;
+        }
+
+        let letters = text.graphemes(true).take(left).collect::<String>();
+        left -= letters.graphemes(true).count();
+        spans.push(Span::styled(letters, terminal_style(style)));
+    }
+
+    Spans::from(spans)
+}
+
+/// Speaker name is shown in the color from the manifest
+fn speaker_style(display: &SpeakerDisplay) -> Style {
+    match display.color {
+        Some(color) => Style::default().fg(Color::Rgb(color.r, color.g, color.b)),
+        None => Style::default(),
+    }
+}
+
+fn terminal_style(style: &TextStyle) -> Style {
+    let mut result = Style::default();
+    if style.bold {
+        result = result.add_modifier(Modifier::BOLD);
+    }
+    if style.italic {
+        result = result.add_modifier(Modifier::ITALIC);
+    }
+    // terminal can't move letters, so effects are shown with blinking
+    match style.effect {
+        Some(TextEffect::Shake) => result = result.add_modifier(Modifier::RAPID_BLINK),
+        Some(TextEffect::Wave) => result = result.add_modifier(Modifier::SLOW_BLINK),
+        None => {}
+    }
+    if let Some(color) = style.color.as_deref().and_then(terminal_color) {
+        result = result.fg(color);
+    }
+    result
+}
+
+fn terminal_color(color: &str) -> Option<Color> {
+    if let Some(hex) = color.strip_prefix('#') {
+        let rgb = u32::from_str_radix(hex, 16)
+            .ok()
+            .filter(|_| hex.len() == 6)?;
+        return Some(Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8));
+    }
+
+    match color {
+        "black" => Some(Color::Black),
+        "red" => Some(Color::Red),
+        "green" => Some(Color::Green),
+        "yellow" => Some(Color::Yellow),
+        "blue" => Some(Color::Blue),
+        "magenta" => Some(Color::Magenta),
+        "cyan" => Some(Color::Cyan),
+        "gray" => Some(Color::Gray),
+        "white" => Some(Color::White),
+        _ => None,
     }
 }